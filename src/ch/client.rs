@@ -1,12 +1,27 @@
 use anyhow::{Context, Result};
+use clickhouse::query::Query;
 use clickhouse::Client;
 use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 /// ClickHouse HTTP client wrapper
 #[derive(Clone)]
 pub struct ChClient {
     client: Client,
     endpoint: String,
+    stats: Arc<Mutex<QueryStats>>,
+}
+
+/// Statistics for the queries executed through a client
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryStats {
+    /// Server-side `query_id` of each executed query
+    pub query_ids: Vec<String>,
+    /// Total rows returned
+    pub row_count: u64,
+    /// Total wall-clock time spent waiting on queries
+    pub duration_ms: u64,
 }
 
 impl ChClient {
@@ -21,9 +36,26 @@ impl ChClient {
         Self {
             client,
             endpoint: endpoint.to_string(),
+            stats: Arc::default(),
+        }
+    }
+
+    /// Create a client sharing this connection but with its own query stats.
+    ///
+    /// Clones share stats; use this to measure the queries of a single collector.
+    pub fn tracked(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            endpoint: self.endpoint.clone(),
+            stats: Arc::default(),
         }
     }
 
+    /// Get statistics for all queries executed so far
+    pub fn stats(&self) -> QueryStats {
+        self.stats.lock().unwrap().clone()
+    }
+
     /// Test connection with SELECT 1
     pub async fn ping(&self) -> Result<()> {
         #[derive(Debug, Deserialize, clickhouse::Row)]
@@ -52,12 +84,13 @@ impl ChClient {
     where
         T: clickhouse::Row + for<'a> Deserialize<'a>,
     {
-        let rows = self
-            .client
-            .query(sql)
+        let (query, query_id) = self.query(sql);
+        let started = Instant::now();
+        let rows = query
             .fetch_all()
             .await
             .with_context(|| format!("Failed to execute query: {}", sql))?;
+        self.record(query_id, rows.len(), started);
 
         Ok(rows)
     }
@@ -67,12 +100,13 @@ impl ChClient {
     where
         T: clickhouse::Row + for<'a> Deserialize<'a>,
     {
-        let row = self
-            .client
-            .query(sql)
+        let (query, query_id) = self.query(sql);
+        let started = Instant::now();
+        let row = query
             .fetch_one()
             .await
             .with_context(|| format!("Failed to execute query: {}", sql))?;
+        self.record(query_id, 1, started);
 
         Ok(row)
     }
@@ -82,16 +116,37 @@ impl ChClient {
     where
         T: clickhouse::Row + for<'a> Deserialize<'a>,
     {
-        let row = self
-            .client
-            .query(sql)
+        let (query, query_id) = self.query(sql);
+        let started = Instant::now();
+        let row = query
             .fetch_optional()
             .await
             .with_context(|| format!("Failed to execute query: {}", sql))?;
+        self.record(query_id, usize::from(row.is_some()), started);
 
         Ok(row)
     }
 
+    /// Build a query tagged with a fresh server-side `query_id`.
+    ///
+    /// `sql` is sent verbatim: `?` is escaped so the driver does not treat it
+    /// as a bind placeholder (values are escaped with `ch::sql` instead).
+    pub(crate) fn query(&self, sql: &str) -> (Query, String) {
+        let query_id = uuid::Uuid::new_v4().to_string();
+        let query = self
+            .client
+            .query(&sql.replace('?', "??"))
+            .with_option("query_id", query_id.as_str());
+        (query, query_id)
+    }
+
+    fn record(&self, query_id: String, rows: usize, started: Instant) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.query_ids.push(query_id);
+        stats.row_count += rows as u64;
+        stats.duration_ms += started.elapsed().as_millis() as u64;
+    }
+
     /// Get the endpoint URL
     pub fn endpoint(&self) -> &str {
         &self.endpoint
@@ -108,6 +163,23 @@ mod tests {
         assert_eq!(client.endpoint(), "http://localhost:8123");
     }
 
+    #[test]
+    fn test_tracked_client_has_own_stats() {
+        let client = ChClient::new("http://localhost:8123", "default", "", "default");
+        client.record("q1".to_string(), 10, Instant::now());
+
+        let tracked = client.tracked();
+        assert_eq!(tracked.stats(), QueryStats::default());
+
+        tracked.record("q2".to_string(), 3, Instant::now());
+        tracked.clone().record("q3".to_string(), 2, Instant::now());
+
+        let stats = tracked.stats();
+        assert_eq!(stats.query_ids, vec!["q2", "q3"]);
+        assert_eq!(stats.row_count, 5);
+        assert_eq!(client.stats().row_count, 10);
+    }
+
     #[test]
     fn test_client_clone() {
         let client = ChClient::new("http://localhost:8123", "default", "", "default");
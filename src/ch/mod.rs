@@ -1,3 +1,4 @@
 mod client;
+pub mod sql;
 
-pub use client::ChClient;
+pub use client::{ChClient, QueryStats};
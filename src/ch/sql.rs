@@ -0,0 +1,99 @@
+//! Escaping for values spliced into SQL text.
+//!
+//! Every database, table or user-supplied string placed into a query or a
+//! generated `Action.sql` goes through these helpers, so names containing
+//! quotes or backslashes cannot break out of their literal.
+
+/// Quote a string literal: `it's` becomes `'it\'s'`
+pub fn literal(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('\'');
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '\'' => quoted.push_str("\\'"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '\0' => quoted.push_str("\\0"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/// Comma-separated string literals, e.g. for `IN (...)` or `[...]`
+pub fn literal_list<S: AsRef<str>>(values: &[S]) -> String {
+    values
+        .iter()
+        .map(|v| literal(v.as_ref()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Quote an identifier with backticks unless it is a plain word
+pub fn identifier(name: &str) -> String {
+    if is_plain_identifier(name) {
+        return name.to_string();
+    }
+
+    let mut quoted = String::with_capacity(name.len() + 2);
+    quoted.push('`');
+    for c in name.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '`' => quoted.push_str("\\`"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('`');
+    quoted
+}
+
+/// Qualified table name for DDL and queries: `database.table`
+pub fn table_name(database: &str, table: &str) -> String {
+    format!("{}.{}", identifier(database), identifier(table))
+}
+
+fn is_plain_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literal_escapes_quotes_and_backslashes() {
+        assert_eq!(literal("events"), "'events'");
+        assert_eq!(literal("it's"), r"'it\'s'");
+        assert_eq!(literal(r"a\b"), r"'a\\b'");
+        assert_eq!(literal(r"x\' OR 1=1 --"), r"'x\\\' OR 1=1 --'");
+        assert_eq!(literal("a\nb"), r"'a\nb'");
+    }
+
+    #[test]
+    fn test_literal_list() {
+        assert_eq!(literal_list(&["a", "b'c"]), r"'a', 'b\'c'");
+        assert_eq!(literal_list::<&str>(&[]), "");
+    }
+
+    #[test]
+    fn test_identifier_quotes_only_when_needed() {
+        assert_eq!(identifier("events_2024"), "events_2024");
+        assert_eq!(identifier("_tmp"), "_tmp");
+        assert_eq!(identifier("2024_events"), "`2024_events`");
+        assert_eq!(identifier("my-table"), "`my-table`");
+        assert_eq!(identifier("a`b"), r"`a\`b`");
+        assert_eq!(identifier(""), "``");
+    }
+
+    #[test]
+    fn test_table_name() {
+        assert_eq!(table_name("analytics", "events"), "analytics.events");
+        assert_eq!(table_name("raw-data", "events"), "`raw-data`.events");
+    }
+}
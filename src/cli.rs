@@ -1,43 +1,79 @@
-use clap::{Parser, Subcommand};
+use crate::collectors::DEFAULT_QUERY_LIMIT;
+use crate::report::{Report, ReportStatus};
+use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
+/// Process exit codes
+pub mod exit_code {
+    /// Audit completed and no findings reached the `--fail-on` threshold
+    pub const SUCCESS: u8 = 0;
+    /// Audit completed with findings at or above the `--fail-on` threshold
+    pub const FINDINGS: u8 = 1;
+    /// Invalid command line (reported by clap)
+    pub const USAGE: u8 = 2;
+    /// Audit could not complete (connection or I/O error)
+    pub const ERROR: u8 = 3;
+    /// Audit completed but one or more collectors failed; the report is partial
+    pub const COLLECTION_FAILED: u8 = 4;
+}
+
+const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0  Audit completed, no findings at or above --fail-on
+  1  Audit completed with findings at or above --fail-on
+  2  Invalid command line
+  3  Audit failed (connection or I/O error)
+  4  Report written, but one or more collectors failed (takes precedence over 1)";
+
 #[derive(Parser, Debug)]
 #[command(name = "pipeaudit")]
 #[command(about = "Audit tool for ClickHouse data pipelines")]
 #[command(version)]
+#[command(after_help = EXIT_CODES_HELP)]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
 }
 
 #[derive(Subcommand, Debug)]
+#[allow(clippy::large_enum_variant)] // parsed once per process
 pub enum Commands {
     /// Run audit on ClickHouse tables
     Audit(AuditArgs),
+    /// Compare two audit reports
+    Diff(DiffArgs),
 }
 
 #[derive(Parser, Debug)]
 pub struct AuditArgs {
     /// ClickHouse HTTP endpoint (e.g., http://localhost:8123)
     #[arg(long, env = "CLICKHOUSE_ENDPOINT")]
-    pub endpoint: String,
+    pub endpoint: Option<String>,
 
-    /// ClickHouse user
-    #[arg(long, env = "CLICKHOUSE_USER", default_value = "default")]
-    pub user: String,
+    /// ClickHouse user [default: default]
+    #[arg(long, env = "CLICKHOUSE_USER")]
+    pub user: Option<String>,
 
     /// ClickHouse password
-    #[arg(long, env = "CLICKHOUSE_PASSWORD", default_value = "")]
-    pub password: String,
+    #[arg(long, env = "CLICKHOUSE_PASSWORD")]
+    pub password: Option<String>,
 
-    /// Database to audit
-    #[arg(long, short)]
-    pub db: String,
+    /// Databases to audit (comma-separated names or globs, e.g. analytics,raw or '*')
+    #[arg(long, short, value_delimiter = ',')]
+    pub db: Vec<String>,
 
-    /// Tables to audit (comma-separated)
+    /// Tables to audit (comma-separated); discovered from system.tables if omitted
     #[arg(long, short, value_delimiter = ',')]
     pub tables: Vec<String>,
 
+    /// Only audit tables matching these patterns (globs, or regexes prefixed with re:)
+    #[arg(long, value_delimiter = ',')]
+    pub include: Vec<String>,
+
+    /// Skip tables matching these patterns (globs, or regexes prefixed with re:)
+    #[arg(long, value_delimiter = ',')]
+    pub exclude: Vec<String>,
+
     /// Output file path for JSON report
     #[arg(long, short)]
     pub out: PathBuf,
@@ -45,11 +81,106 @@ pub struct AuditArgs {
     /// SQL file for EXPLAIN analysis (optional)
     #[arg(long)]
     pub sql_file: Option<PathBuf>,
+
+    /// TOML config file for targets, thresholds and rule settings
+    #[arg(long, short)]
+    pub config: Option<PathBuf>,
+
+    /// Exit with code 1 if any finding is at or above this severity
+    #[arg(long, value_enum)]
+    pub fail_on: Option<FailOn>,
+
+    /// Run only these collectors (comma-separated, e.g. parts,disk)
+    #[arg(long, value_delimiter = ',')]
+    pub only: Vec<String>,
+
+    /// Skip these collectors (comma-separated, e.g. query_log)
+    #[arg(long, value_delimiter = ',')]
+    pub skip: Vec<String>,
+
+    /// How far back to read system.query_log and system.part_log (e.g. 6h, 7d); the window before it is the baseline for regressions
+    #[arg(long, value_parser = parse_window, default_value = "7d")]
+    pub query_window: u64,
+
+    /// Number of query fingerprints (by rows read) and failure groups (by failures) to report
+    #[arg(long, default_value_t = DEFAULT_QUERY_LIMIT)]
+    pub query_limit: usize,
+
+    /// Estimate String column cardinality with SAMPLE on tables that have a sampling key (reads table data)
+    #[arg(long)]
+    pub sample_columns: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct DiffArgs {
+    /// Older report (e.g., yesterday's run)
+    pub old: PathBuf,
+
+    /// Newer report
+    pub new: PathBuf,
+
+    /// Output file path for JSON diff (optional)
+    #[arg(long, short)]
+    pub out: Option<PathBuf>,
+}
+
+/// Severity threshold for a failing exit code
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailOn {
+    Warning,
+    Critical,
+}
+
+impl FailOn {
+    /// Whether a report with this status should fail the run
+    pub fn is_triggered_by(self, status: &ReportStatus) -> bool {
+        match self {
+            FailOn::Warning => *status != ReportStatus::Healthy,
+            FailOn::Critical => *status == ReportStatus::Critical,
+        }
+    }
+}
+
+/// Parse a window such as 30m, 6h or 7d (plain numbers are seconds) into seconds
+fn parse_window(value: &str) -> Result<u64, String> {
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => value.split_at(i),
+        None => (value, "s"),
+    };
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return Err(format!("unknown unit {:?}: use s, m, h or d", unit)),
+    };
+    match number.parse::<u64>() {
+        Ok(n) if n > 0 => Ok(n * multiplier),
+        _ => Err(format!(
+            "expected a positive duration such as 6h or 7d, got {:?}",
+            value
+        )),
+    }
+}
+
+/// Exit code for a finished audit
+///
+/// A partial report cannot vouch for the absence of findings, so failed
+/// collectors take precedence over the `--fail-on` gate.
+pub fn audit_exit_code(report: &Report, fail_on: Option<FailOn>) -> u8 {
+    if report.collection.failed().next().is_some() {
+        exit_code::COLLECTION_FAILED
+    } else if fail_on.is_some_and(|f| f.is_triggered_by(&report.summary.status)) {
+        exit_code::FINDINGS
+    } else {
+        exit_code::SUCCESS
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::report::{CollectionStatus, CollectorStatus, Targets};
 
     #[test]
     fn test_cli_parse_audit_command() {
@@ -68,13 +199,15 @@ mod tests {
 
         match cli.command {
             Commands::Audit(args) => {
-                assert_eq!(args.endpoint, "http://localhost:8123");
-                assert_eq!(args.db, "testdb");
+                assert_eq!(args.endpoint.as_deref(), Some("http://localhost:8123"));
+                assert_eq!(args.db, vec!["testdb"]);
                 assert_eq!(args.tables, vec!["events", "users"]);
                 assert_eq!(args.out, PathBuf::from("report.json"));
-                assert_eq!(args.user, "default");
-                assert_eq!(args.password, "");
+                assert_eq!(args.user, None);
+                assert_eq!(args.password, None);
+                assert_eq!(args.config, None);
             }
+            _ => panic!("expected audit command"),
         }
     }
 
@@ -97,6 +230,7 @@ mod tests {
             Commands::Audit(args) => {
                 assert_eq!(args.tables, vec!["events"]);
             }
+            _ => panic!("expected audit command"),
         }
     }
 
@@ -121,9 +255,10 @@ mod tests {
 
         match cli.command {
             Commands::Audit(args) => {
-                assert_eq!(args.user, "admin");
-                assert_eq!(args.password, "secret");
+                assert_eq!(args.user.as_deref(), Some("admin"));
+                assert_eq!(args.password.as_deref(), Some("secret"));
             }
+            _ => panic!("expected audit command"),
         }
     }
 
@@ -148,6 +283,7 @@ mod tests {
             Commands::Audit(args) => {
                 assert_eq!(args.sql_file, Some(PathBuf::from("queries.sql")));
             }
+            _ => panic!("expected audit command"),
         }
     }
 
@@ -168,10 +304,220 @@ mod tests {
 
         match cli.command {
             Commands::Audit(args) => {
-                assert_eq!(args.db, "testdb");
+                assert_eq!(args.db, vec!["testdb"]);
                 assert_eq!(args.tables, vec!["events", "users", "orders"]);
                 assert_eq!(args.out, PathBuf::from("report.json"));
             }
+            _ => panic!("expected audit command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_with_config() {
+        let cli = Cli::parse_from([
+            "pipeaudit",
+            "audit",
+            "--config",
+            "pipeaudit.toml",
+            "--out",
+            "report.json",
+        ]);
+
+        match cli.command {
+            Commands::Audit(args) => {
+                assert_eq!(args.config, Some(PathBuf::from("pipeaudit.toml")));
+                assert!(args.db.is_empty());
+                assert!(args.tables.is_empty());
+            }
+            _ => panic!("expected audit command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_discovery_filters() {
+        let cli = Cli::parse_from([
+            "pipeaudit",
+            "audit",
+            "--endpoint",
+            "http://localhost:8123",
+            "--db",
+            "analytics,raw",
+            "--include",
+            "events*,re:^orders_",
+            "--exclude",
+            "*_tmp",
+            "--out",
+            "report.json",
+        ]);
+
+        match cli.command {
+            Commands::Audit(args) => {
+                assert_eq!(args.db, vec!["analytics", "raw"]);
+                assert!(args.tables.is_empty());
+                assert_eq!(args.include, vec!["events*", "re:^orders_"]);
+                assert_eq!(args.exclude, vec!["*_tmp"]);
+            }
+            _ => panic!("expected audit command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_fail_on() {
+        let cli = Cli::parse_from([
+            "pipeaudit",
+            "audit",
+            "--endpoint",
+            "http://localhost:8123",
+            "--db",
+            "testdb",
+            "--out",
+            "report.json",
+            "--fail-on",
+            "critical",
+        ]);
+
+        match cli.command {
+            Commands::Audit(args) => {
+                assert_eq!(args.fail_on, Some(FailOn::Critical));
+            }
+            _ => panic!("expected audit command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_only_and_skip() {
+        let cli = Cli::parse_from([
+            "pipeaudit",
+            "audit",
+            "--out",
+            "report.json",
+            "--only",
+            "parts,merges,disk",
+            "--skip",
+            "disk",
+        ]);
+
+        match cli.command {
+            Commands::Audit(args) => {
+                assert_eq!(args.only, vec!["parts", "merges", "disk"]);
+                assert_eq!(args.skip, vec!["disk"]);
+            }
+            _ => panic!("expected audit command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_query_window_and_limit() {
+        let cli = Cli::parse_from(["pipeaudit", "audit", "--out", "report.json"]);
+        match cli.command {
+            Commands::Audit(args) => {
+                assert_eq!(args.query_window, 7 * 86400);
+                assert_eq!(args.query_limit, 20);
+            }
+            _ => panic!("expected audit command"),
+        }
+
+        let cli = Cli::parse_from([
+            "pipeaudit",
+            "audit",
+            "--out",
+            "report.json",
+            "--query-window",
+            "6h",
+            "--query-limit",
+            "100",
+        ]);
+        match cli.command {
+            Commands::Audit(args) => {
+                assert_eq!(args.query_window, 6 * 3600);
+                assert_eq!(args.query_limit, 100);
+            }
+            _ => panic!("expected audit command"),
+        }
+    }
+
+    #[test]
+    fn test_parse_window() {
+        assert_eq!(parse_window("90"), Ok(90));
+        assert_eq!(parse_window("30m"), Ok(1800));
+        assert_eq!(parse_window("1d"), Ok(86400));
+        assert!(parse_window("0h").is_err());
+        assert!(parse_window("2w").is_err());
+        assert!(parse_window("h").is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_fail_on_rejects_unknown() {
+        let result = Cli::try_parse_from([
+            "pipeaudit",
+            "audit",
+            "--out",
+            "report.json",
+            "--fail-on",
+            "info",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_fail_on_warning() {
+        assert!(!FailOn::Warning.is_triggered_by(&ReportStatus::Healthy));
+        assert!(FailOn::Warning.is_triggered_by(&ReportStatus::Warning));
+        assert!(FailOn::Warning.is_triggered_by(&ReportStatus::Critical));
+    }
+
+    #[test]
+    fn test_fail_on_critical() {
+        assert!(!FailOn::Critical.is_triggered_by(&ReportStatus::Healthy));
+        assert!(!FailOn::Critical.is_triggered_by(&ReportStatus::Warning));
+        assert!(FailOn::Critical.is_triggered_by(&ReportStatus::Critical));
+    }
+
+    #[test]
+    fn test_audit_exit_code() {
+        let mut report = Report::new(Targets {
+            endpoint: "http://localhost:8123".to_string(),
+            database: "testdb".to_string(),
+            tables: vec![],
+        });
+        report.summary.status = ReportStatus::Critical;
+        assert_eq!(audit_exit_code(&report, None), exit_code::SUCCESS);
+        assert_eq!(
+            audit_exit_code(&report, Some(FailOn::Critical)),
+            exit_code::FINDINGS
+        );
+
+        report.collection.collectors.push(CollectorStatus {
+            source: "system.parts".to_string(),
+            status: CollectionStatus::Failed,
+            error: Some("Code: 497. Not enough privileges".to_string()),
+            duration_ms: 3,
+        });
+        assert_eq!(audit_exit_code(&report, None), exit_code::COLLECTION_FAILED);
+        assert_eq!(
+            audit_exit_code(&report, Some(FailOn::Critical)),
+            exit_code::COLLECTION_FAILED
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_diff_command() {
+        let cli = Cli::parse_from([
+            "pipeaudit",
+            "diff",
+            "yesterday.json",
+            "today.json",
+            "--out",
+            "diff.json",
+        ]);
+
+        match cli.command {
+            Commands::Diff(args) => {
+                assert_eq!(args.old, PathBuf::from("yesterday.json"));
+                assert_eq!(args.new, PathBuf::from("today.json"));
+                assert_eq!(args.out, Some(PathBuf::from("diff.json")));
+            }
+            _ => panic!("expected diff command"),
         }
     }
 }
@@ -0,0 +1,262 @@
+use super::tables::tables_condition;
+use super::{CollectScope, Collected, Collector, TableRef};
+use crate::ch::sql::{identifier, table_name};
+use crate::ch::ChClient;
+use crate::report::ColumnMetrics;
+use anyhow::Result;
+use clickhouse::Row;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+
+/// Approximate rows read per table to estimate the cardinality of String columns
+const SAMPLE_ROWS: u64 = 100_000;
+
+/// Collector for column storage from system.columns and system.parts_columns
+///
+/// With --sample-columns, String columns of tables that have a sampling key
+/// are also read with `SAMPLE` to estimate how many distinct values they
+/// hold. This is the only collector that reads table data, so it is opt-in.
+pub struct ColumnsCollector;
+
+#[derive(Debug, Row, Deserialize)]
+struct ColumnRow {
+    database: String,
+    table: String,
+    column: String,
+    column_type: String,
+    codec: String,
+    compressed_bytes: u64,
+    uncompressed_bytes: u64,
+    rows: u64,
+    is_in_sorting_key: u8,
+    has_sampling_key: u8,
+}
+
+#[derive(Debug, Row, Deserialize)]
+struct SampleRow {
+    distinct_values: Vec<u64>,
+}
+
+impl ColumnsCollector {
+    /// Build the SQL query for column storage (bytes summed over active parts)
+    pub fn build_query(tables: &[TableRef]) -> String {
+        let condition = tables_condition(tables);
+        format!(
+            r#"
+            SELECT
+                c.database AS database,
+                c.table AS table,
+                c.name AS column,
+                c.type AS column_type,
+                c.compression_codec AS codec,
+                toUInt64(p.compressed_bytes) AS compressed_bytes,
+                toUInt64(p.uncompressed_bytes) AS uncompressed_bytes,
+                toUInt64(p.rows) AS rows,
+                toUInt8(c.is_in_sorting_key) AS is_in_sorting_key,
+                toUInt8(t.sampling_key != '') AS has_sampling_key
+            FROM
+            (
+                SELECT database, table, name, type, compression_codec, is_in_sorting_key, position
+                FROM system.columns
+                WHERE {condition}
+            ) AS c
+            LEFT JOIN
+            (
+                SELECT
+                    database,
+                    table,
+                    column,
+                    sum(column_data_compressed_bytes) AS compressed_bytes,
+                    sum(column_data_uncompressed_bytes) AS uncompressed_bytes,
+                    sum(rows) AS rows
+                FROM system.parts_columns
+                WHERE active AND {condition}
+                GROUP BY database, table, column
+            ) AS p ON c.database = p.database AND c.table = p.table AND c.name = p.column
+            LEFT JOIN
+            (
+                SELECT database, table, sampling_key
+                FROM (SELECT database, name AS table, sampling_key FROM system.tables)
+                WHERE {condition}
+            ) AS t ON c.database = t.database AND c.table = t.table
+            ORDER BY database, table, c.position
+            "#,
+        )
+    }
+
+    /// Build the SQL query estimating distinct values of the given columns
+    pub fn build_sample_query(database: &str, table: &str, columns: &[&str]) -> String {
+        let columns: Vec<String> = columns.iter().map(|c| identifier(c)).collect();
+        format!(
+            "SELECT [{}] AS distinct_values FROM (SELECT {} FROM {} SAMPLE {})",
+            columns
+                .iter()
+                .map(|c| format!("toUInt64(uniq({}))", c))
+                .collect::<Vec<_>>()
+                .join(", "),
+            columns.join(", "),
+            table_name(database, table),
+            SAMPLE_ROWS
+        )
+    }
+
+    /// Collect column metrics from ClickHouse
+    ///
+    /// A table that cannot be sampled keeps `distinct_values = None`; it does
+    /// not fail the collector.
+    pub async fn collect(
+        client: &ChClient,
+        tables: &[TableRef],
+        sample: bool,
+    ) -> Result<Vec<ColumnMetrics>> {
+        let sql = Self::build_query(tables);
+        let rows: Vec<ColumnRow> = client.fetch_all(&sql).await?;
+        let samplable: BTreeSet<TableRef> = rows
+            .iter()
+            .filter(|row| sample && row.has_sampling_key != 0)
+            .map(|row| TableRef::new(&row.database, &row.table))
+            .collect();
+        let mut metrics: Vec<ColumnMetrics> = rows.into_iter().map(Self::metrics).collect();
+
+        for table in &samplable {
+            let sampled: Vec<usize> = metrics
+                .iter()
+                .enumerate()
+                .filter(|(_, m)| {
+                    m.database == table.database && m.table == table.table && is_sampled(m)
+                })
+                .map(|(i, _)| i)
+                .collect();
+            if sampled.is_empty() {
+                continue;
+            }
+
+            let columns: Vec<&str> = sampled
+                .iter()
+                .map(|&i| metrics[i].column.as_str())
+                .collect();
+            let sample_sql = Self::build_sample_query(&table.database, &table.table, &columns);
+            let Ok(sample) = client.fetch_one::<SampleRow>(&sample_sql).await else {
+                continue;
+            };
+            for (i, distinct) in sampled.into_iter().zip(sample.distinct_values) {
+                metrics[i].distinct_values = Some(distinct);
+            }
+        }
+
+        Ok(metrics)
+    }
+
+    fn metrics(row: ColumnRow) -> ColumnMetrics {
+        ColumnMetrics {
+            compression_ratio: if row.compressed_bytes > 0 {
+                row.uncompressed_bytes as f64 / row.compressed_bytes as f64
+            } else {
+                0.0
+            },
+            database: row.database,
+            table: row.table,
+            column: row.column,
+            column_type: row.column_type,
+            codec: if row.codec.is_empty() {
+                None
+            } else {
+                Some(row.codec)
+            },
+            compressed_bytes: row.compressed_bytes,
+            uncompressed_bytes: row.uncompressed_bytes,
+            rows: row.rows,
+            is_in_sorting_key: row.is_in_sorting_key != 0,
+            distinct_values: None,
+        }
+    }
+
+    /// Get the SQL query string for evidence tracking
+    pub fn sql(tables: &[TableRef]) -> String {
+        Self::build_query(tables)
+    }
+}
+
+/// Plain String columns with data are sampled for cardinality
+fn is_sampled(column: &ColumnMetrics) -> bool {
+    column.rows > 0 && matches!(column.column_type.as_str(), "String" | "Nullable(String)")
+}
+
+impl Collector for ColumnsCollector {
+    fn name(&self) -> &'static str {
+        "columns"
+    }
+
+    fn source(&self) -> &'static str {
+        "system.columns"
+    }
+
+    fn sql(&self, scope: &CollectScope) -> String {
+        Self::sql(&scope.tables)
+    }
+
+    fn collect<'a>(
+        &'a self,
+        client: &'a ChClient,
+        scope: &'a CollectScope,
+    ) -> BoxFuture<'a, Result<Collected>> {
+        Box::pin(async move {
+            Ok(Collected::Columns(
+                Self::collect(client, &scope.tables, scope.sample_columns).await?,
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(column_type: &str, compressed_bytes: u64, uncompressed_bytes: u64) -> ColumnRow {
+        ColumnRow {
+            database: "testdb".to_string(),
+            table: "events".to_string(),
+            column: "payload".to_string(),
+            column_type: column_type.to_string(),
+            codec: String::new(),
+            compressed_bytes,
+            uncompressed_bytes,
+            rows: 1000,
+            is_in_sorting_key: 0,
+            has_sampling_key: 1,
+        }
+    }
+
+    #[test]
+    fn test_columns_query() {
+        let sql = ColumnsCollector::build_query(&[TableRef::new("testdb", "events")]);
+        assert!(sql.contains("FROM system.columns"));
+        assert!(sql.contains("FROM system.parts_columns"));
+        assert!(sql.contains("WHERE active AND (database, table) IN (('testdb', 'events'))"));
+        assert!(sql.contains("sum(column_data_compressed_bytes) AS compressed_bytes"));
+        assert!(sql.contains("toUInt8(t.sampling_key != '') AS has_sampling_key"));
+    }
+
+    #[test]
+    fn test_sample_query_quotes_names() {
+        let sql =
+            ColumnsCollector::build_sample_query("testdb", "events", &["country", "user agent"]);
+        assert_eq!(
+            sql,
+            "SELECT [toUInt64(uniq(country)), toUInt64(uniq(`user agent`))] AS distinct_values FROM (SELECT country, `user agent` FROM testdb.events SAMPLE 100000)"
+        );
+    }
+
+    #[test]
+    fn test_metrics_from_row() {
+        let m = ColumnsCollector::metrics(row("String", 100, 450));
+        assert_eq!(m.compression_ratio, 4.5);
+        assert!(m.codec.is_none());
+        assert!(is_sampled(&m));
+
+        let empty = ColumnsCollector::metrics(row("UInt64", 0, 0));
+        assert_eq!(empty.compression_ratio, 0.0);
+        assert!(!is_sampled(&empty));
+    }
+}
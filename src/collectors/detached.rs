@@ -0,0 +1,157 @@
+use super::tables::tables_condition;
+use super::{CollectScope, Collected, Collector, TableRef};
+use crate::ch::ChClient;
+use crate::report::{DetachedPartsMetrics, DetachedReasonMetrics};
+use anyhow::Result;
+use clickhouse::Row;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+
+/// Part names reported per table and reason
+const MAX_PART_NAMES: u64 = 20;
+
+/// Collector for detached parts from system.detached_parts
+pub struct DetachedPartsCollector;
+
+#[derive(Debug, Row, Deserialize)]
+struct ReasonRow {
+    database: String,
+    table: String,
+    detach_reason: String,
+    parts: u64,
+    bytes_on_disk: u64,
+    part_names: Vec<String>,
+}
+
+impl DetachedPartsCollector {
+    /// Build the SQL query for detached parts (one row per table and reason)
+    pub fn build_query(tables: &[TableRef]) -> String {
+        format!(
+            r#"
+            SELECT
+                database,
+                table,
+                ifNull(reason, '') AS detach_reason,
+                toUInt64(count()) AS parts,
+                toUInt64(sum(bytes_on_disk)) AS bytes_on_disk,
+                groupArray({max_names})(name) AS part_names
+            FROM system.detached_parts
+            WHERE {condition}
+            GROUP BY database, table, detach_reason
+            ORDER BY database, table, bytes_on_disk DESC
+            "#,
+            max_names = MAX_PART_NAMES,
+            condition = tables_condition(tables)
+        )
+    }
+
+    /// Collect detached parts metrics from ClickHouse
+    pub async fn collect(
+        client: &ChClient,
+        tables: &[TableRef],
+    ) -> Result<Vec<DetachedPartsMetrics>> {
+        let sql = Self::build_query(tables);
+        let rows: Vec<ReasonRow> = client.fetch_all(&sql).await?;
+
+        Ok(Self::group(rows))
+    }
+
+    /// Group reason rows (ordered by table) into one entry per table
+    fn group(rows: Vec<ReasonRow>) -> Vec<DetachedPartsMetrics> {
+        let mut tables: Vec<DetachedPartsMetrics> = Vec::new();
+
+        for row in rows {
+            let reason = DetachedReasonMetrics {
+                reason: row.detach_reason,
+                parts: row.parts,
+                bytes_on_disk: row.bytes_on_disk,
+                part_names: row.part_names,
+            };
+
+            match tables.last_mut() {
+                Some(t) if t.database == row.database && t.table == row.table => {
+                    t.reasons.push(reason)
+                }
+                _ => tables.push(DetachedPartsMetrics {
+                    database: row.database,
+                    table: row.table,
+                    reasons: vec![reason],
+                }),
+            }
+        }
+
+        tables
+    }
+
+    /// Get the SQL query string for evidence tracking
+    pub fn sql(tables: &[TableRef]) -> String {
+        Self::build_query(tables)
+    }
+}
+
+impl Collector for DetachedPartsCollector {
+    fn name(&self) -> &'static str {
+        "detached_parts"
+    }
+
+    fn source(&self) -> &'static str {
+        "system.detached_parts"
+    }
+
+    fn sql(&self, scope: &CollectScope) -> String {
+        Self::sql(&scope.tables)
+    }
+
+    fn collect<'a>(
+        &'a self,
+        client: &'a ChClient,
+        scope: &'a CollectScope,
+    ) -> BoxFuture<'a, Result<Collected>> {
+        Box::pin(async move {
+            Ok(Collected::DetachedParts(
+                Self::collect(client, &scope.tables).await?,
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(table: &str, reason: &str, parts: u64, bytes_on_disk: u64) -> ReasonRow {
+        ReasonRow {
+            database: "testdb".to_string(),
+            table: table.to_string(),
+            detach_reason: reason.to_string(),
+            parts,
+            bytes_on_disk,
+            part_names: vec![format!("{}_all_1_1_0", reason)],
+        }
+    }
+
+    #[test]
+    fn test_detached_parts_query() {
+        let sql = DetachedPartsCollector::build_query(&[TableRef::new("testdb", "events")]);
+        assert!(sql.contains("FROM system.detached_parts"));
+        assert!(sql.contains("WHERE (database, table) IN (('testdb', 'events'))"));
+        assert!(sql.contains("groupArray(20)(name) AS part_names"));
+        assert!(sql.contains("GROUP BY database, table, detach_reason"));
+    }
+
+    #[test]
+    fn test_group_by_table() {
+        let tables = DetachedPartsCollector::group(vec![
+            row("events", "broken", 1, 500),
+            row("events", "ignored", 3, 200),
+            row("users", "", 2, 100),
+        ]);
+
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].table, "events");
+        assert_eq!(tables[0].reasons.len(), 2);
+        assert_eq!(tables[0].reasons[0].reason, "broken");
+        assert_eq!(tables[0].reasons[1].part_names, vec!["ignored_all_1_1_0"]);
+        assert_eq!(tables[1].reasons[0].reason, "");
+    }
+}
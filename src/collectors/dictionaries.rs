@@ -0,0 +1,170 @@
+use super::{CollectScope, Collected, Collector};
+use crate::ch::sql::literal_list;
+use crate::ch::ChClient;
+use crate::report::DictionaryMetrics;
+use anyhow::Result;
+use clickhouse::Row;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+
+/// Collector for dictionaries from system.dictionaries
+///
+/// Dictionaries are not discovered as tables; every dictionary in the
+/// audited databases is reported, plus those defined in the server config,
+/// which have an empty database.
+pub struct DictionariesCollector;
+
+#[derive(Debug, Row, Deserialize)]
+struct DictionaryRow {
+    database: String,
+    name: String,
+    status: String,
+    dictionary_type: String,
+    source: String,
+    lifetime_min: u64,
+    lifetime_max: u64,
+    last_successful_update_time: String,
+    updated: u8,
+    update_age_sec: u64,
+    loading_duration_sec: f64,
+    bytes_allocated: u64,
+    element_count: u64,
+    last_exception: String,
+}
+
+impl DictionariesCollector {
+    /// Build the SQL query for dictionaries
+    pub fn build_query(databases: &[String]) -> String {
+        format!(
+            r#"
+            SELECT
+                database,
+                name,
+                toString(status) AS status,
+                type AS dictionary_type,
+                source,
+                toUInt64(lifetime_min) AS lifetime_min,
+                toUInt64(lifetime_max) AS lifetime_max,
+                toString(last_successful_update_time) AS last_successful_update_time,
+                toUInt8(last_successful_update_time > toDateTime(0)) AS updated,
+                toUInt64(greatest(dateDiff('second', last_successful_update_time, now()), 0)) AS update_age_sec,
+                toFloat64(loading_duration) AS loading_duration_sec,
+                toUInt64(bytes_allocated) AS bytes_allocated,
+                toUInt64(element_count) AS element_count,
+                last_exception
+            FROM system.dictionaries
+            WHERE database IN ({databases}) OR database = ''
+            ORDER BY database, name
+            "#,
+            databases = literal_list(databases)
+        )
+    }
+
+    /// Collect dictionary metrics from ClickHouse
+    pub async fn collect(
+        client: &ChClient,
+        databases: &[String],
+    ) -> Result<Vec<DictionaryMetrics>> {
+        let sql = Self::build_query(databases);
+        let rows: Vec<DictionaryRow> = client.fetch_all(&sql).await?;
+
+        Ok(rows.into_iter().map(Self::metrics).collect())
+    }
+
+    fn metrics(row: DictionaryRow) -> DictionaryMetrics {
+        DictionaryMetrics {
+            database: row.database,
+            name: row.name,
+            status: row.status,
+            dictionary_type: row.dictionary_type,
+            source: row.source,
+            lifetime_min: row.lifetime_min,
+            lifetime_max: row.lifetime_max,
+            last_successful_update_time: row.last_successful_update_time,
+            update_age_sec: (row.updated != 0).then_some(row.update_age_sec),
+            loading_duration_sec: row.loading_duration_sec,
+            bytes_allocated: row.bytes_allocated,
+            element_count: row.element_count,
+            last_exception: if row.last_exception.is_empty() {
+                None
+            } else {
+                Some(row.last_exception)
+            },
+        }
+    }
+
+    /// Get the SQL query string for evidence tracking
+    pub fn sql(databases: &[String]) -> String {
+        Self::build_query(databases)
+    }
+}
+
+impl Collector for DictionariesCollector {
+    fn name(&self) -> &'static str {
+        "dictionaries"
+    }
+
+    fn source(&self) -> &'static str {
+        "system.dictionaries"
+    }
+
+    fn sql(&self, scope: &CollectScope) -> String {
+        Self::sql(&scope.databases)
+    }
+
+    fn collect<'a>(
+        &'a self,
+        client: &'a ChClient,
+        scope: &'a CollectScope,
+    ) -> BoxFuture<'a, Result<Collected>> {
+        Box::pin(async move {
+            Ok(Collected::Dictionaries(
+                Self::collect(client, &scope.databases).await?,
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(updated: u8, last_exception: &str) -> DictionaryRow {
+        DictionaryRow {
+            database: "testdb".to_string(),
+            name: "countries".to_string(),
+            status: "LOADED".to_string(),
+            dictionary_type: "Hashed".to_string(),
+            source: "ClickHouse: testdb.countries_src".to_string(),
+            lifetime_min: 300,
+            lifetime_max: 600,
+            last_successful_update_time: "2024-01-01 00:00:00".to_string(),
+            updated,
+            update_age_sec: 120,
+            loading_duration_sec: 0.5,
+            bytes_allocated: 1024,
+            element_count: 250,
+            last_exception: last_exception.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_dictionaries_query() {
+        let sql = DictionariesCollector::build_query(&["testdb".to_string()]);
+        assert!(sql.contains("FROM system.dictionaries"));
+        assert!(sql.contains("WHERE database IN ('testdb') OR database = ''"));
+        assert!(sql.contains("toString(status) AS status"));
+        assert!(sql.contains("last_exception"));
+    }
+
+    #[test]
+    fn test_metrics_from_row() {
+        let m = DictionariesCollector::metrics(row(1, ""));
+        assert_eq!(m.update_age_sec, Some(120));
+        assert!(m.last_exception.is_none());
+
+        let m = DictionariesCollector::metrics(row(0, "Connection refused"));
+        assert_eq!(m.update_age_sec, None);
+        assert_eq!(m.last_exception.as_deref(), Some("Connection refused"));
+    }
+}
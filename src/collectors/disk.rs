@@ -1,7 +1,9 @@
+use super::{CollectScope, Collected, Collector};
 use crate::ch::ChClient;
 use crate::report::DiskMetrics;
 use anyhow::Result;
 use clickhouse::Row;
+use futures::future::BoxFuture;
 use serde::Deserialize;
 
 /// Collector for disk metrics from system.disks
@@ -57,6 +59,28 @@ impl DiskCollector {
     }
 }
 
+impl Collector for DiskCollector {
+    fn name(&self) -> &'static str {
+        "disk"
+    }
+
+    fn source(&self) -> &'static str {
+        "system.disks"
+    }
+
+    fn sql(&self, _scope: &CollectScope) -> String {
+        Self::sql()
+    }
+
+    fn collect<'a>(
+        &'a self,
+        client: &'a ChClient,
+        _scope: &'a CollectScope,
+    ) -> BoxFuture<'a, Result<Collected>> {
+        Box::pin(async move { Ok(Collected::Disk(Self::collect(client).await?)) })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,260 @@
+use super::{QueryWindow, TableRef};
+use crate::ch::{ChClient, QueryStats};
+use crate::report::{
+    ColumnMetrics, DetachedPartsMetrics, DictionaryMetrics, DiskMetrics, DistributionQueueMetrics,
+    ExplainMetrics, FailedQueryMetrics, InsertMetrics, KafkaTableMetrics, MergeMetrics,
+    MergeTreeSettingsMetrics, MutationMetrics, MvDagSection, PartsMetrics, QueryMetrics,
+    ReplicationMetrics, ServerSection, TtlMetrics,
+};
+use anyhow::Result;
+use futures::future::{join_all, BoxFuture};
+use std::time::{Duration, Instant};
+
+/// What to audit: passed to every collector
+#[derive(Debug, Clone, Default)]
+pub struct CollectScope {
+    /// Tables to audit, from discovery
+    pub tables: Vec<TableRef>,
+    /// Databases matching --db, including those without MergeTree tables
+    pub databases: Vec<String>,
+    /// Contents of --sql-file, if given
+    pub sql: Option<String>,
+    /// system.query_log window and limit (--query-window, --query-limit)
+    pub query_window: QueryWindow,
+    /// Whether the columns collector may sample table data (--sample-columns)
+    pub sample_columns: bool,
+}
+
+/// Metrics produced by a collector
+#[derive(Debug)]
+pub enum Collected {
+    Parts(Vec<PartsMetrics>),
+    Merges(Vec<MergeMetrics>),
+    Mutations(Vec<MutationMetrics>),
+    Disk(Vec<DiskMetrics>),
+    Queries(Vec<QueryMetrics>),
+    MvDag(MvDagSection),
+    Explain(Vec<ExplainMetrics>),
+    Replication(Vec<ReplicationMetrics>),
+    DistributionQueue(Vec<DistributionQueueMetrics>),
+    Kafka(Vec<KafkaTableMetrics>),
+    MergeTreeSettings(Vec<MergeTreeSettingsMetrics>),
+    Columns(Vec<ColumnMetrics>),
+    Ttl(Vec<TtlMetrics>),
+    DetachedParts(Vec<DetachedPartsMetrics>),
+    Inserts(Vec<InsertMetrics>),
+    FailedQueries(Vec<FailedQueryMetrics>),
+    Server(ServerSection),
+    Dictionaries(Vec<DictionaryMetrics>),
+}
+
+/// Trait for implementing metric collectors
+pub trait Collector: Send + Sync {
+    /// Short name used by --only/--skip (e.g. "parts")
+    fn name(&self) -> &'static str;
+
+    /// Metric source recorded as evidence (e.g. "system.parts")
+    fn source(&self) -> &'static str;
+
+    /// Whether the collector has anything to do for this scope
+    fn applies(&self, _scope: &CollectScope) -> bool {
+        true
+    }
+
+    /// SQL recorded as evidence
+    fn sql(&self, scope: &CollectScope) -> String;
+
+    /// Collect metrics from ClickHouse
+    fn collect<'a>(
+        &'a self,
+        client: &'a ChClient,
+        scope: &'a CollectScope,
+    ) -> BoxFuture<'a, Result<Collected>>;
+}
+
+/// Outcome of running one collector
+#[derive(Debug)]
+pub struct CollectorRun {
+    pub name: &'static str,
+    pub source: &'static str,
+    pub sql: String,
+    pub result: Result<Collected>,
+    pub stats: QueryStats,
+    pub duration: Duration,
+}
+
+/// Registry for managing and running collectors
+#[derive(Default)]
+pub struct CollectorRegistry {
+    collectors: Vec<Box<dyn Collector>>,
+}
+
+impl CollectorRegistry {
+    /// Create a new empty collector registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a collector
+    pub fn register(&mut self, collector: Box<dyn Collector>) {
+        self.collectors.push(collector);
+    }
+
+    /// Keep only the collectors named in `only` (if non-empty), minus those in `skip`
+    pub fn select(mut self, only: &[String], skip: &[String]) -> Result<Self> {
+        let names = self.names();
+        for name in only.iter().chain(skip) {
+            anyhow::ensure!(
+                names.contains(&name.as_str()),
+                "Unknown collector '{}' (known collectors: {})",
+                name,
+                names.join(", ")
+            );
+        }
+
+        self.collectors.retain(|c| {
+            (only.is_empty() || only.iter().any(|n| n == c.name()))
+                && !skip.iter().any(|n| n == c.name())
+        });
+        Ok(self)
+    }
+
+    /// Run all applicable collectors concurrently.
+    ///
+    /// Each collector gets its own tracked client, so query stats are not
+    /// mixed up. Results are returned in registration order.
+    pub async fn run_all(&self, client: &ChClient, scope: &CollectScope) -> Vec<CollectorRun> {
+        let runs =
+            self.collectors
+                .iter()
+                .filter(|c| c.applies(scope))
+                .map(|collector| async move {
+                    let tracked = client.tracked();
+                    let started = Instant::now();
+                    let result = collector.collect(&tracked, scope).await;
+                    CollectorRun {
+                        name: collector.name(),
+                        source: collector.source(),
+                        sql: collector.sql(scope),
+                        result,
+                        stats: tracked.stats(),
+                        duration: started.elapsed(),
+                    }
+                });
+
+        join_all(runs).await
+    }
+
+    /// Get the number of registered collectors
+    pub fn len(&self) -> usize {
+        self.collectors.len()
+    }
+
+    /// Check if registry is empty
+    pub fn is_empty(&self) -> bool {
+        self.collectors.is_empty()
+    }
+
+    /// Get list of registered collector names
+    pub fn names(&self) -> Vec<&'static str> {
+        self.collectors.iter().map(|c| c.name()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Mock collector that never touches the network
+    struct MockCollector {
+        name: &'static str,
+        fail: bool,
+    }
+
+    impl Collector for MockCollector {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn source(&self) -> &'static str {
+            "system.mock"
+        }
+
+        fn applies(&self, scope: &CollectScope) -> bool {
+            self.name != "explain" || scope.sql.is_some()
+        }
+
+        fn sql(&self, scope: &CollectScope) -> String {
+            format!("SELECT '{}'", scope.tables[0])
+        }
+
+        fn collect<'a>(
+            &'a self,
+            _client: &'a ChClient,
+            _scope: &'a CollectScope,
+        ) -> BoxFuture<'a, Result<Collected>> {
+            Box::pin(async move {
+                anyhow::ensure!(!self.fail, "{} failed", self.name);
+                Ok(Collected::Disk(vec![]))
+            })
+        }
+    }
+
+    fn registry(names: &[&'static str]) -> CollectorRegistry {
+        let mut registry = CollectorRegistry::new();
+        for &name in names {
+            registry.register(Box::new(MockCollector {
+                name,
+                fail: name == "broken",
+            }));
+        }
+        registry
+    }
+
+    fn client() -> ChClient {
+        ChClient::new("http://localhost:8123", "default", "", "default")
+    }
+
+    #[test]
+    fn test_registry_names() {
+        let registry = registry(&["parts", "disk"]);
+        assert_eq!(registry.len(), 2);
+        assert_eq!(registry.names(), vec!["parts", "disk"]);
+    }
+
+    #[test]
+    fn test_select_only_and_skip() {
+        let only = vec!["parts".to_string(), "disk".to_string()];
+        let skip = vec!["disk".to_string()];
+        let selected = registry(&["parts", "merges", "disk"])
+            .select(&only, &skip)
+            .unwrap();
+        assert_eq!(selected.names(), vec!["parts"]);
+
+        let all = registry(&["parts", "merges"]).select(&[], &[]).unwrap();
+        assert_eq!(all.len(), 2);
+    }
+
+    #[test]
+    fn test_select_rejects_unknown_collector() {
+        let result = registry(&["parts"]).select(&["part".to_string()], &[]);
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_all_keeps_order_and_failures() {
+        let registry = registry(&["parts", "broken", "disk", "explain"]);
+        let scope = CollectScope {
+            tables: vec![TableRef::new("testdb", "events")],
+            ..Default::default()
+        };
+
+        let runs = registry.run_all(&client(), &scope).await;
+        let names: Vec<_> = runs.iter().map(|r| r.name).collect();
+        // explain does not apply without --sql-file
+        assert_eq!(names, vec!["parts", "broken", "disk"]);
+        assert!(runs[0].result.is_ok());
+        assert!(runs[1].result.is_err());
+        assert_eq!(runs[2].sql, "SELECT 'testdb.events'");
+    }
+}
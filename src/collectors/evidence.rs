@@ -1,3 +1,4 @@
+use crate::ch::QueryStats;
 use crate::report::Evidence;
 
 /// Collector for tracking evidence (source SQL queries and timestamps)
@@ -14,12 +15,20 @@ impl EvidenceCollector {
 
     /// Record a piece of evidence and return its ID
     pub fn record(&mut self, source: &str, sql: &str) -> String {
+        self.record_query(source, sql, &QueryStats::default())
+    }
+
+    /// Record a piece of evidence with the stats of the queries that produced it
+    pub fn record_query(&mut self, source: &str, sql: &str, stats: &QueryStats) -> String {
         let id = format!("ev-{:03}", self.evidence.len() + 1);
         let evidence = Evidence {
             id: id.clone(),
             source: source.to_string(),
             sql: sql.trim().to_string(),
             collected_at: chrono::Utc::now().to_rfc3339(),
+            row_count: stats.row_count,
+            duration_ms: stats.duration_ms,
+            query_ids: stats.query_ids.clone(),
         };
         self.evidence.push(evidence);
         id
@@ -126,6 +135,22 @@ mod tests {
         assert_eq!(all[1].source, "source2");
     }
 
+    #[test]
+    fn test_evidence_record_query_stats() {
+        let mut collector = EvidenceCollector::new();
+        let stats = QueryStats {
+            query_ids: vec!["3f1c".to_string()],
+            row_count: 42,
+            duration_ms: 15,
+        };
+        let id = collector.record_query("system.parts", "SELECT 1", &stats);
+
+        let evidence = collector.get(&id).unwrap();
+        assert_eq!(evidence.row_count, 42);
+        assert_eq!(evidence.duration_ms, 15);
+        assert_eq!(evidence.query_ids, vec!["3f1c"]);
+    }
+
     #[test]
     fn test_evidence_trims_sql() {
         let mut collector = EvidenceCollector::new();
@@ -0,0 +1,500 @@
+use super::{CollectScope, Collected, Collector};
+use crate::ch::sql::literal;
+use crate::ch::ChClient;
+use crate::report::{ExplainEstimate, ExplainIndex, ExplainMetrics, ExplainRead};
+use anyhow::Result;
+use clickhouse::Row;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Collector for EXPLAIN analysis of statements from a SQL file
+pub struct ExplainCollector;
+
+#[derive(Debug, Row, Deserialize)]
+struct ExplainRow {
+    explain: String,
+}
+
+#[derive(Debug, Row, Deserialize)]
+struct EstimateRow {
+    database: String,
+    table: String,
+    parts: u64,
+    rows: u64,
+    marks: u64,
+}
+
+#[derive(Debug, Row, Deserialize)]
+struct SkipIndexRow {
+    name: String,
+}
+
+impl ExplainCollector {
+    /// Split a SQL file into individual statements.
+    ///
+    /// Semicolons inside string literals, quoted identifiers and comments
+    /// do not terminate a statement.
+    pub fn split_statements(sql: &str) -> Vec<String> {
+        let mut statements = Vec::new();
+        let mut current = String::new();
+        let mut chars = sql.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\'' | '"' | '`' => {
+                    current.push(c);
+                    while let Some(inner) = chars.next() {
+                        current.push(inner);
+                        if inner == '\\' {
+                            if let Some(escaped) = chars.next() {
+                                current.push(escaped);
+                            }
+                        } else if inner == c {
+                            break;
+                        }
+                    }
+                }
+                '-' if chars.peek() == Some(&'-') => {
+                    // Line comment: skip to end of line
+                    for inner in chars.by_ref() {
+                        if inner == '\n' {
+                            current.push('\n');
+                            break;
+                        }
+                    }
+                }
+                '/' if chars.peek() == Some(&'*') => {
+                    // Block comment: skip to closing */
+                    chars.next();
+                    let mut prev = ' ';
+                    for inner in chars.by_ref() {
+                        if prev == '*' && inner == '/' {
+                            break;
+                        }
+                        prev = inner;
+                    }
+                    current.push(' ');
+                }
+                ';' => {
+                    let statement = current.trim();
+                    if !statement.is_empty() {
+                        statements.push(statement.to_string());
+                    }
+                    current.clear();
+                }
+                _ => current.push(c),
+            }
+        }
+
+        let statement = current.trim();
+        if !statement.is_empty() {
+            statements.push(statement.to_string());
+        }
+
+        statements
+    }
+
+    /// Build the EXPLAIN query for index usage
+    pub fn build_indexes_query(statement: &str) -> String {
+        format!("EXPLAIN indexes = 1 {}", statement)
+    }
+
+    /// Build the EXPLAIN query for read estimates
+    pub fn build_estimate_query(statement: &str) -> String {
+        format!("EXPLAIN ESTIMATE {}", statement)
+    }
+
+    /// Build the EXPLAIN query for the execution pipeline
+    pub fn build_pipeline_query(statement: &str) -> String {
+        format!("EXPLAIN PIPELINE {}", statement)
+    }
+
+    /// Build the indexes, estimate and pipeline EXPLAIN queries for a statement
+    ///
+    /// The statement is left as written: `ChClient` escapes `?` for the driver.
+    fn build_explain_queries(statement: &str) -> [String; 3] {
+        [
+            Self::build_indexes_query(statement),
+            Self::build_estimate_query(statement),
+            Self::build_pipeline_query(statement),
+        ]
+    }
+
+    /// Build the SQL query for skip indexes defined on a table
+    pub fn build_skip_indexes_query(table: &str) -> String {
+        let database_filter = match table.split_once('.') {
+            Some((database, _)) => literal(database),
+            None => "currentDatabase()".to_string(),
+        };
+        let table_name = table.split_once('.').map_or(table, |(_, t)| t);
+
+        format!(
+            r#"
+            SELECT
+                name
+            FROM system.data_skipping_indices
+            WHERE database = {database_filter} AND table = {table_name}
+            ORDER BY name
+            "#,
+            database_filter = database_filter,
+            table_name = literal(table_name)
+        )
+    }
+
+    /// Run EXPLAIN for every statement in the SQL file
+    pub async fn collect(client: &ChClient, sql: &str) -> Result<Vec<ExplainMetrics>> {
+        let mut metrics = Vec::new();
+        let mut skip_indexes: HashMap<String, Vec<String>> = HashMap::new();
+
+        for (i, statement) in Self::split_statements(sql).iter().enumerate() {
+            let mut explained = Self::explain_statement(client, i + 1, statement).await;
+
+            for read in &mut explained.reads {
+                if read.table.is_empty() {
+                    continue;
+                }
+                if !skip_indexes.contains_key(&read.table) {
+                    let sql = Self::build_skip_indexes_query(&read.table);
+                    let rows: Vec<SkipIndexRow> = client.fetch_all(&sql).await?;
+                    skip_indexes.insert(
+                        read.table.clone(),
+                        rows.into_iter().map(|r| r.name).collect(),
+                    );
+                }
+                read.available_skip_indexes = skip_indexes[&read.table].clone();
+            }
+
+            metrics.push(explained);
+        }
+
+        Ok(metrics)
+    }
+
+    /// Explain a single statement, recording failures instead of aborting
+    async fn explain_statement(
+        client: &ChClient,
+        statement_index: usize,
+        statement: &str,
+    ) -> ExplainMetrics {
+        let mut metrics = ExplainMetrics {
+            statement_index,
+            statement: statement.to_string(),
+            ..Default::default()
+        };
+
+        let [indexes_sql, estimate_sql, pipeline_sql] = Self::build_explain_queries(statement);
+
+        match client.fetch_all::<ExplainRow>(&indexes_sql).await {
+            Ok(rows) => {
+                let lines: Vec<String> = rows.into_iter().map(|r| r.explain).collect();
+                metrics.reads = Self::parse_indexes(&lines);
+            }
+            Err(e) => {
+                metrics.error = Some(e.root_cause().to_string());
+                return metrics;
+            }
+        }
+
+        match client.fetch_all::<EstimateRow>(&estimate_sql).await {
+            Ok(rows) => {
+                metrics.estimates = rows
+                    .into_iter()
+                    .map(|row| ExplainEstimate {
+                        database: row.database,
+                        table: row.table,
+                        parts: row.parts,
+                        rows: row.rows,
+                        marks: row.marks,
+                    })
+                    .collect();
+            }
+            Err(e) => metrics.error = Some(e.root_cause().to_string()),
+        }
+
+        match client.fetch_all::<ExplainRow>(&pipeline_sql).await {
+            Ok(rows) => {
+                let lines: Vec<String> = rows.into_iter().map(|r| r.explain).collect();
+                metrics.pipeline = Some(lines.join("\n"));
+            }
+            Err(e) => metrics.error = Some(e.root_cause().to_string()),
+        }
+
+        // Older servers print ReadFromMergeTree without the table name
+        if let [estimate] = metrics.estimates.as_slice() {
+            for read in metrics.reads.iter_mut().filter(|r| r.table.is_empty()) {
+                read.table = format!("{}.{}", estimate.database, estimate.table);
+            }
+        }
+
+        metrics
+    }
+
+    /// Parse `EXPLAIN indexes = 1` output into per-read index usage
+    fn parse_indexes(lines: &[String]) -> Vec<ExplainRead> {
+        let mut reads: Vec<ExplainRead> = Vec::new();
+        let mut indexes_indent: Option<usize> = None;
+
+        for line in lines {
+            let indent = line.len() - line.trim_start().len();
+            let text = line.trim();
+
+            if let Some(rest) = text.strip_prefix("ReadFromMergeTree") {
+                let table = rest
+                    .trim()
+                    .trim_start_matches('(')
+                    .trim_end_matches(')')
+                    .to_string();
+                reads.push(ExplainRead {
+                    table,
+                    ..Default::default()
+                });
+                indexes_indent = None;
+                continue;
+            }
+
+            let Some(read) = reads.last_mut() else {
+                continue;
+            };
+
+            if text == "Indexes:" {
+                indexes_indent = Some(indent);
+                continue;
+            }
+
+            let Some(base) = indexes_indent else {
+                continue;
+            };
+
+            if indent <= base {
+                indexes_indent = None;
+            } else if indent == base + 2 {
+                read.indexes.push(ExplainIndex {
+                    index_type: text.to_string(),
+                    ..Default::default()
+                });
+            } else if let Some(index) = read.indexes.last_mut() {
+                if let Some(name) = text.strip_prefix("Name: ") {
+                    index.name = Some(name.to_string());
+                } else if let Some(condition) = text.strip_prefix("Condition: ") {
+                    index.condition = Some(condition.to_string());
+                } else if let Some(parts) = text.strip_prefix("Parts: ") {
+                    (index.parts_selected, index.parts_total) = parse_ratio(parts);
+                } else if let Some(granules) = text.strip_prefix("Granules: ") {
+                    (index.granules_selected, index.granules_total) = parse_ratio(granules);
+                }
+            }
+        }
+
+        reads
+    }
+
+    /// Get the SQL query string for evidence tracking
+    pub fn sql(sql: &str) -> String {
+        Self::split_statements(sql)
+            .iter()
+            .flat_map(|s| {
+                [
+                    Self::build_indexes_query(s),
+                    Self::build_estimate_query(s),
+                    Self::build_pipeline_query(s),
+                ]
+            })
+            .collect::<Vec<_>>()
+            .join(";\n")
+    }
+}
+
+/// Parse a "selected/total" pair such as `Granules: 12/1222`
+fn parse_ratio(s: &str) -> (u64, u64) {
+    let (selected, total) = s.split_once('/').unwrap_or((s, s));
+    (
+        selected.trim().parse().unwrap_or(0),
+        total.trim().parse().unwrap_or(0),
+    )
+}
+
+impl Collector for ExplainCollector {
+    fn name(&self) -> &'static str {
+        "explain"
+    }
+
+    fn source(&self) -> &'static str {
+        "explain"
+    }
+
+    fn applies(&self, scope: &CollectScope) -> bool {
+        scope.sql.is_some()
+    }
+
+    fn sql(&self, scope: &CollectScope) -> String {
+        Self::sql(scope.sql.as_deref().unwrap_or_default())
+    }
+
+    fn collect<'a>(
+        &'a self,
+        client: &'a ChClient,
+        scope: &'a CollectScope,
+    ) -> BoxFuture<'a, Result<Collected>> {
+        Box::pin(async move {
+            Ok(Collected::Explain(
+                Self::collect(client, scope.sql.as_deref().unwrap_or_default()).await?,
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(s: &str) -> Vec<String> {
+        s.lines().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_split_statements() {
+        let sql = "SELECT 1;\nSELECT 2;\n\n  SELECT 3  ";
+        let statements = ExplainCollector::split_statements(sql);
+        assert_eq!(statements, vec!["SELECT 1", "SELECT 2", "SELECT 3"]);
+    }
+
+    #[test]
+    fn test_split_statements_ignores_quoted_semicolons() {
+        let sql = "SELECT 'a;b' FROM `t;1`; SELECT \"c;d\"";
+        let statements = ExplainCollector::split_statements(sql);
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[0], "SELECT 'a;b' FROM `t;1`");
+    }
+
+    #[test]
+    fn test_split_statements_strips_comments() {
+        let sql = "-- nightly report; v2\nSELECT 1 /* ; */ FROM t;\n-- trailing comment";
+        let statements = ExplainCollector::split_statements(sql);
+        assert_eq!(statements.len(), 1);
+        assert!(statements[0].starts_with("SELECT 1"));
+        assert!(!statements[0].contains("nightly"));
+    }
+
+    #[test]
+    fn test_explain_queries() {
+        assert_eq!(
+            ExplainCollector::build_indexes_query("SELECT 1"),
+            "EXPLAIN indexes = 1 SELECT 1"
+        );
+        assert_eq!(
+            ExplainCollector::build_estimate_query("SELECT 1"),
+            "EXPLAIN ESTIMATE SELECT 1"
+        );
+        assert_eq!(
+            ExplainCollector::build_pipeline_query("SELECT 1"),
+            "EXPLAIN PIPELINE SELECT 1"
+        );
+    }
+
+    #[test]
+    fn test_question_marks_are_escaped_once() {
+        let client = ChClient::new("http://localhost:8123", "default", "", "default");
+        let statement = "SELECT x > 0 ? 'yes' : '?' FROM testdb.events";
+
+        for sql in ExplainCollector::build_explain_queries(statement) {
+            // The driver turns `??` back into `?`: escaped once, the statement
+            // is sent as written
+            let (query, _) = client.query(&sql);
+            assert!(query.sql_display().to_string().ends_with(statement));
+        }
+    }
+
+    #[test]
+    fn test_skip_indexes_query() {
+        let sql = ExplainCollector::build_skip_indexes_query("testdb.events");
+        assert!(sql.contains("system.data_skipping_indices"));
+        assert!(sql.contains("database = 'testdb'"));
+        assert!(sql.contains("table = 'events'"));
+
+        let sql = ExplainCollector::build_skip_indexes_query("events");
+        assert!(sql.contains("database = currentDatabase()"));
+    }
+
+    #[test]
+    fn test_parse_indexes() {
+        let output = lines(
+            "Expression ((Projection + Before ORDER BY))
+  Filter (WHERE)
+    ReadFromMergeTree (testdb.events)
+    Indexes:
+      PrimaryKey
+        Keys:
+          user_id
+        Condition: (user_id in [42, 42])
+        Parts: 3/10
+        Granules: 4/1222
+      Skip
+        Name: idx_payload
+        Description: bloom_filter GRANULARITY 1
+        Parts: 1/3
+        Granules: 1/4",
+        );
+
+        let reads = ExplainCollector::parse_indexes(&output);
+        assert_eq!(reads.len(), 1);
+        assert_eq!(reads[0].table, "testdb.events");
+        assert_eq!(reads[0].indexes.len(), 2);
+
+        let pk = &reads[0].indexes[0];
+        assert_eq!(pk.index_type, "PrimaryKey");
+        assert_eq!(pk.condition.as_deref(), Some("(user_id in [42, 42])"));
+        assert_eq!((pk.parts_selected, pk.parts_total), (3, 10));
+        assert_eq!((pk.granules_selected, pk.granules_total), (4, 1222));
+
+        let skip = &reads[0].indexes[1];
+        assert_eq!(skip.index_type, "Skip");
+        assert_eq!(skip.name.as_deref(), Some("idx_payload"));
+        assert_eq!((skip.granules_selected, skip.granules_total), (1, 4));
+    }
+
+    #[test]
+    fn test_parse_indexes_key_named_like_index_type() {
+        let output = lines(
+            "ReadFromMergeTree (db.t)
+Indexes:
+  PrimaryKey
+    Keys:
+      Skip
+    Condition: true
+    Parts: 5/5
+    Granules: 10/10",
+        );
+
+        let reads = ExplainCollector::parse_indexes(&output);
+        assert_eq!(reads[0].indexes.len(), 1);
+        assert_eq!(reads[0].indexes[0].condition.as_deref(), Some("true"));
+    }
+
+    #[test]
+    fn test_parse_indexes_multiple_reads() {
+        let output = lines(
+            "Union
+  ReadFromMergeTree (db.a)
+  Indexes:
+    PrimaryKey
+      Condition: true
+      Parts: 1/1
+      Granules: 8/8
+  ReadFromMergeTree (db.b)",
+        );
+
+        let reads = ExplainCollector::parse_indexes(&output);
+        assert_eq!(reads.len(), 2);
+        assert_eq!(reads[0].indexes.len(), 1);
+        assert_eq!(reads[1].table, "db.b");
+        assert!(reads[1].indexes.is_empty());
+    }
+
+    #[test]
+    fn test_sql_evidence_covers_all_statements() {
+        let sql = ExplainCollector::sql("SELECT 1; SELECT 2");
+        assert!(sql.contains("EXPLAIN indexes = 1 SELECT 1"));
+        assert!(sql.contains("EXPLAIN PIPELINE SELECT 2"));
+    }
+}
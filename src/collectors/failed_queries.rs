@@ -0,0 +1,167 @@
+use super::{CollectScope, Collected, Collector, QueryWindow};
+use crate::ch::sql::literal_list;
+use crate::ch::ChClient;
+use crate::report::FailedQueryMetrics;
+use anyhow::Result;
+use clickhouse::Row;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+
+/// Collector for failed queries from system.query_log
+///
+/// Reads the same --query-window as the query_log collector and keeps the
+/// --query-limit most frequent failure groups.
+pub struct FailedQueriesCollector;
+
+#[derive(Debug, Row, Deserialize)]
+struct FailedQueryRow {
+    query_fingerprint: String,
+    normalized_query_hash: u64,
+    exception_code: i32,
+    exception_name: String,
+    user: String,
+    failures: u64,
+    first_seen: String,
+    last_seen: String,
+    sample_exception: String,
+    affected_databases: Vec<String>,
+    affected_tables: Vec<String>,
+}
+
+impl FailedQueriesCollector {
+    /// Build the SQL query for failed queries (one row per query, error code and user)
+    pub fn build_query(databases: &[String], window: &QueryWindow) -> String {
+        format!(
+            r#"
+            SELECT
+                normalizeQuery(query) AS query_fingerprint,
+                any(normalized_query_hash) AS normalized_query_hash,
+                exception_code,
+                toString(errorCodeToName(exception_code)) AS exception_name,
+                user,
+                toUInt64(count()) AS failures,
+                toString(min(event_time)) AS first_seen,
+                toString(max(event_time)) AS last_seen,
+                argMax(exception, event_time) AS sample_exception,
+                groupUniqArrayArray(databases) AS affected_databases,
+                groupUniqArrayArray(tables) AS affected_tables
+            FROM system.query_log
+            WHERE
+                type IN ('ExceptionBeforeStart', 'ExceptionWhileProcessing')
+                AND event_date >= toDate(now() - {window_sec})
+                AND event_time >= now() - {window_sec}
+                AND hasAny(databases, [{databases_list}])
+            GROUP BY query_fingerprint, exception_code, user
+            ORDER BY failures DESC
+            LIMIT {limit}
+            "#,
+            databases_list = literal_list(databases),
+            window_sec = window.window_sec,
+            limit = window.limit
+        )
+    }
+
+    /// Collect failed query metrics from ClickHouse
+    pub async fn collect(
+        client: &ChClient,
+        databases: &[String],
+        window: &QueryWindow,
+    ) -> Result<Vec<FailedQueryMetrics>> {
+        let sql = Self::build_query(databases, window);
+        let rows: Vec<FailedQueryRow> = client.fetch_all(&sql).await?;
+
+        Ok(rows.into_iter().map(Self::metrics).collect())
+    }
+
+    fn metrics(row: FailedQueryRow) -> FailedQueryMetrics {
+        let mut databases = row.affected_databases;
+        databases.sort();
+        let mut tables = row.affected_tables;
+        tables.sort();
+        FailedQueryMetrics {
+            query_fingerprint: row.query_fingerprint,
+            normalized_query_hash: row.normalized_query_hash,
+            exception_code: row.exception_code,
+            exception_name: row.exception_name,
+            user: row.user,
+            failures: row.failures,
+            first_seen: row.first_seen,
+            last_seen: row.last_seen,
+            sample_exception: row.sample_exception,
+            databases,
+            tables,
+        }
+    }
+
+    /// Get the SQL query string for evidence tracking
+    pub fn sql(databases: &[String], window: &QueryWindow) -> String {
+        Self::build_query(databases, window)
+    }
+}
+
+impl Collector for FailedQueriesCollector {
+    fn name(&self) -> &'static str {
+        "failed_queries"
+    }
+
+    fn source(&self) -> &'static str {
+        "failed_queries"
+    }
+
+    fn sql(&self, scope: &CollectScope) -> String {
+        Self::sql(&scope.databases, &scope.query_window)
+    }
+
+    fn collect<'a>(
+        &'a self,
+        client: &'a ChClient,
+        scope: &'a CollectScope,
+    ) -> BoxFuture<'a, Result<Collected>> {
+        Box::pin(async move {
+            Ok(Collected::FailedQueries(
+                Self::collect(client, &scope.databases, &scope.query_window).await?,
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_queries_query() {
+        let window = QueryWindow {
+            window_sec: 3600,
+            limit: 30,
+        };
+        let sql = FailedQueriesCollector::build_query(&["testdb".to_string()], &window);
+        assert!(sql.contains("type IN ('ExceptionBeforeStart', 'ExceptionWhileProcessing')"));
+        assert!(sql.contains("GROUP BY query_fingerprint, exception_code, user"));
+        assert!(sql.contains("hasAny(databases, ['testdb'])"));
+        assert!(sql.contains("argMax(exception, event_time) AS sample_exception"));
+        assert!(sql.contains("event_date >= toDate(now() - 3600)"));
+        assert!(sql.contains("event_time >= now() - 3600"));
+        assert!(sql.contains("LIMIT 30"));
+    }
+
+    #[test]
+    fn test_metrics_sorts_affected_tables() {
+        let m = FailedQueriesCollector::metrics(FailedQueryRow {
+            query_fingerprint: "INSERT INTO events VALUES".to_string(),
+            normalized_query_hash: 42,
+            exception_code: 252,
+            exception_name: "TOO_MANY_PARTS".to_string(),
+            user: "etl".to_string(),
+            failures: 12,
+            first_seen: "2024-01-01 00:00:00".to_string(),
+            last_seen: "2024-01-02 00:00:00".to_string(),
+            sample_exception: "Too many parts".to_string(),
+            affected_databases: vec!["testdb".to_string()],
+            affected_tables: vec!["testdb.users".to_string(), "testdb.events".to_string()],
+        });
+
+        assert_eq!(m.tables, vec!["testdb.events", "testdb.users"]);
+        assert_eq!(m.exception_name, "TOO_MANY_PARTS");
+    }
+}
@@ -0,0 +1,292 @@
+use super::tables::{databases, tables_condition};
+use super::{CollectScope, Collected, Collector, TableRef};
+use crate::ch::sql::literal_list;
+use crate::ch::ChClient;
+use crate::report::InsertMetrics;
+use anyhow::Result;
+use clickhouse::Row;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Collector for insert patterns from system.query_log and system.part_log
+///
+/// query_log gives the number and size of INSERT queries per destination
+/// table (the `INSERT INTO` target, not the source or MV tables the query also
+/// touched); part_log (`NewPart` events grouped by query_id) gives the parts and
+/// partitions each insert created. Both are read over --query-window.
+pub struct InsertsCollector;
+
+#[derive(Debug, Row, Deserialize)]
+struct QueryRow {
+    target: String,
+    inserts: u64,
+    async_inserts: u64,
+    rows: u64,
+    median_rows_per_insert: u64,
+}
+
+#[derive(Debug, Row, Deserialize)]
+struct PartsRow {
+    database: String,
+    table: String,
+    inserts: u64,
+    new_parts: u64,
+    multi_partition_inserts: u64,
+    max_partitions_per_insert: u64,
+}
+
+impl InsertsCollector {
+    /// Build the SQL query for INSERT queries per destination table
+    ///
+    /// query_log `tables` also lists the sources of `INSERT ... SELECT` and the
+    /// tables written through materialized views, so the destination is parsed
+    /// from the query text instead.
+    pub fn build_queries_query(tables: &[TableRef], window_sec: u64) -> String {
+        let targets: Vec<String> = tables
+            .iter()
+            .map(|t| format!("{}.{}", t.database, t.table))
+            .collect();
+        format!(
+            r#"
+            WITH replaceRegexpAll(
+                extract(query, '(?i)\\bINSERT\\s+INTO\\s+(?:TABLE\\s+)?([^\\s(]+)'),
+                '[`"]', ''
+            ) AS insert_into
+            SELECT
+                if(position(insert_into, '.') > 0, insert_into,
+                   concat(current_database, '.', insert_into)) AS target,
+                toUInt64(count()) AS inserts,
+                toUInt64(countIf(Settings['async_insert'] = '1')) AS async_inserts,
+                toUInt64(sum(written_rows)) AS rows,
+                toUInt64(median(written_rows)) AS median_rows_per_insert
+            FROM system.query_log
+            WHERE
+                type = 'QueryFinish'
+                AND query_kind = 'Insert'
+                AND event_date >= toDate(now() - {window})
+                AND event_time >= now() - {window}
+                AND hasAny(databases, [{databases}])
+                AND target IN ({targets})
+            GROUP BY target
+            "#,
+            window = window_sec,
+            databases = literal_list(&databases(tables)),
+            targets = literal_list(&targets)
+        )
+    }
+
+    /// Build the SQL query for parts created by inserts
+    pub fn build_parts_query(tables: &[TableRef], window_sec: u64) -> String {
+        format!(
+            r#"
+            SELECT
+                database,
+                table,
+                toUInt64(count()) AS inserts,
+                toUInt64(sum(parts)) AS new_parts,
+                toUInt64(countIf(partitions > 1)) AS multi_partition_inserts,
+                toUInt64(max(partitions)) AS max_partitions_per_insert
+            FROM
+            (
+                SELECT
+                    database,
+                    table,
+                    query_id,
+                    count() AS parts,
+                    uniqExact(partition_id) AS partitions
+                FROM system.part_log
+                WHERE
+                    event_type = 'NewPart'
+                    AND event_date >= toDate(now() - {window})
+                    AND event_time >= now() - {window}
+                    AND {condition}
+                GROUP BY database, table, query_id
+            )
+            GROUP BY database, table
+            ORDER BY database, table
+            "#,
+            window = window_sec,
+            condition = tables_condition(tables)
+        )
+    }
+
+    /// Collect insert metrics from ClickHouse
+    pub async fn collect(
+        client: &ChClient,
+        tables: &[TableRef],
+        window_sec: u64,
+    ) -> Result<Vec<InsertMetrics>> {
+        let queries_sql = Self::build_queries_query(tables, window_sec);
+        let queries: Vec<QueryRow> = client.fetch_all(&queries_sql).await?;
+
+        let parts_sql = Self::build_parts_query(tables, window_sec);
+        let parts: Vec<PartsRow> = client.fetch_all(&parts_sql).await?;
+
+        Ok(Self::merge(tables, window_sec, queries, parts))
+    }
+
+    /// Combine query_log and part_log rows per table, in scope order
+    ///
+    /// Tables with neither inserts nor new parts in the window are left out.
+    fn merge(
+        tables: &[TableRef],
+        window_sec: u64,
+        queries: Vec<QueryRow>,
+        parts: Vec<PartsRow>,
+    ) -> Vec<InsertMetrics> {
+        let mut queries: HashMap<String, QueryRow> =
+            queries.into_iter().map(|q| (q.target.clone(), q)).collect();
+        let mut parts: HashMap<(String, String), PartsRow> = parts
+            .into_iter()
+            .map(|p| ((p.database.clone(), p.table.clone()), p))
+            .collect();
+
+        let mut metrics = Vec::new();
+        for t in tables {
+            let query = queries.remove(&format!("{}.{}", t.database, t.table));
+            let part = parts.remove(&(t.database.clone(), t.table.clone()));
+            if query.is_none() && part.is_none() {
+                continue;
+            }
+
+            let mut m = InsertMetrics {
+                database: t.database.clone(),
+                table: t.table.clone(),
+                window_sec,
+                ..Default::default()
+            };
+            if let Some(q) = query {
+                m.inserts = q.inserts;
+                m.async_inserts = q.async_inserts;
+                m.rows = q.rows;
+                m.median_rows_per_insert = q.median_rows_per_insert;
+            }
+            if let Some(p) = part {
+                // part_log still counts inserts whose query_log entry was flushed away
+                m.inserts = m.inserts.max(p.inserts);
+                m.new_parts = p.new_parts;
+                m.multi_partition_inserts = p.multi_partition_inserts;
+                m.max_partitions_per_insert = p.max_partitions_per_insert;
+                m.parts_per_insert = p.new_parts as f64 / p.inserts.max(1) as f64;
+            }
+            m.inserts_per_sec = m.inserts as f64 / window_sec.max(1) as f64;
+            metrics.push(m);
+        }
+        metrics
+    }
+
+    /// Get the SQL query string for evidence tracking
+    pub fn sql(tables: &[TableRef], window_sec: u64) -> String {
+        format!(
+            "Queries: {} | Parts: {}",
+            Self::build_queries_query(tables, window_sec),
+            Self::build_parts_query(tables, window_sec)
+        )
+    }
+}
+
+impl Collector for InsertsCollector {
+    fn name(&self) -> &'static str {
+        "inserts"
+    }
+
+    fn source(&self) -> &'static str {
+        "inserts"
+    }
+
+    fn sql(&self, scope: &CollectScope) -> String {
+        Self::sql(&scope.tables, scope.query_window.window_sec)
+    }
+
+    fn collect<'a>(
+        &'a self,
+        client: &'a ChClient,
+        scope: &'a CollectScope,
+    ) -> BoxFuture<'a, Result<Collected>> {
+        Box::pin(async move {
+            Ok(Collected::Inserts(
+                Self::collect(client, &scope.tables, scope.query_window.window_sec).await?,
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queries_query() {
+        let sql =
+            InsertsCollector::build_queries_query(&[TableRef::new("testdb", "events")], 86400);
+        assert!(sql.contains("query_kind = 'Insert'"));
+        assert!(!sql.contains("ARRAY JOIN"));
+        assert!(
+            sql.contains(r"extract(query, '(?i)\\bINSERT\\s+INTO\\s+(?:TABLE\\s+)?([^\\s(]+)')")
+        );
+        assert!(sql.contains("concat(current_database, '.', insert_into)) AS target"));
+        assert!(sql.contains("hasAny(databases, ['testdb'])"));
+        assert!(sql.contains("target IN ('testdb.events')"));
+        assert!(sql.contains("event_time >= now() - 86400"));
+    }
+
+    #[test]
+    fn test_parts_query() {
+        let sql = InsertsCollector::build_parts_query(&[TableRef::new("testdb", "events")], 3600);
+        assert!(sql.contains("FROM system.part_log"));
+        assert!(sql.contains("event_type = 'NewPart'"));
+        assert!(sql.contains("GROUP BY database, table, query_id"));
+        assert!(sql.contains("uniqExact(partition_id) AS partitions"));
+        assert!(sql.contains("event_time >= now() - 3600"));
+    }
+
+    #[test]
+    fn test_merge_queries_and_parts() {
+        let tables = [
+            TableRef::new("testdb", "events"),
+            TableRef::new("testdb", "users"),
+            TableRef::new("testdb", "idle"),
+        ];
+        let metrics = InsertsCollector::merge(
+            &tables,
+            86400,
+            vec![QueryRow {
+                target: "testdb.events".to_string(),
+                inserts: 86400,
+                async_inserts: 0,
+                rows: 864000,
+                median_rows_per_insert: 10,
+            }],
+            vec![
+                PartsRow {
+                    database: "testdb".to_string(),
+                    table: "events".to_string(),
+                    inserts: 86000,
+                    new_parts: 172000,
+                    multi_partition_inserts: 100,
+                    max_partitions_per_insert: 3,
+                },
+                PartsRow {
+                    database: "testdb".to_string(),
+                    table: "users".to_string(),
+                    inserts: 10,
+                    new_parts: 10,
+                    multi_partition_inserts: 0,
+                    max_partitions_per_insert: 1,
+                },
+            ],
+        );
+
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(metrics[0].table, "events");
+        assert_eq!(metrics[0].inserts, 86400);
+        assert_eq!(metrics[0].inserts_per_sec, 1.0);
+        assert_eq!(metrics[0].parts_per_insert, 2.0);
+        assert_eq!(metrics[0].max_partitions_per_insert, 3);
+
+        // Only part_log saw inserts into users
+        assert_eq!(metrics[1].inserts, 10);
+        assert_eq!(metrics[1].median_rows_per_insert, 0);
+    }
+}
@@ -0,0 +1,207 @@
+use super::{CollectScope, Collected, Collector};
+use crate::ch::sql::literal_list;
+use crate::ch::ChClient;
+use crate::report::{KafkaAssignment, KafkaConsumerMetrics, KafkaTableMetrics};
+use anyhow::Result;
+use clickhouse::Row;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+
+/// Collector for Kafka engine consumers from system.kafka_consumers
+///
+/// Kafka tables are not MergeTree tables, so they are never discovered;
+/// every Kafka table in the audited databases is reported.
+pub struct KafkaCollector;
+
+#[derive(Debug, Row, Deserialize)]
+struct ConsumerRow {
+    database: String,
+    table: String,
+    consumer_id: String,
+    topics: Vec<String>,
+    partition_ids: Vec<i64>,
+    current_offsets: Vec<i64>,
+    last_poll_time: String,
+    last_commit_time: String,
+    commit_age_sec: u64,
+    num_commits: u64,
+    num_messages_read: u64,
+    last_exception: String,
+    is_currently_used: u8,
+}
+
+impl KafkaCollector {
+    /// Build the SQL query for Kafka consumers (one row per consumer)
+    pub fn build_query(databases: &[String]) -> String {
+        format!(
+            r#"
+            SELECT
+                database,
+                table,
+                consumer_id,
+                assignments.topic AS topics,
+                CAST(assignments.partition_id, 'Array(Int64)') AS partition_ids,
+                CAST(assignments.current_offset, 'Array(Int64)') AS current_offsets,
+                toString(last_poll_time) AS last_poll_time,
+                toString(last_commit_time) AS last_commit_time,
+                toUInt64(greatest(dateDiff('second', last_commit_time, now()), 0)) AS commit_age_sec,
+                toUInt64(num_commits) AS num_commits,
+                toUInt64(num_messages_read) AS num_messages_read,
+                arrayElement(exceptions.text, -1) AS last_exception,
+                toUInt8(is_currently_used) AS is_currently_used
+            FROM system.kafka_consumers
+            WHERE database IN ({databases})
+            ORDER BY database, table, consumer_id
+            "#,
+            databases = literal_list(databases)
+        )
+    }
+
+    /// Collect Kafka consumer metrics from ClickHouse
+    pub async fn collect(
+        client: &ChClient,
+        databases: &[String],
+    ) -> Result<Vec<KafkaTableMetrics>> {
+        let sql = Self::build_query(databases);
+        let rows: Vec<ConsumerRow> = client.fetch_all(&sql).await?;
+
+        Ok(Self::group(rows))
+    }
+
+    /// Group consumer rows (ordered by table) into one entry per table
+    fn group(rows: Vec<ConsumerRow>) -> Vec<KafkaTableMetrics> {
+        let mut tables: Vec<KafkaTableMetrics> = Vec::new();
+
+        for row in rows {
+            let assignments = row
+                .topics
+                .into_iter()
+                .zip(row.partition_ids)
+                .zip(row.current_offsets)
+                .map(|((topic, partition_id), current_offset)| KafkaAssignment {
+                    topic,
+                    partition_id,
+                    current_offset,
+                })
+                .collect();
+            let consumer = KafkaConsumerMetrics {
+                consumer_id: row.consumer_id,
+                assignments,
+                last_poll_time: row.last_poll_time,
+                last_commit_time: row.last_commit_time,
+                commit_age_sec: (row.num_commits > 0).then_some(row.commit_age_sec),
+                num_commits: row.num_commits,
+                num_messages_read: row.num_messages_read,
+                last_exception: if row.last_exception.is_empty() {
+                    None
+                } else {
+                    Some(row.last_exception)
+                },
+                is_currently_used: row.is_currently_used != 0,
+            };
+
+            match tables.last_mut() {
+                Some(t) if t.database == row.database && t.table == row.table => {
+                    t.consumers.push(consumer)
+                }
+                _ => tables.push(KafkaTableMetrics {
+                    database: row.database,
+                    table: row.table,
+                    consumers: vec![consumer],
+                }),
+            }
+        }
+
+        tables
+    }
+
+    /// Get the SQL query string for evidence tracking
+    pub fn sql(databases: &[String]) -> String {
+        Self::build_query(databases)
+    }
+}
+
+impl Collector for KafkaCollector {
+    fn name(&self) -> &'static str {
+        "kafka"
+    }
+
+    fn source(&self) -> &'static str {
+        "system.kafka_consumers"
+    }
+
+    fn sql(&self, scope: &CollectScope) -> String {
+        Self::sql(&scope.databases)
+    }
+
+    fn collect<'a>(
+        &'a self,
+        client: &'a ChClient,
+        scope: &'a CollectScope,
+    ) -> BoxFuture<'a, Result<Collected>> {
+        Box::pin(async move {
+            Ok(Collected::Kafka(
+                Self::collect(client, &scope.databases).await?,
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn consumer(table: &str, consumer_id: &str, num_commits: u64) -> ConsumerRow {
+        ConsumerRow {
+            database: "testdb".to_string(),
+            table: table.to_string(),
+            consumer_id: consumer_id.to_string(),
+            topics: vec!["events".to_string(), "events".to_string()],
+            partition_ids: vec![0, 1],
+            current_offsets: vec![1200, 980],
+            last_poll_time: "2024-01-01 00:00:00".to_string(),
+            last_commit_time: "2024-01-01 00:00:00".to_string(),
+            commit_age_sec: 30,
+            num_commits,
+            num_messages_read: 5000,
+            last_exception: String::new(),
+            is_currently_used: 1,
+        }
+    }
+
+    #[test]
+    fn test_kafka_query() {
+        let sql = KafkaCollector::build_query(&["ingest".to_string()]);
+        assert!(sql.contains("system.kafka_consumers"));
+        assert!(sql.contains("database IN ('ingest')"));
+        assert!(sql.contains("assignments.topic AS topics"));
+        assert!(sql.contains("arrayElement(exceptions.text, -1) AS last_exception"));
+    }
+
+    #[test]
+    fn test_group_consumers_by_table() {
+        let tables = KafkaCollector::group(vec![
+            consumer("events_queue", "c1", 10),
+            consumer("events_queue", "c2", 10),
+            consumer("orders_queue", "c3", 10),
+        ]);
+
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].table, "events_queue");
+        assert_eq!(tables[0].consumers.len(), 2);
+        assert_eq!(tables[1].consumers[0].consumer_id, "c3");
+
+        let assignment = &tables[0].consumers[0].assignments[1];
+        assert_eq!(assignment.partition_id, 1);
+        assert_eq!(assignment.current_offset, 980);
+    }
+
+    #[test]
+    fn test_never_committed_has_no_commit_age() {
+        let tables = KafkaCollector::group(vec![consumer("events_queue", "c1", 0)]);
+        let consumer = &tables[0].consumers[0];
+        assert_eq!(consumer.commit_age_sec, None);
+        assert!(consumer.is_currently_used);
+        assert!(consumer.last_exception.is_none());
+    }
+}
@@ -0,0 +1,272 @@
+use super::tables::tables_condition;
+use super::{CollectScope, Collected, Collector, TableRef};
+use crate::ch::sql::literal_list;
+use crate::ch::ChClient;
+use crate::report::MergeTreeSettingsMetrics;
+use anyhow::Result;
+use clickhouse::Row;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+
+/// Settings that decide when inserts are delayed or rejected with "Too many parts"
+const PART_LIMIT_SETTINGS: &[&str] = &[
+    "parts_to_delay_insert",
+    "parts_to_throw_insert",
+    "max_parts_in_total",
+];
+
+/// Collector for effective part limits from system.merge_tree_settings
+///
+/// Server defaults are combined with the `SETTINGS` clause of each table's
+/// CREATE statement, which takes precedence.
+pub struct MergeTreeSettingsCollector;
+
+#[derive(Debug, Row, Deserialize)]
+struct SettingRow {
+    name: String,
+    value: String,
+}
+
+#[derive(Debug, Row, Deserialize)]
+struct TableRow {
+    database: String,
+    table: String,
+    create_table_query: String,
+}
+
+impl MergeTreeSettingsCollector {
+    /// Build the SQL query for server-wide defaults
+    pub fn build_defaults_query() -> String {
+        format!(
+            r#"
+            SELECT
+                name,
+                value
+            FROM system.merge_tree_settings
+            WHERE name IN ({})
+            "#,
+            literal_list(PART_LIMIT_SETTINGS)
+        )
+    }
+
+    /// Build the SQL query for per-table CREATE statements
+    pub fn build_tables_query(tables: &[TableRef]) -> String {
+        format!(
+            r#"
+            SELECT
+                database,
+                name AS table,
+                create_table_query
+            FROM system.tables
+            WHERE {condition}
+            ORDER BY database, table
+            "#,
+            condition = tables_condition(tables)
+        )
+    }
+
+    /// Collect effective part limits from ClickHouse
+    pub async fn collect(
+        client: &ChClient,
+        tables: &[TableRef],
+    ) -> Result<Vec<MergeTreeSettingsMetrics>> {
+        let defaults_sql = Self::build_defaults_query();
+        let defaults: Vec<SettingRow> = client.fetch_all(&defaults_sql).await?;
+
+        let tables_sql = Self::build_tables_query(tables);
+        let tables: Vec<TableRow> = client.fetch_all(&tables_sql).await?;
+
+        Ok(Self::resolve(&defaults, tables))
+    }
+
+    /// Apply each table's overrides on top of the server defaults
+    fn resolve(defaults: &[SettingRow], tables: Vec<TableRow>) -> Vec<MergeTreeSettingsMetrics> {
+        let mut base = MergeTreeSettingsMetrics::default();
+        for row in defaults {
+            if let Ok(value) = row.value.parse() {
+                set_limit(&mut base, &row.name, value);
+            }
+        }
+
+        tables
+            .into_iter()
+            .map(|row| {
+                let mut metrics = MergeTreeSettingsMetrics {
+                    database: row.database,
+                    table: row.table,
+                    ..base.clone()
+                };
+                for (name, value) in table_settings(&row.create_table_query) {
+                    if let Ok(value) = value.parse() {
+                        if set_limit(&mut metrics, &name, value) {
+                            metrics.overrides.push(name);
+                        }
+                    }
+                }
+                metrics
+            })
+            .collect()
+    }
+
+    /// Get the SQL query string for evidence tracking
+    pub fn sql(tables: &[TableRef]) -> String {
+        format!(
+            "Defaults: {} | Tables: {}",
+            Self::build_defaults_query(),
+            Self::build_tables_query(tables)
+        )
+    }
+}
+
+/// Set one of the part limits by name; false if `name` is not a part limit
+fn set_limit(metrics: &mut MergeTreeSettingsMetrics, name: &str, value: u64) -> bool {
+    match name {
+        "parts_to_delay_insert" => metrics.parts_to_delay_insert = value,
+        "parts_to_throw_insert" => metrics.parts_to_throw_insert = value,
+        "max_parts_in_total" => metrics.max_parts_in_total = value,
+        _ => return false,
+    }
+    true
+}
+
+/// `name = value` pairs from the table-level `SETTINGS` clause of a CREATE statement
+///
+/// The clause is the last one in the statement; column-level `SETTINGS (...)`
+/// come before it. Parsing stops at the first item that is not an assignment
+/// (e.g. a trailing `COMMENT`).
+pub(super) fn table_settings(create_query: &str) -> Vec<(String, String)> {
+    let Some(start) = create_query.rfind(" SETTINGS ") else {
+        return Vec::new();
+    };
+
+    let mut settings = Vec::new();
+    for item in create_query[start + " SETTINGS ".len()..].split(',') {
+        let Some((name, value)) = item.split_once('=') else {
+            break;
+        };
+        let name = name.trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            break;
+        }
+        let value = value.split_whitespace().next().unwrap_or_default();
+        settings.push((name.to_string(), value.trim_matches('\'').to_string()));
+    }
+    settings
+}
+
+impl Collector for MergeTreeSettingsCollector {
+    fn name(&self) -> &'static str {
+        "merge_tree_settings"
+    }
+
+    fn source(&self) -> &'static str {
+        "system.merge_tree_settings"
+    }
+
+    fn sql(&self, scope: &CollectScope) -> String {
+        Self::sql(&scope.tables)
+    }
+
+    fn collect<'a>(
+        &'a self,
+        client: &'a ChClient,
+        scope: &'a CollectScope,
+    ) -> BoxFuture<'a, Result<Collected>> {
+        Box::pin(async move {
+            Ok(Collected::MergeTreeSettings(
+                Self::collect(client, &scope.tables).await?,
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> Vec<SettingRow> {
+        [
+            ("parts_to_delay_insert", "1000"),
+            ("parts_to_throw_insert", "3000"),
+            ("max_parts_in_total", "100000"),
+        ]
+        .iter()
+        .map(|(name, value)| SettingRow {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+        .collect()
+    }
+
+    fn table(create_table_query: &str) -> TableRow {
+        TableRow {
+            database: "testdb".to_string(),
+            table: "events".to_string(),
+            create_table_query: create_table_query.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_defaults_query() {
+        let sql = MergeTreeSettingsCollector::build_defaults_query();
+        assert!(sql.contains("system.merge_tree_settings"));
+        assert!(sql.contains(
+            "name IN ('parts_to_delay_insert', 'parts_to_throw_insert', 'max_parts_in_total')"
+        ));
+    }
+
+    #[test]
+    fn test_tables_query() {
+        let sql =
+            MergeTreeSettingsCollector::build_tables_query(&[TableRef::new("testdb", "events")]);
+        assert!(sql.contains("name AS table"));
+        assert!(sql.contains("(database, table) IN (('testdb', 'events'))"));
+    }
+
+    #[test]
+    fn test_table_settings() {
+        let settings = table_settings(
+            "CREATE TABLE testdb.events (`id` UInt64 SETTINGS (max_compress_block_size = 1024)) ENGINE = MergeTree ORDER BY id SETTINGS index_granularity = 8192, parts_to_throw_insert = 600, storage_policy = 'hot' COMMENT 'raw events'",
+        );
+        assert_eq!(
+            settings,
+            vec![
+                ("index_granularity".to_string(), "8192".to_string()),
+                ("parts_to_throw_insert".to_string(), "600".to_string()),
+                ("storage_policy".to_string(), "hot".to_string()),
+            ]
+        );
+
+        assert!(
+            table_settings("CREATE TABLE t (id UInt64) ENGINE = MergeTree ORDER BY id").is_empty()
+        );
+    }
+
+    #[test]
+    fn test_resolve_applies_overrides() {
+        let metrics = MergeTreeSettingsCollector::resolve(
+            &defaults(),
+            vec![table(
+                "CREATE TABLE testdb.events (id UInt64) ENGINE = MergeTree ORDER BY id SETTINGS index_granularity = 8192, parts_to_throw_insert = 600",
+            )],
+        );
+
+        assert_eq!(metrics[0].parts_to_delay_insert, 1000);
+        assert_eq!(metrics[0].parts_to_throw_insert, 600);
+        assert_eq!(metrics[0].max_parts_in_total, 100000);
+        assert_eq!(metrics[0].overrides, vec!["parts_to_throw_insert"]);
+    }
+
+    #[test]
+    fn test_resolve_without_overrides() {
+        let metrics = MergeTreeSettingsCollector::resolve(
+            &defaults(),
+            vec![table(
+                "CREATE TABLE testdb.events (id UInt64) ENGINE = MergeTree ORDER BY id",
+            )],
+        );
+
+        assert_eq!(metrics[0].parts_to_throw_insert, 3000);
+        assert!(metrics[0].overrides.is_empty());
+    }
+}
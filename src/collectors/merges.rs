@@ -1,7 +1,10 @@
+use super::tables::tables_condition;
+use super::{CollectScope, Collected, Collector, TableRef};
 use crate::ch::ChClient;
 use crate::report::MergeMetrics;
 use anyhow::Result;
 use clickhouse::Row;
+use futures::future::BoxFuture;
 use serde::Deserialize;
 
 /// Collector for merge metrics from system.merges
@@ -19,13 +22,7 @@ struct MergesRow {
 
 impl MergesCollector {
     /// Build the SQL query for merges collection
-    pub fn build_query(database: &str, tables: &[String]) -> String {
-        let tables_list = tables
-            .iter()
-            .map(|t| format!("'{}'", t))
-            .collect::<Vec<_>>()
-            .join(", ");
-
+    pub fn build_query(tables: &[TableRef]) -> String {
         format!(
             r#"
             SELECT
@@ -36,22 +33,17 @@ impl MergesCollector {
                 sum(bytes_read_uncompressed) AS merge_bytes_read,
                 max(elapsed) AS max_merge_elapsed_sec
             FROM system.merges
-            WHERE database = '{database}' AND table IN ({tables_list})
+            WHERE {condition}
             GROUP BY database, table
-            ORDER BY table
+            ORDER BY database, table
             "#,
-            database = database,
-            tables_list = tables_list
+            condition = tables_condition(tables)
         )
     }
 
     /// Collect merge metrics from ClickHouse
-    pub async fn collect(
-        client: &ChClient,
-        database: &str,
-        tables: &[String],
-    ) -> Result<Vec<MergeMetrics>> {
-        let sql = Self::build_query(database, tables);
+    pub async fn collect(client: &ChClient, tables: &[TableRef]) -> Result<Vec<MergeMetrics>> {
+        let sql = Self::build_query(tables);
         let rows: Vec<MergesRow> = client.fetch_all(&sql).await?;
 
         let metrics = rows
@@ -70,8 +62,34 @@ impl MergesCollector {
     }
 
     /// Get the SQL query string for evidence tracking
-    pub fn sql(database: &str, tables: &[String]) -> String {
-        Self::build_query(database, tables)
+    pub fn sql(tables: &[TableRef]) -> String {
+        Self::build_query(tables)
+    }
+}
+
+impl Collector for MergesCollector {
+    fn name(&self) -> &'static str {
+        "merges"
+    }
+
+    fn source(&self) -> &'static str {
+        "system.merges"
+    }
+
+    fn sql(&self, scope: &CollectScope) -> String {
+        Self::sql(&scope.tables)
+    }
+
+    fn collect<'a>(
+        &'a self,
+        client: &'a ChClient,
+        scope: &'a CollectScope,
+    ) -> BoxFuture<'a, Result<Collected>> {
+        Box::pin(async move {
+            Ok(Collected::Merges(
+                Self::collect(client, &scope.tables).await?,
+            ))
+        })
     }
 }
 
@@ -81,13 +99,13 @@ mod tests {
 
     #[test]
     fn test_merges_query_contains_system_merges() {
-        let sql = MergesCollector::build_query("testdb", &["events".to_string()]);
+        let sql = MergesCollector::build_query(&[TableRef::new("testdb", "events")]);
         assert!(sql.contains("system.merges"));
     }
 
     #[test]
     fn test_merges_query_has_aggregations() {
-        let sql = MergesCollector::build_query("db", &["t".to_string()]);
+        let sql = MergesCollector::build_query(&[TableRef::new("db", "t")]);
         assert!(sql.contains("count() AS merges_in_queue"));
         assert!(sql.contains("sum(rows_read)"));
         assert!(sql.contains("max(elapsed)"));
@@ -95,7 +113,7 @@ mod tests {
 
     #[test]
     fn test_merges_query_filters_database() {
-        let sql = MergesCollector::build_query("mydb", &["t".to_string()]);
-        assert!(sql.contains("database = 'mydb'"));
+        let sql = MergesCollector::build_query(&[TableRef::new("mydb", "t")]);
+        assert!(sql.contains("(database, table) IN (('mydb', 't'))"));
     }
 }
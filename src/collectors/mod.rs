@@ -1,15 +1,71 @@
+mod columns;
+mod detached;
+mod dictionaries;
 mod disk;
+mod distribution;
+mod engine;
 mod evidence;
+mod explain;
+mod failed_queries;
+mod inserts;
+mod kafka;
+mod merge_tree_settings;
 mod merges;
 mod mutations;
 mod mv_dag;
 mod parts;
 mod query_log;
+mod replication;
+mod server;
+mod tables;
+mod ttl;
 
+pub use columns::ColumnsCollector;
+pub use detached::DetachedPartsCollector;
+pub use dictionaries::DictionariesCollector;
 pub use disk::DiskCollector;
+pub use distribution::DistributionQueueCollector;
+pub use engine::{CollectScope, Collected, Collector, CollectorRegistry, CollectorRun};
 pub use evidence::EvidenceCollector;
+pub use explain::ExplainCollector;
+pub use failed_queries::FailedQueriesCollector;
+pub use inserts::InsertsCollector;
+pub use kafka::KafkaCollector;
+pub use merge_tree_settings::MergeTreeSettingsCollector;
 pub use merges::MergesCollector;
 pub use mutations::MutationsCollector;
 pub use mv_dag::MvDagCollector;
 pub use parts::PartsCollector;
-pub use query_log::QueryLogCollector;
+pub use query_log::{
+    QueryLogCollector, QueryWindow, DEFAULT_QUERY_LIMIT, DEFAULT_QUERY_WINDOW_SEC,
+};
+pub use replication::ReplicationCollector;
+pub use server::ServerCollector;
+pub use tables::{TableDiscovery, TableFilter, TableRef};
+pub use ttl::TtlCollector;
+
+impl CollectorRegistry {
+    /// Create a registry with all default collectors
+    pub fn with_default_collectors() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(PartsCollector));
+        registry.register(Box::new(MergeTreeSettingsCollector));
+        registry.register(Box::new(ColumnsCollector));
+        registry.register(Box::new(TtlCollector));
+        registry.register(Box::new(DetachedPartsCollector));
+        registry.register(Box::new(MergesCollector));
+        registry.register(Box::new(MutationsCollector));
+        registry.register(Box::new(DiskCollector));
+        registry.register(Box::new(ServerCollector));
+        registry.register(Box::new(QueryLogCollector));
+        registry.register(Box::new(InsertsCollector));
+        registry.register(Box::new(FailedQueriesCollector));
+        registry.register(Box::new(MvDagCollector));
+        registry.register(Box::new(ReplicationCollector));
+        registry.register(Box::new(DistributionQueueCollector));
+        registry.register(Box::new(KafkaCollector));
+        registry.register(Box::new(DictionariesCollector));
+        registry.register(Box::new(ExplainCollector));
+        registry
+    }
+}
@@ -1,10 +1,17 @@
+use super::tables::tables_condition;
+use super::{CollectScope, Collected, Collector, TableRef};
 use crate::ch::ChClient;
-use crate::report::MutationMetrics;
+use crate::report::{ActiveMutation, MutationMetrics};
 use anyhow::Result;
 use clickhouse::Row;
+use futures::future::BoxFuture;
 use serde::Deserialize;
+use std::collections::HashMap;
 
 /// Collector for mutation metrics from system.mutations
+///
+/// Per-table counts come from one query; every mutation that is not done is
+/// listed by a second one.
 pub struct MutationsCollector;
 
 #[derive(Debug, Row, Deserialize)]
@@ -17,15 +24,23 @@ struct MutationsRow {
     oldest_active_mutation_age_sec: Option<u64>,
 }
 
+#[derive(Debug, Row, Deserialize)]
+struct ActiveRow {
+    database: String,
+    table: String,
+    mutation_id: String,
+    command: String,
+    create_time: String,
+    age_sec: u64,
+    parts_to_do: u64,
+    latest_fail_reason: String,
+    latest_fail_time: String,
+    latest_fail_age_sec: u64,
+}
+
 impl MutationsCollector {
     /// Build the SQL query for mutations collection
-    pub fn build_query(database: &str, tables: &[String]) -> String {
-        let tables_list = tables
-            .iter()
-            .map(|t| format!("'{}'", t))
-            .collect::<Vec<_>>()
-            .join(", ");
-
+    pub fn build_query(tables: &[TableRef]) -> String {
         format!(
             r#"
             SELECT
@@ -39,27 +54,74 @@ impl MutationsCollector {
                     is_done = 0
                 ) AS oldest_active_mutation_age_sec
             FROM system.mutations
-            WHERE database = '{database}' AND table IN ({tables_list})
+            WHERE {condition}
             GROUP BY database, table
-            ORDER BY table
+            ORDER BY database, table
+            "#,
+            condition = tables_condition(tables)
+        )
+    }
+
+    /// Build the SQL query listing mutations that are not done
+    pub fn build_active_query(tables: &[TableRef]) -> String {
+        format!(
+            r#"
+            SELECT
+                database,
+                table,
+                mutation_id,
+                command,
+                toString(create_time) AS create_time,
+                toUInt64(greatest(dateDiff('second', create_time, now()), 0)) AS age_sec,
+                toUInt64(parts_to_do) AS parts_to_do,
+                latest_fail_reason,
+                toString(latest_fail_time) AS latest_fail_time,
+                toUInt64(greatest(dateDiff('second', latest_fail_time, now()), 0)) AS latest_fail_age_sec
+            FROM system.mutations
+            WHERE {condition} AND is_done = 0
+            ORDER BY database, table, create_time
             "#,
-            database = database,
-            tables_list = tables_list
+            condition = tables_condition(tables)
         )
     }
 
     /// Collect mutation metrics from ClickHouse
-    pub async fn collect(
-        client: &ChClient,
-        database: &str,
-        tables: &[String],
-    ) -> Result<Vec<MutationMetrics>> {
-        let sql = Self::build_query(database, tables);
+    pub async fn collect(client: &ChClient, tables: &[TableRef]) -> Result<Vec<MutationMetrics>> {
+        let sql = Self::build_query(tables);
         let rows: Vec<MutationsRow> = client.fetch_all(&sql).await?;
 
-        let metrics = rows
-            .into_iter()
+        let active_sql = Self::build_active_query(tables);
+        let active: Vec<ActiveRow> = client.fetch_all(&active_sql).await?;
+
+        Ok(Self::merge(rows, active))
+    }
+
+    /// Attach each active mutation to its table's counts
+    fn merge(rows: Vec<MutationsRow>, active: Vec<ActiveRow>) -> Vec<MutationMetrics> {
+        let mut by_table: HashMap<(String, String), Vec<ActiveMutation>> = HashMap::new();
+        for row in active {
+            // latest_fail_time is the epoch until the first failure
+            let failed = !row.latest_fail_reason.is_empty();
+            by_table
+                .entry((row.database, row.table))
+                .or_default()
+                .push(ActiveMutation {
+                    mutation_id: row.mutation_id,
+                    command: row.command,
+                    create_time: row.create_time,
+                    age_sec: row.age_sec,
+                    parts_to_do: row.parts_to_do,
+                    latest_fail_reason: failed.then_some(row.latest_fail_reason),
+                    latest_fail_time: failed.then_some(row.latest_fail_time),
+                    latest_fail_age_sec: failed.then_some(row.latest_fail_age_sec),
+                });
+        }
+
+        rows.into_iter()
             .map(|row| MutationMetrics {
+                active: by_table
+                    .remove(&(row.database.clone(), row.table.clone()))
+                    .unwrap_or_default(),
                 database: row.database,
                 table: row.table,
                 total_mutations: row.total_mutations,
@@ -67,14 +129,42 @@ impl MutationsCollector {
                 latest_mutation_time: row.latest_mutation_time,
                 oldest_active_mutation_age_sec: row.oldest_active_mutation_age_sec,
             })
-            .collect();
-
-        Ok(metrics)
+            .collect()
     }
 
     /// Get the SQL query string for evidence tracking
-    pub fn sql(database: &str, tables: &[String]) -> String {
-        Self::build_query(database, tables)
+    pub fn sql(tables: &[TableRef]) -> String {
+        format!(
+            "Tables: {} | Active: {}",
+            Self::build_query(tables),
+            Self::build_active_query(tables)
+        )
+    }
+}
+
+impl Collector for MutationsCollector {
+    fn name(&self) -> &'static str {
+        "mutations"
+    }
+
+    fn source(&self) -> &'static str {
+        "system.mutations"
+    }
+
+    fn sql(&self, scope: &CollectScope) -> String {
+        Self::sql(&scope.tables)
+    }
+
+    fn collect<'a>(
+        &'a self,
+        client: &'a ChClient,
+        scope: &'a CollectScope,
+    ) -> BoxFuture<'a, Result<Collected>> {
+        Box::pin(async move {
+            Ok(Collected::Mutations(
+                Self::collect(client, &scope.tables).await?,
+            ))
+        })
     }
 }
 
@@ -84,20 +174,71 @@ mod tests {
 
     #[test]
     fn test_mutations_query_contains_system_mutations() {
-        let sql = MutationsCollector::build_query("testdb", &["events".to_string()]);
+        let sql = MutationsCollector::build_query(&[TableRef::new("testdb", "events")]);
         assert!(sql.contains("system.mutations"));
     }
 
     #[test]
     fn test_mutations_query_counts_active() {
-        let sql = MutationsCollector::build_query("db", &["t".to_string()]);
+        let sql = MutationsCollector::build_query(&[TableRef::new("db", "t")]);
         assert!(sql.contains("countIf(is_done = 0) AS active_mutations"));
     }
 
     #[test]
     fn test_mutations_query_calculates_age() {
-        let sql = MutationsCollector::build_query("db", &["t".to_string()]);
+        let sql = MutationsCollector::build_query(&[TableRef::new("db", "t")]);
         assert!(sql.contains("dateDiff('second', create_time, now())"));
         assert!(sql.contains("oldest_active_mutation_age_sec"));
     }
+
+    #[test]
+    fn test_active_mutations_query() {
+        let sql = MutationsCollector::build_active_query(&[TableRef::new("db", "t")]);
+        assert!(sql.contains("AND is_done = 0"));
+        assert!(sql.contains("latest_fail_reason"));
+        assert!(sql.contains("AS latest_fail_age_sec"));
+        assert!(sql.contains("toUInt64(parts_to_do) AS parts_to_do"));
+    }
+
+    #[test]
+    fn test_merge_attaches_active_mutations() {
+        let counts = |table: &str| MutationsRow {
+            database: "db".to_string(),
+            table: table.to_string(),
+            total_mutations: 3,
+            active_mutations: 1,
+            latest_mutation_time: Some("2024-01-01 00:00:00".to_string()),
+            oldest_active_mutation_age_sec: Some(7200),
+        };
+        let active = |id: &str, reason: &str| ActiveRow {
+            database: "db".to_string(),
+            table: "events".to_string(),
+            mutation_id: id.to_string(),
+            command: "DELETE WHERE user_id = 1".to_string(),
+            create_time: "2024-01-01 00:00:00".to_string(),
+            age_sec: 7200,
+            parts_to_do: 12,
+            latest_fail_reason: reason.to_string(),
+            latest_fail_time: "1970-01-01 00:00:00".to_string(),
+            latest_fail_age_sec: 60,
+        };
+
+        let metrics = MutationsCollector::merge(
+            vec![counts("events"), counts("sessions")],
+            vec![
+                active("mutation_4.txt", ""),
+                active("mutation_5.txt", "Code: 341. Cannot parse"),
+            ],
+        );
+
+        assert_eq!(metrics[0].active.len(), 2);
+        assert_eq!(metrics[0].active[0].latest_fail_reason, None);
+        assert_eq!(metrics[0].active[0].latest_fail_time, None);
+        assert_eq!(metrics[0].active[0].latest_fail_age_sec, None);
+        assert_eq!(
+            metrics[0].active[1].latest_fail_reason.as_deref(),
+            Some("Code: 341. Cannot parse")
+        );
+        assert!(metrics[1].active.is_empty());
+    }
 }
@@ -1,7 +1,10 @@
+use super::{CollectScope, Collected, Collector};
+use crate::ch::sql::literal_list;
 use crate::ch::ChClient;
 use crate::report::{MvDagEdge, MvDagNode, MvDagSection, TableType};
 use anyhow::Result;
 use clickhouse::Row;
+use futures::future::BoxFuture;
 use serde::Deserialize;
 use std::collections::{HashMap, HashSet};
 
@@ -25,7 +28,7 @@ struct DependencyRow {
 
 impl MvDagCollector {
     /// Build the SQL query for tables
-    pub fn build_tables_query(database: &str) -> String {
+    pub fn build_tables_query(databases: &[String]) -> String {
         format!(
             r#"
             SELECT
@@ -33,15 +36,15 @@ impl MvDagCollector {
                 name,
                 engine
             FROM system.tables
-            WHERE database = '{}'
-            ORDER BY name
+            WHERE database IN ({})
+            ORDER BY database, name
             "#,
-            database
+            literal_list(databases)
         )
     }
 
     /// Build the SQL query for dependencies
-    pub fn build_dependencies_query(database: &str) -> String {
+    pub fn build_dependencies_query(databases: &[String]) -> String {
         format!(
             r#"
             SELECT
@@ -56,30 +59,26 @@ impl MvDagCollector {
                     arrayJoin(dependencies_database) AS dep_database,
                     arrayJoin(dependencies_table) AS dep_table
                 FROM system.tables
-                WHERE database = '{}' AND notEmpty(dependencies_table)
+                WHERE database IN ({}) AND notEmpty(dependencies_table)
             )
             "#,
-            database
+            literal_list(databases)
         )
     }
 
     /// Collect MV DAG from ClickHouse
-    pub async fn collect(client: &ChClient, database: &str) -> Result<MvDagSection> {
-        let tables_sql = Self::build_tables_query(database);
+    pub async fn collect(client: &ChClient, databases: &[String]) -> Result<MvDagSection> {
+        let tables_sql = Self::build_tables_query(databases);
         let tables: Vec<TableRow> = client.fetch_all(&tables_sql).await?;
 
-        let deps_sql = Self::build_dependencies_query(database);
+        let deps_sql = Self::build_dependencies_query(databases);
         let deps: Vec<DependencyRow> = client.fetch_all(&deps_sql).await?;
 
-        Ok(Self::build_dag(database, tables, deps))
+        Ok(Self::build_dag(tables, deps))
     }
 
     /// Build DAG from raw data (internal use and testing)
-    fn build_dag(
-        database: &str,
-        tables: Vec<TableRow>,
-        dependencies: Vec<DependencyRow>,
-    ) -> MvDagSection {
+    fn build_dag(tables: Vec<TableRow>, dependencies: Vec<DependencyRow>) -> MvDagSection {
         // Build dependency map: table -> [tables it depends on]
         let mut depends_on: HashMap<String, Vec<String>> = HashMap::new();
         let mut depended_by: HashMap<String, Vec<String>> = HashMap::new();
@@ -96,7 +95,7 @@ impl MvDagCollector {
         }
 
         // Calculate depths using BFS
-        let depths = Self::calculate_depths(&tables, &depends_on, database);
+        let depths = Self::calculate_depths(&tables, &depends_on);
 
         // Build nodes
         let mut mv_count = 0;
@@ -144,7 +143,6 @@ impl MvDagCollector {
     fn calculate_depths(
         tables: &[TableRow],
         depends_on: &HashMap<String, Vec<String>>,
-        database: &str,
     ) -> HashMap<String, usize> {
         let mut depths: HashMap<String, usize> = HashMap::new();
 
@@ -165,7 +163,7 @@ impl MvDagCollector {
         while changed {
             changed = false;
             for (table, deps) in depends_on {
-                if !table.starts_with(&format!("{}.", database)) {
+                if !table_keys.contains(table) {
                     continue;
                 }
 
@@ -186,15 +184,41 @@ impl MvDagCollector {
     }
 
     /// Get SQL for evidence
-    pub fn sql(database: &str) -> String {
+    pub fn sql(databases: &[String]) -> String {
         format!(
             "Tables: {} | Dependencies: {}",
-            Self::build_tables_query(database),
-            Self::build_dependencies_query(database)
+            Self::build_tables_query(databases),
+            Self::build_dependencies_query(databases)
         )
     }
 }
 
+impl Collector for MvDagCollector {
+    fn name(&self) -> &'static str {
+        "mv_dag"
+    }
+
+    fn source(&self) -> &'static str {
+        "system.tables"
+    }
+
+    fn sql(&self, scope: &CollectScope) -> String {
+        Self::sql(&scope.databases)
+    }
+
+    fn collect<'a>(
+        &'a self,
+        client: &'a ChClient,
+        scope: &'a CollectScope,
+    ) -> BoxFuture<'a, Result<Collected>> {
+        Box::pin(async move {
+            Ok(Collected::MvDag(
+                Self::collect(client, &scope.databases).await?,
+            ))
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -219,7 +243,7 @@ mod tests {
     #[test]
     fn test_dag_single_table() {
         let tables = vec![table("events", "MergeTree")];
-        let dag = MvDagCollector::build_dag("testdb", tables, vec![]);
+        let dag = MvDagCollector::build_dag(tables, vec![]);
 
         assert_eq!(dag.nodes.len(), 1);
         assert_eq!(dag.max_depth, 0);
@@ -235,7 +259,7 @@ mod tests {
         ];
         let deps = vec![dep("events_daily", "events")];
 
-        let dag = MvDagCollector::build_dag("testdb", tables, deps);
+        let dag = MvDagCollector::build_dag(tables, deps);
 
         assert_eq!(dag.nodes.len(), 2);
         assert_eq!(dag.total_tables, 1);
@@ -257,7 +281,7 @@ mod tests {
             dep("events_weekly", "events_daily"),
         ];
 
-        let dag = MvDagCollector::build_dag("testdb", tables, deps);
+        let dag = MvDagCollector::build_dag(tables, deps);
 
         assert_eq!(dag.max_depth, 2);
         assert_eq!(dag.edges.len(), 2);
@@ -270,7 +294,7 @@ mod tests {
             table("events_mv", "MaterializedView"),
         ];
 
-        let dag = MvDagCollector::build_dag("testdb", tables, vec![]);
+        let dag = MvDagCollector::build_dag(tables, vec![]);
 
         let events = dag.nodes.iter().find(|n| n.name == "events").unwrap();
         let mv = dag.nodes.iter().find(|n| n.name == "events_mv").unwrap();
@@ -281,15 +305,15 @@ mod tests {
 
     #[test]
     fn test_tables_query() {
-        let sql = MvDagCollector::build_tables_query("testdb");
+        let sql = MvDagCollector::build_tables_query(&["testdb".to_string()]);
         assert!(sql.contains("system.tables"));
-        assert!(sql.contains("database = 'testdb'"));
+        assert!(sql.contains("database IN ('testdb')"));
     }
 
     #[test]
     fn test_dependencies_query() {
-        let sql = MvDagCollector::build_dependencies_query("testdb");
+        let sql = MvDagCollector::build_dependencies_query(&["testdb".to_string()]);
         assert!(sql.contains("dependencies_table"));
-        assert!(sql.contains("database = 'testdb'"));
+        assert!(sql.contains("database IN ('testdb')"));
     }
 }
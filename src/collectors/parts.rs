@@ -1,8 +1,12 @@
+use super::tables::tables_condition;
+use super::{CollectScope, Collected, Collector, TableRef};
 use crate::ch::ChClient;
-use crate::report::PartsMetrics;
+use crate::report::{PartitionMetrics, PartsMetrics};
 use anyhow::Result;
 use clickhouse::Row;
+use futures::future::BoxFuture;
 use serde::Deserialize;
+use std::collections::HashMap;
 
 /// Collector for parts metrics from system.parts
 pub struct PartsCollector;
@@ -19,15 +23,22 @@ struct PartsRow {
     newest_part: String,
 }
 
+#[derive(Debug, Row, Deserialize)]
+struct PartitionRow {
+    database: String,
+    table: String,
+    partition_id: String,
+    partition: String,
+    active_parts: u64,
+    rows: u64,
+    bytes_on_disk: u64,
+    min_part_bytes: u64,
+    max_part_bytes: u64,
+}
+
 impl PartsCollector {
     /// Build the SQL query for parts collection
-    pub fn build_query(database: &str, tables: &[String]) -> String {
-        let tables_list = tables
-            .iter()
-            .map(|t| format!("'{}'", t))
-            .collect::<Vec<_>>()
-            .join(", ");
-
+    pub fn build_query(tables: &[TableRef]) -> String {
         format!(
             r#"
             SELECT
@@ -40,24 +51,48 @@ impl PartsCollector {
                 if(count() > 0, toString(min(modification_time)), '') AS oldest_part,
                 if(count() > 0, toString(max(modification_time)), '') AS newest_part
             FROM system.parts
-            WHERE database = '{database}' AND table IN ({tables_list})
+            WHERE {condition}
             GROUP BY database, table
-            ORDER BY table
+            ORDER BY database, table
+            "#,
+            condition = tables_condition(tables)
+        )
+    }
+
+    /// Build the SQL query for active parts per partition
+    ///
+    /// Inserts are throttled per partition, so a single hot partition matters
+    /// more than the table-level total.
+    pub fn build_partitions_query(tables: &[TableRef]) -> String {
+        format!(
+            r#"
+            SELECT
+                database,
+                table,
+                partition_id,
+                any(partition) AS partition,
+                toUInt64(count()) AS active_parts,
+                toUInt64(sum(rows)) AS rows,
+                toUInt64(sum(bytes_on_disk)) AS bytes_on_disk,
+                toUInt64(min(bytes_on_disk)) AS min_part_bytes,
+                toUInt64(max(bytes_on_disk)) AS max_part_bytes
+            FROM system.parts
+            WHERE active AND {condition}
+            GROUP BY database, table, partition_id
+            ORDER BY database, table, active_parts DESC, partition_id
             "#,
-            database = database,
-            tables_list = tables_list
+            condition = tables_condition(tables)
         )
     }
 
     /// Collect parts metrics from ClickHouse
-    pub async fn collect(
-        client: &ChClient,
-        database: &str,
-        tables: &[String],
-    ) -> Result<Vec<PartsMetrics>> {
-        let sql = Self::build_query(database, tables);
+    pub async fn collect(client: &ChClient, tables: &[TableRef]) -> Result<Vec<PartsMetrics>> {
+        let sql = Self::build_query(tables);
         let rows: Vec<PartsRow> = client.fetch_all(&sql).await?;
 
+        let partitions_sql = Self::build_partitions_query(tables);
+        let partitions: Vec<PartitionRow> = client.fetch_all(&partitions_sql).await?;
+
         let metrics = rows
             .into_iter()
             .map(|row| PartsMetrics {
@@ -77,15 +112,75 @@ impl PartsCollector {
                 } else {
                     Some(row.newest_part)
                 },
+                partitions: Vec::new(),
             })
             .collect();
 
-        Ok(metrics)
+        Ok(Self::attach_partitions(metrics, partitions))
+    }
+
+    /// Attach partition rows (already ordered worst-first) to their tables
+    fn attach_partitions(
+        mut metrics: Vec<PartsMetrics>,
+        partitions: Vec<PartitionRow>,
+    ) -> Vec<PartsMetrics> {
+        let mut by_table: HashMap<(String, String), Vec<PartitionMetrics>> = HashMap::new();
+        for row in partitions {
+            by_table
+                .entry((row.database, row.table))
+                .or_default()
+                .push(PartitionMetrics {
+                    partition_id: row.partition_id,
+                    partition: row.partition,
+                    active_parts: row.active_parts,
+                    rows: row.rows,
+                    bytes_on_disk: row.bytes_on_disk,
+                    min_part_bytes: row.min_part_bytes,
+                    max_part_bytes: row.max_part_bytes,
+                });
+        }
+
+        for m in &mut metrics {
+            if let Some(partitions) = by_table.remove(&(m.database.clone(), m.table.clone())) {
+                m.partitions = partitions;
+            }
+        }
+        metrics
     }
 
     /// Get the SQL query string for evidence tracking
-    pub fn sql(database: &str, tables: &[String]) -> String {
-        Self::build_query(database, tables)
+    pub fn sql(tables: &[TableRef]) -> String {
+        format!(
+            "Tables: {} | Partitions: {}",
+            Self::build_query(tables),
+            Self::build_partitions_query(tables)
+        )
+    }
+}
+
+impl Collector for PartsCollector {
+    fn name(&self) -> &'static str {
+        "parts"
+    }
+
+    fn source(&self) -> &'static str {
+        "system.parts"
+    }
+
+    fn sql(&self, scope: &CollectScope) -> String {
+        Self::sql(&scope.tables)
+    }
+
+    fn collect<'a>(
+        &'a self,
+        client: &'a ChClient,
+        scope: &'a CollectScope,
+    ) -> BoxFuture<'a, Result<Collected>> {
+        Box::pin(async move {
+            Ok(Collected::Parts(
+                Self::collect(client, &scope.tables).await?,
+            ))
+        })
     }
 }
 
@@ -95,35 +190,31 @@ mod tests {
 
     #[test]
     fn test_parts_query_single_table() {
-        let sql = PartsCollector::build_query("testdb", &["events".to_string()]);
+        let sql = PartsCollector::build_query(&[TableRef::new("testdb", "events")]);
 
         assert!(sql.contains("system.parts"));
-        assert!(sql.contains("database = 'testdb'"));
-        assert!(sql.contains("'events'"));
+        assert!(sql.contains("(database, table) IN (('testdb', 'events'))"));
         assert!(sql.contains("toUInt64(count()) AS parts_count"));
         assert!(sql.contains("toUInt64(countIf(active)) AS active_parts"));
     }
 
     #[test]
     fn test_parts_query_multiple_tables() {
-        let sql = PartsCollector::build_query(
-            "testdb",
-            &[
-                "events".to_string(),
-                "users".to_string(),
-                "orders".to_string(),
-            ],
-        );
+        let sql = PartsCollector::build_query(&[
+            TableRef::new("testdb", "events"),
+            TableRef::new("testdb", "users"),
+            TableRef::new("raw", "orders"),
+        ]);
 
-        assert!(sql.contains("'events'"));
-        assert!(sql.contains("'users'"));
-        assert!(sql.contains("'orders'"));
-        assert!(sql.contains("table IN ("));
+        assert!(sql.contains("('testdb', 'events')"));
+        assert!(sql.contains("('testdb', 'users')"));
+        assert!(sql.contains("('raw', 'orders')"));
+        assert!(sql.contains("(database, table) IN ("));
     }
 
     #[test]
     fn test_parts_query_has_aggregations() {
-        let sql = PartsCollector::build_query("db", &["t".to_string()]);
+        let sql = PartsCollector::build_query(&[TableRef::new("db", "t")]);
 
         assert!(sql.contains("toUInt64(sum(rows)) AS total_rows"));
         assert!(sql.contains("toUInt64(sum(bytes_on_disk)) AS bytes_on_disk"));
@@ -133,19 +224,61 @@ mod tests {
 
     #[test]
     fn test_parts_query_group_by() {
-        let sql = PartsCollector::build_query("db", &["t".to_string()]);
+        let sql = PartsCollector::build_query(&[TableRef::new("db", "t")]);
 
         assert!(sql.contains("GROUP BY database, table"));
     }
 
     #[test]
     fn test_sql_evidence_matches_build_query() {
-        let database = "testdb";
-        let tables = vec!["events".to_string()];
+        let tables = vec![TableRef::new("testdb", "events")];
 
-        let sql1 = PartsCollector::build_query(database, &tables);
-        let sql2 = PartsCollector::sql(database, &tables);
+        let sql = PartsCollector::sql(&tables);
+
+        assert!(sql.contains(&PartsCollector::build_query(&tables)));
+        assert!(sql.contains(&PartsCollector::build_partitions_query(&tables)));
+    }
+
+    #[test]
+    fn test_partitions_query() {
+        let sql = PartsCollector::build_partitions_query(&[TableRef::new("db", "t")]);
+
+        assert!(sql.contains("WHERE active AND (database, table) IN (('db', 't'))"));
+        assert!(sql.contains("GROUP BY database, table, partition_id"));
+        assert!(sql.contains("toUInt64(min(bytes_on_disk)) AS min_part_bytes"));
+        assert!(sql.contains("toUInt64(max(bytes_on_disk)) AS max_part_bytes"));
+        assert!(sql.contains("ORDER BY database, table, active_parts DESC"));
+    }
+
+    #[test]
+    fn test_attach_partitions() {
+        let table = |name: &str| PartsMetrics {
+            database: "db".to_string(),
+            table: name.to_string(),
+            ..Default::default()
+        };
+        let partition = |name: &str, id: &str, active_parts| PartitionRow {
+            database: "db".to_string(),
+            table: name.to_string(),
+            partition_id: id.to_string(),
+            partition: id.to_string(),
+            active_parts,
+            rows: 0,
+            bytes_on_disk: 0,
+            min_part_bytes: 0,
+            max_part_bytes: 0,
+        };
+
+        let metrics = PartsCollector::attach_partitions(
+            vec![table("events"), table("users")],
+            vec![
+                partition("events", "202402", 40),
+                partition("events", "202401", 3),
+            ],
+        );
 
-        assert_eq!(sql1, sql2);
+        assert_eq!(metrics[0].partitions.len(), 2);
+        assert_eq!(metrics[0].partitions[0].partition_id, "202402");
+        assert!(metrics[1].partitions.is_empty());
     }
 }
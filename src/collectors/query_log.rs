@@ -1,10 +1,43 @@
+use super::{CollectScope, Collected, Collector};
+use crate::ch::sql::literal_list;
 use crate::ch::ChClient;
 use crate::report::QueryMetrics;
 use anyhow::Result;
 use clickhouse::Row;
+use futures::future::BoxFuture;
 use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Default --query-window: 7 days
+pub const DEFAULT_QUERY_WINDOW_SEC: u64 = 7 * 86400;
+
+/// Default --query-limit
+pub const DEFAULT_QUERY_LIMIT: usize = 20;
+
+/// How much of system.query_log to read (--query-window, --query-limit)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryWindow {
+    /// Length of the current window; the window before it is read for comparison
+    pub window_sec: u64,
+    /// Fingerprints kept, by rows read
+    pub limit: usize,
+}
+
+impl Default for QueryWindow {
+    fn default() -> Self {
+        Self {
+            window_sec: DEFAULT_QUERY_WINDOW_SEC,
+            limit: DEFAULT_QUERY_LIMIT,
+        }
+    }
+}
 
 /// Collector for query metrics from system.query_log
+///
+/// Totals and percentiles cover the current window. The p95 duration is also
+/// computed over the previous window of the same length to spot regressions.
+/// Marks selected per run are compared with the active marks of the tables
+/// read, from system.parts, to detect full scans.
 pub struct QueryLogCollector;
 
 #[derive(Debug, Row, Deserialize)]
@@ -18,73 +51,194 @@ struct QueryLogRow {
     read_amplification: f64,
     avg_memory_bytes: u64,
     sample_query: Option<String>,
+    p50_duration_ms: f64,
+    p95_duration_ms: f64,
+    p99_duration_ms: f64,
+    p50_memory_bytes: u64,
+    p95_memory_bytes: u64,
+    p99_memory_bytes: u64,
+    previous_execution_count: u64,
+    previous_p95_duration_ms: f64,
+    avg_selected_marks: f64,
+    avg_selected_parts: f64,
+    avg_selected_ranges: f64,
+    tables: Vec<String>,
+}
+
+#[derive(Debug, Row, Deserialize)]
+struct TableMarksRow {
+    name: String,
+    marks: u64,
+    bytes_on_disk: u64,
 }
 
 impl QueryLogCollector {
     /// Build the SQL query for query_log collection
-    pub fn build_query(database: &str, limit: usize) -> String {
+    pub fn build_query(databases: &[String], window: &QueryWindow) -> String {
         format!(
             r#"
+            WITH event_time >= now() - INTERVAL {window_sec} SECOND AS in_window
             SELECT
                 normalizeQuery(query) AS query_fingerprint,
-                count() AS execution_count,
-                avg(query_duration_ms) AS avg_duration_ms,
-                sum(read_rows) AS total_read_rows,
-                sum(read_bytes) AS total_read_bytes,
-                sum(result_rows) AS total_result_rows,
-                round(sum(read_rows) / greatest(sum(result_rows), 1), 2) AS read_amplification,
-                toUInt64(avg(memory_usage)) AS avg_memory_bytes,
-                any(query) AS sample_query
+                countIf(in_window) AS execution_count,
+                avgIf(query_duration_ms, in_window) AS avg_duration_ms,
+                sumIf(read_rows, in_window) AS total_read_rows,
+                sumIf(read_bytes, in_window) AS total_read_bytes,
+                sumIf(result_rows, in_window) AS total_result_rows,
+                round(total_read_rows / greatest(total_result_rows, 1), 2) AS read_amplification,
+                toUInt64(avgIf(memory_usage, in_window)) AS avg_memory_bytes,
+                anyIf(query, in_window) AS sample_query,
+                quantilesIf(0.5, 0.95, 0.99)(query_duration_ms, in_window) AS duration_quantiles,
+                toFloat64(duration_quantiles[1]) AS p50_duration_ms,
+                toFloat64(duration_quantiles[2]) AS p95_duration_ms,
+                toFloat64(duration_quantiles[3]) AS p99_duration_ms,
+                quantilesIf(0.5, 0.95, 0.99)(memory_usage, in_window) AS memory_quantiles,
+                toUInt64(memory_quantiles[1]) AS p50_memory_bytes,
+                toUInt64(memory_quantiles[2]) AS p95_memory_bytes,
+                toUInt64(memory_quantiles[3]) AS p99_memory_bytes,
+                countIf(NOT in_window) AS previous_execution_count,
+                if(previous_execution_count > 0,
+                   toFloat64(quantileIf(0.95)(query_duration_ms, NOT in_window)),
+                   0) AS previous_p95_duration_ms,
+                avgIf(ProfileEvents['SelectedMarks'], in_window) AS avg_selected_marks,
+                avgIf(ProfileEvents['SelectedParts'], in_window) AS avg_selected_parts,
+                avgIf(ProfileEvents['SelectedRanges'], in_window) AS avg_selected_ranges,
+                groupUniqArrayArrayIf(tables, in_window) AS tables
             FROM system.query_log
             WHERE
                 type = 'QueryFinish'
                 AND query_kind = 'Select'
-                AND event_date >= today() - 7
-                AND has(databases, '{database}')
+                AND event_date >= toDate(now() - INTERVAL {history_sec} SECOND)
+                AND event_time >= now() - INTERVAL {history_sec} SECOND
+                AND hasAny(databases, [{databases_list}])
             GROUP BY query_fingerprint
+            HAVING execution_count > 0
             ORDER BY total_read_rows DESC
             LIMIT {limit}
             "#,
-            database = database,
-            limit = limit
+            window_sec = window.window_sec,
+            history_sec = window.window_sec * 2,
+            databases_list = literal_list(databases),
+            limit = window.limit
         )
     }
 
-    /// Collect query metrics from ClickHouse
-    pub async fn collect(client: &ChClient, database: &str) -> Result<Vec<QueryMetrics>> {
-        Self::collect_with_limit(client, database, 20).await
+    /// Build the SQL query for active marks per table
+    pub fn build_marks_query(databases: &[String]) -> String {
+        format!(
+            r#"
+            SELECT
+                concat(database, '.', table) AS name,
+                toUInt64(sum(marks)) AS marks,
+                toUInt64(sum(bytes_on_disk)) AS bytes_on_disk
+            FROM system.parts
+            WHERE active AND database IN ({databases_list})
+            GROUP BY database, table
+            "#,
+            databases_list = literal_list(databases)
+        )
     }
 
-    /// Collect query metrics with custom limit
-    pub async fn collect_with_limit(
+    /// Collect query metrics from ClickHouse
+    pub async fn collect(
         client: &ChClient,
-        database: &str,
-        limit: usize,
+        databases: &[String],
+        window: &QueryWindow,
     ) -> Result<Vec<QueryMetrics>> {
-        let sql = Self::build_query(database, limit);
+        let sql = Self::build_query(databases, window);
         let rows: Vec<QueryLogRow> = client.fetch_all(&sql).await?;
 
-        let metrics = rows
+        let marks_sql = Self::build_marks_query(databases);
+        let marks: Vec<TableMarksRow> = client.fetch_all(&marks_sql).await?;
+        let marks: HashMap<String, TableMarksRow> =
+            marks.into_iter().map(|m| (m.name.clone(), m)).collect();
+
+        Ok(rows
             .into_iter()
-            .map(|row| QueryMetrics {
-                query_fingerprint: row.query_fingerprint,
-                execution_count: row.execution_count,
-                avg_duration_ms: row.avg_duration_ms,
-                total_read_rows: row.total_read_rows,
-                total_read_bytes: row.total_read_bytes,
-                total_result_rows: row.total_result_rows,
-                read_amplification: row.read_amplification,
-                avg_memory_bytes: row.avg_memory_bytes,
-                sample_query: row.sample_query,
-            })
-            .collect();
+            .map(|row| Self::metrics(row, window, &marks))
+            .collect())
+    }
 
-        Ok(metrics)
+    /// Build query metrics, with the marks of the tables the query read
+    ///
+    /// For queries over several tables the selected marks are compared with
+    /// the sum of their marks. Tables outside the audited databases are unknown
+    /// and left out.
+    fn metrics(
+        row: QueryLogRow,
+        window: &QueryWindow,
+        marks: &HashMap<String, TableMarksRow>,
+    ) -> QueryMetrics {
+        let read: Vec<&TableMarksRow> = row.tables.iter().filter_map(|t| marks.get(t)).collect();
+        let table_marks: u64 = read.iter().map(|m| m.marks).sum();
+        let table_bytes: u64 = read.iter().map(|m| m.bytes_on_disk).sum();
+
+        QueryMetrics {
+            query_fingerprint: row.query_fingerprint,
+            execution_count: row.execution_count,
+            avg_duration_ms: row.avg_duration_ms,
+            total_read_rows: row.total_read_rows,
+            total_read_bytes: row.total_read_bytes,
+            total_result_rows: row.total_result_rows,
+            read_amplification: row.read_amplification,
+            avg_memory_bytes: row.avg_memory_bytes,
+            sample_query: row.sample_query,
+            window_sec: window.window_sec,
+            p50_duration_ms: row.p50_duration_ms,
+            p95_duration_ms: row.p95_duration_ms,
+            p99_duration_ms: row.p99_duration_ms,
+            p50_memory_bytes: row.p50_memory_bytes,
+            p95_memory_bytes: row.p95_memory_bytes,
+            p99_memory_bytes: row.p99_memory_bytes,
+            previous_execution_count: row.previous_execution_count,
+            previous_p95_duration_ms: (row.previous_execution_count > 0)
+                .then_some(row.previous_p95_duration_ms),
+            avg_selected_marks: row.avg_selected_marks,
+            avg_selected_parts: row.avg_selected_parts,
+            avg_selected_ranges: row.avg_selected_ranges,
+            tables: row.tables,
+            table_marks,
+            table_bytes,
+            // SelectedMarks is missing on older servers and for queries that
+            // never read a MergeTree table: unknown, not a 0% scan
+            marks_scanned_ratio: (table_marks > 0 && row.avg_selected_marks > 0.0)
+                .then(|| (row.avg_selected_marks / table_marks as f64).min(1.0)),
+        }
     }
 
     /// Get the SQL query string for evidence tracking
-    pub fn sql(database: &str) -> String {
-        Self::build_query(database, 20)
+    pub fn sql(databases: &[String], window: &QueryWindow) -> String {
+        format!(
+            "Queries: {} | Marks: {}",
+            Self::build_query(databases, window),
+            Self::build_marks_query(databases)
+        )
+    }
+}
+
+impl Collector for QueryLogCollector {
+    fn name(&self) -> &'static str {
+        "query_log"
+    }
+
+    fn source(&self) -> &'static str {
+        "system.query_log"
+    }
+
+    fn sql(&self, scope: &CollectScope) -> String {
+        Self::sql(&scope.databases, &scope.query_window)
+    }
+
+    fn collect<'a>(
+        &'a self,
+        client: &'a ChClient,
+        scope: &'a CollectScope,
+    ) -> BoxFuture<'a, Result<Collected>> {
+        Box::pin(async move {
+            Ok(Collected::Queries(
+                Self::collect(client, &scope.databases, &scope.query_window).await?,
+            ))
+        })
     }
 }
 
@@ -92,47 +246,174 @@ impl QueryLogCollector {
 mod tests {
     use super::*;
 
+    fn build(databases: &[&str]) -> String {
+        let databases: Vec<String> = databases.iter().map(|d| d.to_string()).collect();
+        QueryLogCollector::build_query(&databases, &QueryWindow::default())
+    }
+
     #[test]
     fn test_querylog_query_contains_system_query_log() {
-        let sql = QueryLogCollector::build_query("testdb", 20);
+        let sql = build(&["testdb"]);
         assert!(sql.contains("system.query_log"));
     }
 
     #[test]
     fn test_querylog_query_normalizes_query() {
-        let sql = QueryLogCollector::build_query("testdb", 20);
+        let sql = build(&["testdb"]);
         assert!(sql.contains("normalizeQuery(query)"));
     }
 
     #[test]
     fn test_querylog_query_calculates_read_amplification() {
-        let sql = QueryLogCollector::build_query("testdb", 20);
-        assert!(sql.contains("sum(read_rows) / greatest(sum(result_rows), 1)"));
+        let sql = build(&["testdb"]);
+        assert!(sql.contains("total_read_rows / greatest(total_result_rows, 1)"));
         assert!(sql.contains("read_amplification"));
     }
 
     #[test]
     fn test_querylog_query_filters_by_database() {
-        let sql = QueryLogCollector::build_query("mydb", 20);
-        assert!(sql.contains("has(databases, 'mydb')"));
+        let sql = build(&["mydb"]);
+        assert!(sql.contains("hasAny(databases, ['mydb'])"));
     }
 
     #[test]
     fn test_querylog_query_filters_select_queries() {
-        let sql = QueryLogCollector::build_query("testdb", 20);
+        let sql = build(&["testdb"]);
         assert!(sql.contains("query_kind = 'Select'"));
         assert!(sql.contains("type = 'QueryFinish'"));
     }
 
     #[test]
     fn test_querylog_query_uses_limit() {
-        let sql = QueryLogCollector::build_query("testdb", 50);
+        let window = QueryWindow {
+            limit: 50,
+            ..Default::default()
+        };
+        let sql = QueryLogCollector::build_query(&["testdb".to_string()], &window);
         assert!(sql.contains("LIMIT 50"));
     }
 
     #[test]
     fn test_querylog_query_orders_by_read_rows() {
-        let sql = QueryLogCollector::build_query("testdb", 20);
+        let sql = build(&["testdb"]);
         assert!(sql.contains("ORDER BY total_read_rows DESC"));
     }
+
+    #[test]
+    fn test_querylog_query_reads_two_windows() {
+        let window = QueryWindow {
+            window_sec: 3600,
+            ..Default::default()
+        };
+        let sql = QueryLogCollector::build_query(&["testdb".to_string()], &window);
+        assert!(sql.contains("event_time >= now() - INTERVAL 3600 SECOND AS in_window"));
+        assert!(sql.contains("AND event_time >= now() - INTERVAL 7200 SECOND"));
+        assert!(sql.contains("quantilesIf(0.5, 0.95, 0.99)(query_duration_ms, in_window)"));
+        assert!(sql.contains("quantileIf(0.95)(query_duration_ms, NOT in_window)"));
+    }
+
+    #[test]
+    fn test_querylog_query_reads_profile_events() {
+        let sql = build(&["testdb"]);
+        assert!(sql.contains("avgIf(ProfileEvents['SelectedMarks'], in_window)"));
+        assert!(sql.contains("avgIf(ProfileEvents['SelectedParts'], in_window)"));
+        assert!(sql.contains("avgIf(ProfileEvents['SelectedRanges'], in_window)"));
+        assert!(sql.contains("groupUniqArrayArrayIf(tables, in_window) AS tables"));
+    }
+
+    #[test]
+    fn test_marks_query() {
+        let sql = QueryLogCollector::build_marks_query(&["testdb".to_string()]);
+        assert!(sql.contains("FROM system.parts"));
+        assert!(sql.contains("WHERE active AND database IN ('testdb')"));
+    }
+
+    fn row(previous_execution_count: u64) -> QueryLogRow {
+        QueryLogRow {
+            query_fingerprint: "SELECT ?".to_string(),
+            execution_count: 10,
+            avg_duration_ms: 120.0,
+            total_read_rows: 1000,
+            total_read_bytes: 8000,
+            total_result_rows: 10,
+            read_amplification: 100.0,
+            avg_memory_bytes: 1024,
+            sample_query: None,
+            p50_duration_ms: 100.0,
+            p95_duration_ms: 300.0,
+            p99_duration_ms: 500.0,
+            p50_memory_bytes: 1000,
+            p95_memory_bytes: 2000,
+            p99_memory_bytes: 3000,
+            previous_execution_count,
+            previous_p95_duration_ms: 0.0,
+            avg_selected_marks: 600.0,
+            avg_selected_parts: 4.0,
+            avg_selected_ranges: 8.0,
+            tables: vec!["testdb.events".to_string(), "testdb.users".to_string()],
+        }
+    }
+
+    fn marks(tables: &[(&str, u64)]) -> HashMap<String, TableMarksRow> {
+        tables
+            .iter()
+            .map(|(name, marks)| {
+                (
+                    name.to_string(),
+                    TableMarksRow {
+                        name: name.to_string(),
+                        marks: *marks,
+                        bytes_on_disk: marks * 1000,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_metrics_without_previous_window() {
+        let window = QueryWindow::default();
+
+        let m = QueryLogCollector::metrics(row(0), &window, &HashMap::new());
+        assert_eq!(m.previous_p95_duration_ms, None);
+        assert_eq!(m.window_sec, DEFAULT_QUERY_WINDOW_SEC);
+
+        let m = QueryLogCollector::metrics(row(5), &window, &HashMap::new());
+        assert_eq!(m.previous_p95_duration_ms, Some(0.0));
+    }
+
+    #[test]
+    fn test_metrics_marks_scanned_ratio() {
+        let window = QueryWindow::default();
+
+        let m = QueryLogCollector::metrics(
+            row(0),
+            &window,
+            &marks(&[("testdb.events", 1000), ("testdb.users", 200)]),
+        );
+        assert_eq!(m.table_marks, 1200);
+        assert_eq!(m.table_bytes, 1_200_000);
+        assert_eq!(m.marks_scanned_ratio, Some(0.5));
+
+        // Only tables in the audited databases count
+        let m = QueryLogCollector::metrics(row(0), &window, &marks(&[("testdb.users", 200)]));
+        assert_eq!(m.marks_scanned_ratio, Some(1.0));
+
+        let m = QueryLogCollector::metrics(row(0), &window, &HashMap::new());
+        assert_eq!(m.marks_scanned_ratio, None);
+    }
+
+    #[test]
+    fn test_metrics_without_selected_marks() {
+        let mut no_marks = row(0);
+        no_marks.avg_selected_marks = 0.0;
+
+        let m = QueryLogCollector::metrics(
+            no_marks,
+            &QueryWindow::default(),
+            &marks(&[("testdb.events", 1000)]),
+        );
+        assert_eq!(m.table_marks, 1000);
+        assert_eq!(m.marks_scanned_ratio, None);
+    }
 }
@@ -0,0 +1,289 @@
+use super::tables::tables_condition;
+use super::{CollectScope, Collected, Collector, TableRef};
+use crate::ch::ChClient;
+use crate::report::{ReplicationMetrics, ReplicationQueueEntry};
+use anyhow::Result;
+use clickhouse::Row;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Collector for replication health from system.replicas and system.replication_queue
+pub struct ReplicationCollector;
+
+#[derive(Debug, Row, Deserialize)]
+struct ReplicaRow {
+    database: String,
+    table: String,
+    replica_name: String,
+    absolute_delay: u64,
+    queue_size: u64,
+    inserts_in_queue: u64,
+    merges_in_queue: u64,
+    is_readonly: u8,
+    is_session_expired: u8,
+}
+
+#[derive(Debug, Row, Deserialize)]
+struct QueueRow {
+    database: String,
+    table: String,
+    entry_type: String,
+    create_time: String,
+    age_sec: u64,
+    num_tries: u64,
+    last_exception: String,
+    retried_entry_type: String,
+    retried_create_time: String,
+    retried_age_sec: u64,
+    max_num_tries: u64,
+    retried_last_exception: String,
+}
+
+impl ReplicationCollector {
+    /// Build the SQL query for replica status
+    pub fn build_replicas_query(tables: &[TableRef]) -> String {
+        format!(
+            r#"
+            SELECT
+                database,
+                table,
+                replica_name,
+                toUInt64(absolute_delay) AS absolute_delay,
+                toUInt64(queue_size) AS queue_size,
+                toUInt64(inserts_in_queue) AS inserts_in_queue,
+                toUInt64(merges_in_queue) AS merges_in_queue,
+                toUInt8(is_readonly) AS is_readonly,
+                toUInt8(is_session_expired) AS is_session_expired
+            FROM system.replicas
+            WHERE {condition}
+            ORDER BY database, table
+            "#,
+            condition = tables_condition(tables)
+        )
+    }
+
+    /// Build the SQL query for the oldest and the most retried replication
+    /// queue entry per table
+    pub fn build_queue_query(tables: &[TableRef]) -> String {
+        format!(
+            r#"
+            SELECT
+                database,
+                table,
+                argMin(type, create_time) AS entry_type,
+                toString(min(create_time)) AS create_time,
+                toUInt64(dateDiff('second', min(create_time), now())) AS age_sec,
+                toUInt64(argMin(num_tries, create_time)) AS num_tries,
+                argMin(last_exception, create_time) AS last_exception,
+                argMax(type, num_tries) AS retried_entry_type,
+                toString(argMax(create_time, num_tries)) AS retried_create_time,
+                toUInt64(dateDiff('second', argMax(create_time, num_tries), now())) AS retried_age_sec,
+                toUInt64(max(num_tries)) AS max_num_tries,
+                argMax(last_exception, num_tries) AS retried_last_exception
+            FROM system.replication_queue
+            WHERE {condition}
+            GROUP BY database, table
+            ORDER BY database, table
+            "#,
+            condition = tables_condition(tables)
+        )
+    }
+
+    /// Collect replication metrics from ClickHouse
+    pub async fn collect(
+        client: &ChClient,
+        tables: &[TableRef],
+    ) -> Result<Vec<ReplicationMetrics>> {
+        let replicas_sql = Self::build_replicas_query(tables);
+        let replicas: Vec<ReplicaRow> = client.fetch_all(&replicas_sql).await?;
+
+        let queue_sql = Self::build_queue_query(tables);
+        let queue: Vec<QueueRow> = client.fetch_all(&queue_sql).await?;
+
+        Ok(Self::merge(replicas, queue))
+    }
+
+    /// Attach the oldest and most retried queue entries to each replica
+    fn merge(replicas: Vec<ReplicaRow>, queue: Vec<QueueRow>) -> Vec<ReplicationMetrics> {
+        let mut entries: HashMap<(String, String), (ReplicationQueueEntry, ReplicationQueueEntry)> =
+            queue
+                .into_iter()
+                .map(|row| {
+                    let oldest = ReplicationQueueEntry {
+                        entry_type: row.entry_type,
+                        create_time: row.create_time,
+                        age_sec: row.age_sec,
+                        num_tries: row.num_tries,
+                        last_exception: non_empty(row.last_exception),
+                    };
+                    let retried = ReplicationQueueEntry {
+                        entry_type: row.retried_entry_type,
+                        create_time: row.retried_create_time,
+                        age_sec: row.retried_age_sec,
+                        num_tries: row.max_num_tries,
+                        last_exception: non_empty(row.retried_last_exception),
+                    };
+                    ((row.database, row.table), (oldest, retried))
+                })
+                .collect();
+
+        replicas
+            .into_iter()
+            .map(|row| {
+                let (oldest, retried) = entries
+                    .remove(&(row.database.clone(), row.table.clone()))
+                    .unzip();
+                ReplicationMetrics {
+                    oldest_queue_entry: oldest,
+                    most_retried_queue_entry: retried,
+                    database: row.database,
+                    table: row.table,
+                    replica_name: row.replica_name,
+                    absolute_delay: row.absolute_delay,
+                    queue_size: row.queue_size,
+                    inserts_in_queue: row.inserts_in_queue,
+                    merges_in_queue: row.merges_in_queue,
+                    is_readonly: row.is_readonly != 0,
+                    is_session_expired: row.is_session_expired != 0,
+                }
+            })
+            .collect()
+    }
+
+    /// Get SQL for evidence
+    pub fn sql(tables: &[TableRef]) -> String {
+        format!(
+            "Replicas: {} | Queue: {}",
+            Self::build_replicas_query(tables),
+            Self::build_queue_query(tables)
+        )
+    }
+}
+
+fn non_empty(exception: String) -> Option<String> {
+    if exception.is_empty() {
+        None
+    } else {
+        Some(exception)
+    }
+}
+
+impl Collector for ReplicationCollector {
+    fn name(&self) -> &'static str {
+        "replication"
+    }
+
+    fn source(&self) -> &'static str {
+        "system.replicas"
+    }
+
+    fn sql(&self, scope: &CollectScope) -> String {
+        Self::sql(&scope.tables)
+    }
+
+    fn collect<'a>(
+        &'a self,
+        client: &'a ChClient,
+        scope: &'a CollectScope,
+    ) -> BoxFuture<'a, Result<Collected>> {
+        Box::pin(async move {
+            Ok(Collected::Replication(
+                Self::collect(client, &scope.tables).await?,
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replica(table: &str) -> ReplicaRow {
+        ReplicaRow {
+            database: "testdb".to_string(),
+            table: table.to_string(),
+            replica_name: "replica_1".to_string(),
+            absolute_delay: 120,
+            queue_size: 4,
+            inserts_in_queue: 3,
+            merges_in_queue: 1,
+            is_readonly: 1,
+            is_session_expired: 0,
+        }
+    }
+
+    fn queue_entry(table: &str, last_exception: &str) -> QueueRow {
+        QueueRow {
+            database: "testdb".to_string(),
+            table: table.to_string(),
+            entry_type: "GET_PART".to_string(),
+            create_time: "2024-01-01 00:00:00".to_string(),
+            age_sec: 7200,
+            num_tries: 42,
+            last_exception: last_exception.to_string(),
+            retried_entry_type: "MERGE_PARTS".to_string(),
+            retried_create_time: "2024-01-01 01:00:00".to_string(),
+            retried_age_sec: 3600,
+            max_num_tries: 5000,
+            retried_last_exception: "Code: 49. Part is covered by another part".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_replicas_query() {
+        let sql = ReplicationCollector::build_replicas_query(&[TableRef::new("testdb", "events")]);
+        assert!(sql.contains("system.replicas"));
+        assert!(sql.contains("(database, table) IN (('testdb', 'events'))"));
+        assert!(sql.contains("absolute_delay"));
+        assert!(sql.contains("toUInt8(is_session_expired)"));
+    }
+
+    #[test]
+    fn test_queue_query_selects_oldest_entry() {
+        let sql = ReplicationCollector::build_queue_query(&[TableRef::new("testdb", "events")]);
+        assert!(sql.contains("system.replication_queue"));
+        assert!(sql.contains("argMin(last_exception, create_time)"));
+        assert!(sql.contains("toUInt64(max(num_tries)) AS max_num_tries"));
+        assert!(sql.contains("argMax(last_exception, num_tries)"));
+        assert!(sql.contains("GROUP BY database, table"));
+    }
+
+    #[test]
+    fn test_merge_attaches_queue_entry() {
+        let metrics = ReplicationCollector::merge(
+            vec![replica("events"), replica("orders")],
+            vec![queue_entry(
+                "events",
+                "Code: 234. No active replica has part",
+            )],
+        );
+
+        assert_eq!(metrics.len(), 2);
+        assert!(metrics[0].is_readonly);
+        assert!(!metrics[0].is_session_expired);
+        let entry = metrics[0].oldest_queue_entry.as_ref().unwrap();
+        assert_eq!(entry.num_tries, 42);
+        assert_eq!(
+            entry.last_exception.as_deref(),
+            Some("Code: 234. No active replica has part")
+        );
+        let retried = metrics[0].most_retried_queue_entry.as_ref().unwrap();
+        assert_eq!(retried.entry_type, "MERGE_PARTS");
+        assert_eq!(retried.num_tries, 5000);
+        assert!(metrics[1].oldest_queue_entry.is_none());
+        assert!(metrics[1].most_retried_queue_entry.is_none());
+    }
+
+    #[test]
+    fn test_merge_empty_exception_is_none() {
+        let metrics =
+            ReplicationCollector::merge(vec![replica("events")], vec![queue_entry("events", "")]);
+        assert!(metrics[0]
+            .oldest_queue_entry
+            .as_ref()
+            .unwrap()
+            .last_exception
+            .is_none());
+    }
+}
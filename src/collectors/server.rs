@@ -0,0 +1,238 @@
+use super::{CollectScope, Collected, Collector};
+use crate::ch::sql::literal_list;
+use crate::ch::ChClient;
+use crate::report::{PoolMetrics, ServerSection};
+use anyhow::Result;
+use clickhouse::Row;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Background pools: report name, task metric, size metric
+const POOLS: &[(&str, &str, &str)] = &[
+    (
+        "merges_mutations",
+        "BackgroundMergesAndMutationsPoolTask",
+        "BackgroundMergesAndMutationsPoolSize",
+    ),
+    (
+        "fetches",
+        "BackgroundFetchesPoolTask",
+        "BackgroundFetchesPoolSize",
+    ),
+    ("moves", "BackgroundMovePoolTask", "BackgroundMovePoolSize"),
+];
+
+const METRICS: &[&str] = &[
+    "MemoryTracking",
+    "OpenFileForRead",
+    "OpenFileForWrite",
+    "BackgroundMergesAndMutationsPoolTask",
+    "BackgroundMergesAndMutationsPoolSize",
+    "BackgroundFetchesPoolTask",
+    "BackgroundFetchesPoolSize",
+    "BackgroundMovePoolTask",
+    "BackgroundMovePoolSize",
+];
+const ASYNC_METRICS: &[&str] = &["OSMemoryTotal"];
+const EVENTS: &[&str] = &[
+    "MarkCacheHits",
+    "MarkCacheMisses",
+    "UncompressedCacheHits",
+    "UncompressedCacheMisses",
+];
+const SETTINGS: &[&str] = &[
+    "max_server_memory_usage",
+    "max_server_memory_usage_to_ram_ratio",
+];
+
+/// Collector for server resource usage from system.metrics,
+/// system.asynchronous_metrics, system.events and system.server_settings
+///
+/// All four are read in one query as (source, name, value) rows.
+pub struct ServerCollector;
+
+#[derive(Debug, Row, Deserialize)]
+struct ValueRow {
+    source: String,
+    name: String,
+    value: f64,
+}
+
+impl ServerCollector {
+    /// Build the SQL query for server-wide values
+    pub fn build_query() -> String {
+        format!(
+            r#"
+            SELECT 'metrics' AS source, metric AS name, toFloat64(value) AS value
+            FROM system.metrics
+            WHERE metric IN ({metrics})
+            UNION ALL
+            SELECT 'asynchronous_metrics' AS source, metric AS name, toFloat64(value) AS value
+            FROM system.asynchronous_metrics
+            WHERE metric IN ({async_metrics})
+            UNION ALL
+            SELECT 'events' AS source, event AS name, toFloat64(value) AS value
+            FROM system.events
+            WHERE event IN ({events})
+            UNION ALL
+            SELECT 'server_settings' AS source, name, toFloat64OrZero(value) AS value
+            FROM system.server_settings
+            WHERE name IN ({settings})
+            "#,
+            metrics = literal_list(METRICS),
+            async_metrics = literal_list(ASYNC_METRICS),
+            events = literal_list(EVENTS),
+            settings = literal_list(SETTINGS)
+        )
+    }
+
+    /// Collect server resource usage from ClickHouse
+    pub async fn collect(client: &ChClient) -> Result<ServerSection> {
+        let sql = Self::build_query();
+        let rows: Vec<ValueRow> = client.fetch_all(&sql).await?;
+
+        Ok(Self::resolve(rows))
+    }
+
+    /// Turn (source, name, value) rows into the server section
+    fn resolve(rows: Vec<ValueRow>) -> ServerSection {
+        let values: HashMap<(String, String), f64> = rows
+            .into_iter()
+            .map(|row| ((row.source, row.name), row.value))
+            .collect();
+        let get = |source: &str, name: &str| {
+            values
+                .get(&(source.to_string(), name.to_string()))
+                .copied()
+                .unwrap_or_default()
+        };
+        let hit_ratio = |hits: &str, misses: &str| {
+            let (hits, misses) = (get("events", hits), get("events", misses));
+            (hits + misses > 0.0).then(|| hits / (hits + misses))
+        };
+
+        // 0 means "derive from RAM"
+        let memory_limit = match get("server_settings", "max_server_memory_usage") {
+            limit if limit > 0.0 => limit,
+            _ => {
+                get("server_settings", "max_server_memory_usage_to_ram_ratio")
+                    * get("asynchronous_metrics", "OSMemoryTotal")
+            }
+        };
+
+        ServerSection {
+            memory_tracked: get("metrics", "MemoryTracking").max(0.0) as u64,
+            memory_limit: memory_limit as u64,
+            pools: POOLS
+                .iter()
+                .map(|(name, task, size)| PoolMetrics {
+                    name: name.to_string(),
+                    tasks: get("metrics", task) as u64,
+                    size: get("metrics", size) as u64,
+                })
+                .collect(),
+            mark_cache_hit_ratio: hit_ratio("MarkCacheHits", "MarkCacheMisses"),
+            uncompressed_cache_hit_ratio: hit_ratio(
+                "UncompressedCacheHits",
+                "UncompressedCacheMisses",
+            ),
+            open_files: (get("metrics", "OpenFileForRead") + get("metrics", "OpenFileForWrite"))
+                as u64,
+        }
+    }
+
+    /// Get the SQL query string for evidence tracking
+    pub fn sql() -> String {
+        Self::build_query()
+    }
+}
+
+impl Collector for ServerCollector {
+    fn name(&self) -> &'static str {
+        "server"
+    }
+
+    fn source(&self) -> &'static str {
+        "server"
+    }
+
+    fn sql(&self, _scope: &CollectScope) -> String {
+        Self::sql()
+    }
+
+    fn collect<'a>(
+        &'a self,
+        client: &'a ChClient,
+        _scope: &'a CollectScope,
+    ) -> BoxFuture<'a, Result<Collected>> {
+        Box::pin(async move { Ok(Collected::Server(Self::collect(client).await?)) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(source: &str, name: &str, value: f64) -> ValueRow {
+        ValueRow {
+            source: source.to_string(),
+            name: name.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn test_server_query() {
+        let sql = ServerCollector::build_query();
+        assert!(sql.contains("FROM system.metrics"));
+        assert!(sql.contains("FROM system.asynchronous_metrics"));
+        assert!(sql.contains("FROM system.events"));
+        assert!(sql.contains("FROM system.server_settings"));
+        assert!(sql.contains("'BackgroundMergesAndMutationsPoolSize'"));
+        assert!(sql.contains("'MarkCacheHits', 'MarkCacheMisses'"));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let server = ServerCollector::resolve(vec![
+            row("metrics", "MemoryTracking", 8e9),
+            row("metrics", "BackgroundMergesAndMutationsPoolTask", 32.0),
+            row("metrics", "BackgroundMergesAndMutationsPoolSize", 32.0),
+            row("metrics", "OpenFileForRead", 100.0),
+            row("metrics", "OpenFileForWrite", 20.0),
+            row("events", "MarkCacheHits", 90.0),
+            row("events", "MarkCacheMisses", 10.0),
+            row("server_settings", "max_server_memory_usage", 0.0),
+            row(
+                "server_settings",
+                "max_server_memory_usage_to_ram_ratio",
+                0.9,
+            ),
+            row("asynchronous_metrics", "OSMemoryTotal", 10e9),
+        ]);
+
+        assert_eq!(server.memory_tracked, 8_000_000_000);
+        assert_eq!(server.memory_limit, 9_000_000_000);
+        assert_eq!(server.pools[0].name, "merges_mutations");
+        assert_eq!(server.pools[0].tasks, 32);
+        assert_eq!(server.pools[1].size, 0);
+        assert_eq!(server.mark_cache_hit_ratio, Some(0.9));
+        assert_eq!(server.uncompressed_cache_hit_ratio, None);
+        assert_eq!(server.open_files, 120);
+    }
+
+    #[test]
+    fn test_resolve_explicit_memory_limit() {
+        let server = ServerCollector::resolve(vec![
+            row("server_settings", "max_server_memory_usage", 4e9),
+            row(
+                "server_settings",
+                "max_server_memory_usage_to_ram_ratio",
+                0.9,
+            ),
+            row("asynchronous_metrics", "OSMemoryTotal", 10e9),
+        ]);
+        assert_eq!(server.memory_limit, 4_000_000_000);
+    }
+}
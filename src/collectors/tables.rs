@@ -0,0 +1,415 @@
+use crate::ch::sql::{literal, literal_list};
+use crate::ch::ChClient;
+use crate::config::glob_match;
+use anyhow::Result;
+use clickhouse::Row;
+use serde::Deserialize;
+use std::fmt;
+
+/// Databases never audited when matching `--db '*'`
+const SYSTEM_DATABASES: &[&str] = &["system", "INFORMATION_SCHEMA", "information_schema"];
+
+/// Prefix marking an include/exclude pattern as a regular expression
+const REGEX_PREFIX: &str = "re:";
+
+/// A table selected for auditing
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TableRef {
+    pub database: String,
+    pub table: String,
+}
+
+impl TableRef {
+    pub fn new(database: &str, table: &str) -> Self {
+        Self {
+            database: database.to_string(),
+            table: table.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for TableRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.database, self.table)
+    }
+}
+
+/// Which tables to audit
+///
+/// Patterns match either the table name or `database.table`. They are globs
+/// (`*`, `?`) unless prefixed with `re:`, in which case ClickHouse evaluates
+/// them as regular expressions.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableFilter {
+    /// Database names or globs, e.g. `analytics` or `*`
+    pub databases: Vec<String>,
+    /// Explicit tables (`table` or `database.table`); empty means all
+    pub tables: Vec<String>,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl TableFilter {
+    /// Explicitly requested tables that are not among the discovered ones
+    pub fn unmatched(&self, found: &[TableRef]) -> Vec<String> {
+        self.tables
+            .iter()
+            .filter(|name| !found.iter().any(|t| names_table(name, t)))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Discovers MergeTree-family tables from system.tables
+pub struct TableDiscovery;
+
+#[derive(Debug, Row, Deserialize)]
+struct TableRow {
+    database: String,
+    name: String,
+}
+
+#[derive(Debug, Row, Deserialize)]
+struct DatabaseRow {
+    name: String,
+}
+
+impl TableDiscovery {
+    /// Build the SQL query for table discovery.
+    ///
+    /// All include patterns are applied here as one OR condition, globs as
+    /// `LIKE`; regex excludes are applied here and glob excludes by `select`.
+    pub fn build_query(filter: &TableFilter) -> String {
+        let mut conditions = vec![
+            "engine LIKE '%MergeTree'".to_string(),
+            "NOT is_temporary".to_string(),
+            format!("database NOT IN ({})", literal_list(SYSTEM_DATABASES)),
+        ];
+
+        let includes: Vec<String> = regexes(&filter.include)
+            .map(regex_condition)
+            .chain(globs(&filter.include).map(glob_condition))
+            .collect();
+        if !includes.is_empty() {
+            conditions.push(format!("({})", includes.join(" OR ")));
+        }
+        for re in regexes(&filter.exclude) {
+            conditions.push(format!("NOT {}", regex_condition(re)));
+        }
+
+        format!(
+            r#"
+            SELECT database, name
+            FROM system.tables
+            WHERE {}
+            ORDER BY database, name
+            "#,
+            conditions.join("\n              AND ")
+        )
+    }
+
+    /// Build the SQL query for database discovery
+    pub fn build_databases_query() -> String {
+        format!(
+            r#"
+            SELECT name
+            FROM system.databases
+            WHERE name NOT IN ({})
+            ORDER BY name
+            "#,
+            literal_list(SYSTEM_DATABASES)
+        )
+    }
+
+    /// Keep the databases matching the filter's names or globs
+    pub fn select_databases(names: Vec<String>, filter: &TableFilter) -> Vec<String> {
+        names
+            .into_iter()
+            .filter(|name| filter.databases.iter().any(|db| glob_match(db, name)))
+            .collect()
+    }
+
+    /// Discover the databases to audit, including those without MergeTree
+    /// tables (e.g. only Distributed or Kafka tables)
+    pub async fn discover_databases(
+        client: &ChClient,
+        filter: &TableFilter,
+    ) -> Result<Vec<String>> {
+        let sql = Self::build_databases_query();
+        let rows: Vec<DatabaseRow> = client.fetch_all(&sql).await?;
+
+        Ok(Self::select_databases(
+            rows.into_iter().map(|row| row.name).collect(),
+            filter,
+        ))
+    }
+
+    /// Apply database, table and glob exclude filters to discovered tables
+    pub fn select(tables: Vec<TableRef>, filter: &TableFilter) -> Vec<TableRef> {
+        tables
+            .into_iter()
+            .filter(|t| {
+                filter
+                    .databases
+                    .iter()
+                    .any(|db| glob_match(db, &t.database))
+            })
+            .filter(|t| filter.tables.is_empty() || filter.tables.iter().any(|n| names_table(n, t)))
+            .filter(|t| !globs(&filter.exclude).any(|p| matches_glob(p, t)))
+            .collect()
+    }
+
+    /// Discover the tables to audit
+    pub async fn discover(client: &ChClient, filter: &TableFilter) -> Result<Vec<TableRef>> {
+        let sql = Self::build_query(filter);
+        let rows: Vec<TableRow> = client.fetch_all(&sql).await?;
+
+        let tables = rows
+            .into_iter()
+            .map(|row| TableRef::new(&row.database, &row.name))
+            .collect();
+
+        Ok(Self::select(tables, filter))
+    }
+}
+
+/// SQL condition matching tables against a list: `(database, table) IN (...)`
+pub fn tables_condition(tables: &[TableRef]) -> String {
+    if tables.is_empty() {
+        return "0".to_string();
+    }
+
+    let list = tables
+        .iter()
+        .map(|t| format!("({}, {})", literal(&t.database), literal(&t.table)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("(database, table) IN ({})", list)
+}
+
+/// Distinct databases of the given tables, in order
+pub fn databases(tables: &[TableRef]) -> Vec<String> {
+    let mut databases: Vec<String> = tables.iter().map(|t| t.database.clone()).collect();
+    databases.sort();
+    databases.dedup();
+    databases
+}
+
+fn names_table(name: &str, table: &TableRef) -> bool {
+    match name.split_once('.') {
+        Some((database, name)) => database == table.database && name == table.table,
+        None => name == table.table,
+    }
+}
+
+fn matches_glob(pattern: &str, table: &TableRef) -> bool {
+    glob_match(pattern, &table.table) || glob_match(pattern, &table.to_string())
+}
+
+fn globs(patterns: &[String]) -> impl Iterator<Item = &str> {
+    patterns
+        .iter()
+        .filter(|p| !p.starts_with(REGEX_PREFIX))
+        .map(String::as_str)
+}
+
+fn regexes(patterns: &[String]) -> impl Iterator<Item = &str> {
+    patterns.iter().filter_map(|p| p.strip_prefix(REGEX_PREFIX))
+}
+
+fn regex_condition(re: &str) -> String {
+    format!(
+        "(match(name, {re}) OR match(concat(database, '.', name), {re}))",
+        re = literal(re)
+    )
+}
+
+fn glob_condition(glob: &str) -> String {
+    let mut like = String::new();
+    for c in glob.chars() {
+        match c {
+            '*' => like.push('%'),
+            '?' => like.push('_'),
+            '%' | '_' | '\\' => {
+                like.push('\\');
+                like.push(c);
+            }
+            c => like.push(c),
+        }
+    }
+    format!(
+        "(name LIKE {like} OR concat(database, '.', name) LIKE {like})",
+        like = literal(&like)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn discovered() -> Vec<TableRef> {
+        vec![
+            TableRef::new("analytics", "events"),
+            TableRef::new("analytics", "events_tmp"),
+            TableRef::new("analytics", "orders"),
+            TableRef::new("raw", "events"),
+            TableRef::new("staging", "events"),
+        ]
+    }
+
+    fn filter(databases: &[&str]) -> TableFilter {
+        TableFilter {
+            databases: databases.iter().map(|d| d.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn names(tables: &[TableRef]) -> Vec<String> {
+        tables.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_discovery_query_filters_mergetree() {
+        let sql = TableDiscovery::build_query(&filter(&["*"]));
+        assert!(sql.contains("system.tables"));
+        assert!(sql.contains("engine LIKE '%MergeTree'"));
+        assert!(sql.contains("database NOT IN ('system'"));
+        assert!(!sql.contains("match("));
+    }
+
+    #[test]
+    fn test_discovery_query_regex_patterns() {
+        let mut f = filter(&["*"]);
+        f.include = vec!["re:^events".to_string(), "orders".to_string()];
+        f.exclude = vec!["re:_tmp$".to_string()];
+
+        let sql = TableDiscovery::build_query(&f);
+        assert!(sql.contains("(match(name, '^events')"));
+        assert!(sql.contains("NOT (match(name, '_tmp$')"));
+        assert!(sql.contains("OR (name LIKE 'orders'"));
+    }
+
+    #[test]
+    fn test_discovery_query_includes_are_a_union() {
+        let mut f = filter(&["*"]);
+        f.include = vec!["events*".to_string(), "re:^orders_".to_string()];
+
+        let sql = TableDiscovery::build_query(&f);
+        assert!(sql.contains(
+            "AND ((match(name, '^orders_') OR match(concat(database, '.', name), '^orders_')) OR (name LIKE 'events%' OR concat(database, '.', name) LIKE 'events%'))"
+        ));
+        // Include globs are not required again client-side
+        let tables = TableDiscovery::select(vec![TableRef::new("analytics", "orders_v2")], &f);
+        assert_eq!(names(&tables), vec!["analytics.orders_v2"]);
+    }
+
+    #[test]
+    fn test_glob_condition_escapes_like_wildcards() {
+        assert_eq!(
+            glob_condition("raw_ev?nts*"),
+            r"(name LIKE 'raw\\_ev_nts%' OR concat(database, '.', name) LIKE 'raw\\_ev_nts%')"
+        );
+    }
+
+    #[test]
+    fn test_regex_condition_escapes_quotes() {
+        assert!(regex_condition(r"it's\d").contains(r"'it\'s\\d'"));
+    }
+
+    #[test]
+    fn test_select_single_database() {
+        let tables = TableDiscovery::select(discovered(), &filter(&["analytics"]));
+        assert_eq!(
+            names(&tables),
+            vec![
+                "analytics.events",
+                "analytics.events_tmp",
+                "analytics.orders"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_multiple_and_wildcard_databases() {
+        let tables = TableDiscovery::select(discovered(), &filter(&["analytics", "raw"]));
+        assert_eq!(tables.len(), 4);
+
+        let tables = TableDiscovery::select(discovered(), &filter(&["*"]));
+        assert_eq!(tables.len(), 5);
+    }
+
+    #[test]
+    fn test_select_databases() {
+        let names = || {
+            vec![
+                "analytics".to_string(),
+                "ingest".to_string(),
+                "raw".to_string(),
+            ]
+        };
+        assert!(TableDiscovery::build_databases_query().contains("FROM system.databases"));
+        assert_eq!(
+            TableDiscovery::select_databases(names(), &filter(&["ingest"])),
+            vec!["ingest"]
+        );
+        assert_eq!(
+            TableDiscovery::select_databases(names(), &filter(&["*"])).len(),
+            3
+        );
+        assert!(TableDiscovery::select_databases(names(), &filter(&["nope"])).is_empty());
+    }
+
+    #[test]
+    fn test_select_explicit_tables() {
+        let mut f = filter(&["*"]);
+        f.tables = vec!["orders".to_string(), "raw.events".to_string()];
+
+        let tables = TableDiscovery::select(discovered(), &f);
+        assert_eq!(names(&tables), vec!["analytics.orders", "raw.events"]);
+    }
+
+    #[test]
+    fn test_select_exclude_globs() {
+        let mut f = filter(&["*"]);
+        f.exclude = vec!["*_tmp".to_string(), "staging.*".to_string()];
+
+        let tables = TableDiscovery::select(discovered(), &f);
+        assert_eq!(
+            names(&tables),
+            vec!["analytics.events", "analytics.orders", "raw.events"]
+        );
+    }
+
+    #[test]
+    fn test_unmatched_tables() {
+        let mut f = filter(&["analytics"]);
+        f.tables = vec!["events".to_string(), "missing".to_string()];
+
+        let found = TableDiscovery::select(discovered(), &f);
+        assert_eq!(f.unmatched(&found), vec!["missing"]);
+    }
+
+    #[test]
+    fn test_tables_condition() {
+        let sql = tables_condition(&[
+            TableRef::new("analytics", "events"),
+            TableRef::new("raw", "events"),
+        ]);
+        assert_eq!(
+            sql,
+            "(database, table) IN (('analytics', 'events'), ('raw', 'events'))"
+        );
+        assert_eq!(tables_condition(&[]), "0");
+
+        let sql = tables_condition(&[TableRef::new("db", "x') OR 1=1 --")]);
+        assert_eq!(sql, r"(database, table) IN (('db', 'x\') OR 1=1 --'))");
+    }
+
+    #[test]
+    fn test_databases_are_distinct() {
+        assert_eq!(
+            databases(&discovered()),
+            vec!["analytics", "raw", "staging"]
+        );
+    }
+}
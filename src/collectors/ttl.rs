@@ -0,0 +1,306 @@
+use super::merge_tree_settings::table_settings;
+use super::tables::tables_condition;
+use super::{CollectScope, Collected, Collector, TableRef};
+use crate::ch::ChClient;
+use crate::report::TtlMetrics;
+use anyhow::Result;
+use clickhouse::Row;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Collector for table TTL clauses (system.tables) and TTL state of parts (system.parts)
+pub struct TtlCollector;
+
+#[derive(Debug, Row, Deserialize)]
+struct TableRow {
+    database: String,
+    table: String,
+    create_table_query: String,
+    partition_key: String,
+    default_ttl_only_drop_parts: u8,
+}
+
+#[derive(Debug, Default, Row, Deserialize)]
+struct PartsRow {
+    database: String,
+    table: String,
+    bytes_on_disk: u64,
+    newest_part_age_sec: u64,
+    expired_parts: u64,
+    expired_bytes: u64,
+    oldest_expired_age_sec: u64,
+    unmoved_parts: u64,
+    unmoved_bytes: u64,
+    oldest_unmoved_age_sec: u64,
+}
+
+impl TtlCollector {
+    /// Build the SQL query for table definitions
+    pub fn build_tables_query(tables: &[TableRef]) -> String {
+        format!(
+            r#"
+            SELECT
+                database,
+                name AS table,
+                create_table_query,
+                partition_key,
+                toUInt8((
+                    SELECT any(value) FROM system.merge_tree_settings WHERE name = 'ttl_only_drop_parts'
+                ) = '1') AS default_ttl_only_drop_parts
+            FROM system.tables
+            WHERE {condition}
+            ORDER BY database, table
+            "#,
+            condition = tables_condition(tables)
+        )
+    }
+
+    /// Build the SQL query for TTL state of active parts
+    ///
+    /// A part is "expired" once every row is past the delete TTL (it should be
+    /// dropped whole), and "unmoved" when a move TTL has passed but the part is
+    /// still on the first volume of the table's own storage policy. Ages are -1
+    /// otherwise.
+    pub fn build_parts_query(tables: &[TableRef]) -> String {
+        format!(
+            r#"
+            SELECT
+                database,
+                table,
+                toUInt64(sum(bytes_on_disk)) AS bytes_on_disk,
+                toUInt64(greatest(dateDiff('second', max(modification_time), now()), 0)) AS newest_part_age_sec,
+                toUInt64(countIf(expired_age >= 0)) AS expired_parts,
+                toUInt64(sumIf(bytes_on_disk, expired_age >= 0)) AS expired_bytes,
+                toUInt64(greatest(max(expired_age), 0)) AS oldest_expired_age_sec,
+                toUInt64(countIf(unmoved_age >= 0)) AS unmoved_parts,
+                toUInt64(sumIf(bytes_on_disk, unmoved_age >= 0)) AS unmoved_bytes,
+                toUInt64(greatest(max(unmoved_age), 0)) AS oldest_unmoved_age_sec
+            FROM
+            (
+                SELECT
+                    p.database AS database,
+                    p.table AS table,
+                    p.bytes_on_disk AS bytes_on_disk,
+                    p.modification_time AS modification_time,
+                    if(p.delete_ttl_info_max > toDateTime(0) AND p.delete_ttl_info_max < now(),
+                       dateDiff('second', p.delete_ttl_info_max, now()), -1) AS expired_age,
+                    if(has(v.disks, p.disk_name),
+                       arrayMax(arrayPushFront(
+                           arrayMap(t -> if(t > toDateTime(0) AND t < now(), dateDiff('second', t, now()), -1),
+                                    p.move_ttl_max),
+                           -1)),
+                       -1) AS unmoved_age
+                FROM
+                (
+                    SELECT database, table, bytes_on_disk, modification_time, delete_ttl_info_max,
+                           disk_name, move_ttl_info.max AS move_ttl_max
+                    FROM system.parts
+                    WHERE active AND {condition}
+                ) AS p
+                LEFT JOIN
+                (
+                    SELECT database, table, storage_policy
+                    FROM (SELECT database, name AS table, storage_policy FROM system.tables)
+                    WHERE {condition}
+                ) AS s ON p.database = s.database AND p.table = s.table
+                LEFT JOIN
+                (
+                    SELECT policy_name, disks
+                    FROM system.storage_policies
+                    WHERE volume_priority = 1
+                ) AS v ON s.storage_policy = v.policy_name
+            )
+            GROUP BY database, table
+            ORDER BY database, table
+            "#,
+            condition = tables_condition(tables)
+        )
+    }
+
+    /// Collect TTL metrics from ClickHouse
+    pub async fn collect(client: &ChClient, tables: &[TableRef]) -> Result<Vec<TtlMetrics>> {
+        let tables_sql = Self::build_tables_query(tables);
+        let table_rows: Vec<TableRow> = client.fetch_all(&tables_sql).await?;
+
+        let parts_sql = Self::build_parts_query(tables);
+        let parts: Vec<PartsRow> = client.fetch_all(&parts_sql).await?;
+
+        Ok(Self::merge(table_rows, parts))
+    }
+
+    /// Combine table definitions with their parts (tables without parts are kept)
+    fn merge(tables: Vec<TableRow>, parts: Vec<PartsRow>) -> Vec<TtlMetrics> {
+        let mut parts: HashMap<(String, String), PartsRow> = parts
+            .into_iter()
+            .map(|p| ((p.database.clone(), p.table.clone()), p))
+            .collect();
+
+        tables
+            .into_iter()
+            .map(|row| {
+                let ttl_only_drop_parts = table_settings(&row.create_table_query)
+                    .into_iter()
+                    .find(|(name, _)| name == "ttl_only_drop_parts")
+                    .map_or(row.default_ttl_only_drop_parts != 0, |(_, value)| {
+                        value == "1"
+                    });
+                // Tables without active parts have no row
+                let (p, newest_part_age_sec) =
+                    match parts.remove(&(row.database.clone(), row.table.clone())) {
+                        Some(p) => {
+                            let age = p.newest_part_age_sec;
+                            (p, Some(age))
+                        }
+                        None => (PartsRow::default(), None),
+                    };
+                TtlMetrics {
+                    ttl: table_ttl(&row.create_table_query),
+                    database: row.database,
+                    table: row.table,
+                    partition_key: row.partition_key,
+                    ttl_only_drop_parts,
+                    bytes_on_disk: p.bytes_on_disk,
+                    newest_part_age_sec,
+                    expired_parts: p.expired_parts,
+                    expired_bytes: p.expired_bytes,
+                    oldest_expired_age_sec: p.oldest_expired_age_sec,
+                    unmoved_parts: p.unmoved_parts,
+                    unmoved_bytes: p.unmoved_bytes,
+                    oldest_unmoved_age_sec: p.oldest_unmoved_age_sec,
+                }
+            })
+            .collect()
+    }
+
+    /// Get the SQL query string for evidence tracking
+    pub fn sql(tables: &[TableRef]) -> String {
+        format!(
+            "Tables: {} | Parts: {}",
+            Self::build_tables_query(tables),
+            Self::build_parts_query(tables)
+        )
+    }
+}
+
+/// The table-level TTL clause of a CREATE statement
+///
+/// Column TTLs sit inside the column list, so only the text after `ENGINE`
+/// is searched. The clause ends at `SETTINGS` or `COMMENT`.
+fn table_ttl(create_query: &str) -> Option<String> {
+    let engine = &create_query[create_query.rfind(" ENGINE = ")?..];
+    let rest = &engine[engine.find(" TTL ")? + " TTL ".len()..];
+    let end = [" SETTINGS ", " COMMENT "]
+        .iter()
+        .filter_map(|marker| rest.find(marker))
+        .min()
+        .unwrap_or(rest.len());
+    Some(rest[..end].trim().to_string())
+}
+
+impl Collector for TtlCollector {
+    fn name(&self) -> &'static str {
+        "ttl"
+    }
+
+    fn source(&self) -> &'static str {
+        "ttl"
+    }
+
+    fn sql(&self, scope: &CollectScope) -> String {
+        Self::sql(&scope.tables)
+    }
+
+    fn collect<'a>(
+        &'a self,
+        client: &'a ChClient,
+        scope: &'a CollectScope,
+    ) -> BoxFuture<'a, Result<Collected>> {
+        Box::pin(async move { Ok(Collected::Ttl(Self::collect(client, &scope.tables).await?)) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(create_table_query: &str, default_ttl_only_drop_parts: u8) -> TableRow {
+        TableRow {
+            database: "testdb".to_string(),
+            table: "events".to_string(),
+            create_table_query: create_table_query.to_string(),
+            partition_key: "toYYYYMM(ts)".to_string(),
+            default_ttl_only_drop_parts,
+        }
+    }
+
+    #[test]
+    fn test_ttl_queries() {
+        let tables = [TableRef::new("testdb", "events")];
+
+        let sql = TtlCollector::build_tables_query(&tables);
+        assert!(sql.contains("name AS table"));
+        assert!(sql.contains("name = 'ttl_only_drop_parts'"));
+
+        let sql = TtlCollector::build_parts_query(&tables);
+        assert!(sql.contains("WHERE active AND (database, table) IN (('testdb', 'events'))"));
+        assert!(sql.contains("delete_ttl_info_max < now()"));
+        assert!(sql.contains("move_ttl_info.max"));
+        assert!(sql.contains("volume_priority = 1"));
+        assert!(sql.contains("ON s.storage_policy = v.policy_name"));
+        assert!(sql.contains("has(v.disks, p.disk_name)"));
+    }
+
+    #[test]
+    fn test_table_ttl() {
+        assert_eq!(
+            table_ttl("CREATE TABLE testdb.events (`ts` DateTime, `tmp` String TTL ts + toIntervalDay(1)) ENGINE = MergeTree PARTITION BY toYYYYMM(ts) ORDER BY ts TTL ts + toIntervalDay(30), ts + toIntervalDay(7) TO VOLUME 'cold' SETTINGS index_granularity = 8192"),
+            Some("ts + toIntervalDay(30), ts + toIntervalDay(7) TO VOLUME 'cold'".to_string())
+        );
+        assert_eq!(
+            table_ttl("CREATE TABLE testdb.events (`ts` DateTime TTL ts + toIntervalDay(1)) ENGINE = MergeTree ORDER BY ts"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_merge_tables_and_parts() {
+        let metrics = TtlCollector::merge(
+            vec![
+                table("CREATE TABLE testdb.events (`ts` DateTime) ENGINE = MergeTree ORDER BY ts TTL ts + toIntervalDay(30) SETTINGS ttl_only_drop_parts = 1", 0),
+            ],
+            vec![PartsRow {
+                database: "testdb".to_string(),
+                table: "events".to_string(),
+                bytes_on_disk: 1000,
+                newest_part_age_sec: 60,
+                expired_parts: 2,
+                expired_bytes: 300,
+                oldest_expired_age_sec: 7200,
+                unmoved_parts: 0,
+                unmoved_bytes: 0,
+                oldest_unmoved_age_sec: 0,
+            }],
+        );
+
+        assert_eq!(metrics[0].ttl.as_deref(), Some("ts + toIntervalDay(30)"));
+        assert!(metrics[0].ttl_only_drop_parts);
+        assert_eq!(metrics[0].newest_part_age_sec, Some(60));
+        assert_eq!(metrics[0].expired_parts, 2);
+    }
+
+    #[test]
+    fn test_merge_table_without_parts_uses_server_default() {
+        let metrics = TtlCollector::merge(
+            vec![table(
+                "CREATE TABLE testdb.events (`ts` DateTime) ENGINE = MergeTree ORDER BY ts",
+                1,
+            )],
+            vec![],
+        );
+
+        assert!(metrics[0].ttl.is_none());
+        assert!(metrics[0].ttl_only_drop_parts);
+        assert_eq!(metrics[0].newest_part_age_sec, None);
+    }
+}
@@ -0,0 +1,470 @@
+use crate::cli::AuditArgs;
+use crate::collectors::TableFilter;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Audit configuration loaded from a TOML file (`--config pipeaudit.toml`)
+///
+/// ```toml
+/// endpoint = "http://localhost:8123"
+/// database = "analytics,raw"   # or "*" for every non-system database
+/// tables = ["events", "orders"]  # optional; empty means auto-discover
+/// exclude = ["*_tmp", "re:^backup_"]
+///
+/// [rules.parts_explosion]
+/// warning = 300
+/// critical = 1000
+///
+/// [rules.query_amplification]
+/// enabled = false
+///
+/// [[overrides]]
+/// pattern = "analytics.raw_*"
+/// [overrides.rules.parts_explosion]
+/// warning = 3000
+/// critical = 10000
+/// ```
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub endpoint: Option<String>,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    /// Comma-separated database names or globs
+    pub database: Option<String>,
+    pub tables: Vec<String>,
+    /// Table patterns to include (globs, or regexes prefixed with `re:`)
+    pub include: Vec<String>,
+    /// Table patterns to exclude (globs, or regexes prefixed with `re:`)
+    pub exclude: Vec<String>,
+    /// Rule settings keyed by rule ID
+    pub rules: BTreeMap<String, RuleSettings>,
+    /// Per-target overrides, applied in order (later entries win)
+    pub overrides: Vec<Override>,
+}
+
+/// Settings for a single rule
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+pub struct RuleSettings {
+    pub enabled: Option<bool>,
+    /// Named thresholds, e.g. `warning = 300`
+    #[serde(flatten)]
+    pub thresholds: BTreeMap<String, f64>,
+}
+
+/// Rule settings applied to targets matching a glob pattern
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Override {
+    /// Glob matched against the finding target, e.g. `analytics.raw_*`
+    pub pattern: String,
+    pub rules: BTreeMap<String, RuleSettings>,
+}
+
+/// Connection and target settings resolved from CLI flags and config
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditTarget {
+    pub endpoint: String,
+    pub user: String,
+    pub password: String,
+    pub databases: Vec<String>,
+    pub tables: Vec<String>,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl AuditTarget {
+    /// Filter used to discover the tables to audit
+    pub fn filter(&self) -> TableFilter {
+        TableFilter {
+            databases: self.databases.clone(),
+            tables: self.tables.clone(),
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+        }
+    }
+}
+
+impl Config {
+    /// Load config from a TOML file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {:?}", path))?;
+        Self::parse(&content).with_context(|| format!("Invalid config file {:?}", path))
+    }
+
+    /// Parse config from a TOML string
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// Resolve connection settings; CLI flags take precedence over the config file
+    pub fn resolve(&self, args: &AuditArgs) -> Result<AuditTarget> {
+        let endpoint = args
+            .endpoint
+            .clone()
+            .or_else(|| self.endpoint.clone())
+            .context("No endpoint given: use --endpoint or set `endpoint` in the config file")?;
+        let databases = if args.db.is_empty() {
+            self.database.as_deref().map(split_list).unwrap_or_default()
+        } else {
+            args.db.clone()
+        };
+        anyhow::ensure!(
+            !databases.is_empty(),
+            "No database given: use --db or set `database` in the config file"
+        );
+
+        Ok(AuditTarget {
+            endpoint,
+            user: args
+                .user
+                .clone()
+                .or_else(|| self.user.clone())
+                .unwrap_or_else(|| "default".to_string()),
+            password: args
+                .password
+                .clone()
+                .or_else(|| self.password.clone())
+                .unwrap_or_default(),
+            databases,
+            tables: or_config(&args.tables, &self.tables),
+            include: or_config(&args.include, &self.include),
+            exclude: or_config(&args.exclude, &self.exclude),
+        })
+    }
+
+    /// Check that every rule and threshold referenced in the config exists
+    ///
+    /// `rules` lists each known rule ID with the threshold names it accepts.
+    pub fn validate(&self, rules: &[(&str, &[&str])]) -> Result<()> {
+        let referenced = self
+            .rules
+            .iter()
+            .chain(self.overrides.iter().flat_map(|o| o.rules.iter()));
+
+        for (id, settings) in referenced {
+            let Some((_, thresholds)) = rules.iter().find(|(rule_id, _)| rule_id == id) else {
+                anyhow::bail!(
+                    "Unknown rule '{}' in config (known rules: {})",
+                    id,
+                    rules
+                        .iter()
+                        .map(|(rule_id, _)| *rule_id)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            };
+            for name in settings.thresholds.keys() {
+                anyhow::ensure!(
+                    thresholds.contains(&name.as_str()),
+                    "Unknown threshold '{}' for rule '{}' in config (known thresholds: {})",
+                    name,
+                    id,
+                    if thresholds.is_empty() {
+                        "none".to_string()
+                    } else {
+                        thresholds.join(", ")
+                    }
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the resolved settings for a single rule
+    pub fn rule(&self, rule_id: &str) -> RuleConfig {
+        RuleConfig {
+            settings: self.rules.get(rule_id).cloned().unwrap_or_default(),
+            overrides: self
+                .overrides
+                .iter()
+                .filter_map(|o| o.rules.get(rule_id).map(|s| (o.pattern.clone(), s.clone())))
+                .collect(),
+        }
+    }
+}
+
+/// Settings for one rule, including the overrides that mention it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleConfig {
+    settings: RuleSettings,
+    overrides: Vec<(String, RuleSettings)>,
+}
+
+impl RuleConfig {
+    /// Whether the rule is enabled at all
+    pub fn is_enabled(&self) -> bool {
+        self.settings.enabled.unwrap_or(true)
+    }
+
+    /// Whether the rule is enabled globally or for any override pattern
+    pub fn enabled_anywhere(&self) -> bool {
+        self.is_enabled() || self.overrides.iter().any(|(_, s)| s.enabled == Some(true))
+    }
+
+    /// Whether the rule is enabled for a specific target
+    pub fn enabled_for(&self, target: &str) -> bool {
+        self.matching(target)
+            .filter_map(|s| s.enabled)
+            .last()
+            .unwrap_or_else(|| self.is_enabled())
+    }
+
+    /// Look up a threshold for a target, falling back to the rule default
+    pub fn threshold(&self, target: &str, name: &str, default: f64) -> f64 {
+        self.matching(target)
+            .filter_map(|s| s.thresholds.get(name).copied())
+            .last()
+            .or_else(|| self.settings.thresholds.get(name).copied())
+            .unwrap_or(default)
+    }
+
+    fn matching<'a>(&'a self, target: &'a str) -> impl Iterator<Item = &'a RuleSettings> + 'a {
+        self.overrides
+            .iter()
+            .filter(move |(pattern, _)| glob_match(pattern, target))
+            .map(|(_, settings)| settings)
+    }
+}
+
+/// Match text against a glob pattern supporting `*` and `?`
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((sp, st)) = star {
+            // Backtrack: let the last `*` absorb one more character
+            p = sp + 1;
+            t = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// CLI list if given, otherwise the config list
+fn or_config(cli: &[String], config: &[String]) -> Vec<String> {
+    if cli.is_empty() {
+        config.to_vec()
+    } else {
+        cli.to_vec()
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Commands};
+    use clap::Parser;
+
+    const SAMPLE: &str = r#"
+endpoint = "http://ch:8123"
+database = "analytics"
+tables = ["events", "orders"]
+
+[rules.parts_explosion]
+warning = 500
+critical = 2000
+
+[rules.query_amplification]
+enabled = false
+
+[[overrides]]
+pattern = "analytics.raw_*"
+[overrides.rules.parts_explosion]
+warning = 3000
+
+[[overrides]]
+pattern = "analytics.raw_tmp"
+[overrides.rules.parts_explosion]
+enabled = false
+"#;
+
+    fn audit_args(extra: &[&str]) -> AuditArgs {
+        let mut argv = vec!["pipeaudit", "audit", "--out", "report.json"];
+        argv.extend_from_slice(extra);
+        match Cli::parse_from(argv).command {
+            Commands::Audit(args) => args,
+            Commands::Diff(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::parse(SAMPLE).unwrap();
+        assert_eq!(config.endpoint.as_deref(), Some("http://ch:8123"));
+        assert_eq!(config.tables, vec!["events", "orders"]);
+        assert_eq!(config.rules["parts_explosion"].thresholds["warning"], 500.0);
+        assert_eq!(config.overrides.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_fields() {
+        assert!(Config::parse("endpont = \"http://ch:8123\"").is_err());
+    }
+
+    #[test]
+    fn test_rule_thresholds_with_overrides() {
+        let config = Config::parse(SAMPLE).unwrap();
+        let rule = config.rule("parts_explosion");
+
+        assert_eq!(rule.threshold("analytics.events", "warning", 300.0), 500.0);
+        assert_eq!(
+            rule.threshold("analytics.raw_logs", "warning", 300.0),
+            3000.0
+        );
+        // Not overridden: falls back to the rule-level setting
+        assert_eq!(
+            rule.threshold("analytics.raw_logs", "critical", 1000.0),
+            2000.0
+        );
+        // Not configured at all: falls back to the default
+        assert_eq!(rule.threshold("analytics.events", "other", 7.0), 7.0);
+    }
+
+    #[test]
+    fn test_rule_enabled() {
+        let config = Config::parse(SAMPLE).unwrap();
+
+        assert!(config.rule("parts_explosion").is_enabled());
+        assert!(!config.rule("query_amplification").is_enabled());
+        assert!(config.rule("disk_headroom").is_enabled());
+
+        let parts = config.rule("parts_explosion");
+        assert!(parts.enabled_for("analytics.raw_logs"));
+        assert!(!parts.enabled_for("analytics.raw_tmp"));
+    }
+
+    #[test]
+    fn test_validate_unknown_rule() {
+        let config = Config::parse(SAMPLE).unwrap();
+        let parts: (&str, &[&str]) = ("parts_explosion", &["warning", "critical"]);
+        assert!(config
+            .validate(&[parts, ("query_amplification", &[])])
+            .is_ok());
+        assert!(config.validate(&[parts]).is_err());
+    }
+
+    #[test]
+    fn test_validate_unknown_threshold() {
+        let config = Config::parse(SAMPLE).unwrap();
+        let err = config
+            .validate(&[
+                ("parts_explosion", &["warning"]),
+                ("query_amplification", &[]),
+            ])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown threshold 'critical' for rule 'parts_explosion' in config (known thresholds: warning)"
+        );
+    }
+
+    #[test]
+    fn test_rule_enabled_anywhere() {
+        let config = Config::parse(
+            "[rules.parts_explosion]\nenabled = false\n\n[[overrides]]\npattern = \"a.big_*\"\n[overrides.rules.parts_explosion]\nenabled = true",
+        )
+        .unwrap();
+        let rule = config.rule("parts_explosion");
+        assert!(!rule.is_enabled());
+        assert!(rule.enabled_anywhere());
+        assert!(rule.enabled_for("a.big_events"));
+        assert!(!rule.enabled_for("a.events"));
+        assert!(!Config::parse("[rules.parts_explosion]\nenabled = false")
+            .unwrap()
+            .rule("parts_explosion")
+            .enabled_anywhere());
+    }
+
+    #[test]
+    fn test_resolve_from_config() {
+        let config = Config::parse(SAMPLE).unwrap();
+        let target = config.resolve(&audit_args(&[])).unwrap();
+
+        assert_eq!(target.endpoint, "http://ch:8123");
+        assert_eq!(target.databases, vec!["analytics"]);
+        assert_eq!(target.tables, vec!["events", "orders"]);
+        assert!(target.include.is_empty());
+        assert_eq!(target.user, "default");
+        assert_eq!(target.password, "");
+    }
+
+    #[test]
+    fn test_resolve_cli_takes_precedence() {
+        let config = Config::parse(SAMPLE).unwrap();
+        let args = audit_args(&["--db", "raw", "--tables", "logs", "--user", "admin"]);
+        let target = config.resolve(&args).unwrap();
+
+        assert_eq!(target.databases, vec!["raw"]);
+        assert_eq!(target.tables, vec!["logs"]);
+        assert_eq!(target.user, "admin");
+    }
+
+    #[test]
+    fn test_resolve_multiple_databases_and_patterns() {
+        let config = Config::parse(
+            "endpoint = \"http://ch:8123\"\ndatabase = \"analytics, raw\"\nexclude = [\"*_tmp\"]",
+        )
+        .unwrap();
+        let target = config.resolve(&audit_args(&[])).unwrap();
+        assert_eq!(target.databases, vec!["analytics", "raw"]);
+        assert!(target.tables.is_empty());
+        assert_eq!(target.exclude, vec!["*_tmp"]);
+
+        let args = audit_args(&["--db", "*", "--include", "re:^events"]);
+        let filter = config.resolve(&args).unwrap().filter();
+        assert_eq!(filter.databases, vec!["*"]);
+        assert_eq!(filter.include, vec!["re:^events"]);
+        assert_eq!(filter.exclude, vec!["*_tmp"]);
+    }
+
+    #[test]
+    fn test_resolve_requires_database() {
+        let config = Config::parse("endpoint = \"http://ch:8123\"").unwrap();
+        assert!(config.resolve(&audit_args(&[])).is_err());
+    }
+
+    #[test]
+    fn test_resolve_requires_endpoint() {
+        let config = Config::default();
+        assert!(config.resolve(&audit_args(&["--db", "testdb"])).is_err());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("analytics.*", "analytics.events"));
+        assert!(glob_match("*.events", "raw.events"));
+        assert!(glob_match("raw.ev?nts", "raw.events"));
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("a*b*c", "aXXbYYc"));
+        assert!(!glob_match("analytics.*", "raw.events"));
+        assert!(!glob_match("raw.ev?nts", "raw.evnts"));
+    }
+}
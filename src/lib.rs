@@ -1,6 +1,7 @@
 pub mod ch;
 pub mod cli;
 pub mod collectors;
+pub mod config;
 pub mod output;
 pub mod report;
 pub mod rules;
@@ -1,84 +1,153 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
 use pipeaudit::ch::ChClient;
-use pipeaudit::cli::{Cli, Commands};
-use pipeaudit::collectors::{
-    DiskCollector, MergesCollector, MutationsCollector, MvDagCollector, PartsCollector,
-    QueryLogCollector,
-};
-use pipeaudit::output::{print_summary, write_report};
-use pipeaudit::report::{ReportBuilder, Targets};
+use pipeaudit::cli::{audit_exit_code, exit_code, Cli, Commands};
+use pipeaudit::collectors::{CollectScope, CollectorRegistry, QueryWindow, TableDiscovery};
+use pipeaudit::config::Config;
+use pipeaudit::output::{print_diff, print_summary, read_report, write_diff, write_report};
+use pipeaudit::report::{ReportBuilder, ReportDiff, Targets};
 use pipeaudit::rules::RuleRegistry;
+use std::process::ExitCode;
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     let cli = Cli::parse();
 
-    match cli.command {
-        Commands::Audit(args) => {
-            run_audit(args).await?;
+    let result = match cli.command {
+        Commands::Audit(args) => run_audit(args).await,
+        Commands::Diff(args) => run_diff(args),
+    };
+
+    match result {
+        Ok(code) => ExitCode::from(code),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            ExitCode::from(exit_code::ERROR)
         }
     }
-
-    Ok(())
 }
 
-async fn run_audit(args: pipeaudit::cli::AuditArgs) -> Result<()> {
-    // 1. Connect to ClickHouse
-    let client = ChClient::new(&args.endpoint, &args.user, &args.password, &args.db);
+/// Run the audit and return the process exit code
+async fn run_audit(args: pipeaudit::cli::AuditArgs) -> Result<u8> {
+    // 1. Load config and resolve targets
+    let config = match &args.config {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    let target = config.resolve(&args)?;
+    let registry = RuleRegistry::with_config(&config)?;
+    let collectors = CollectorRegistry::with_default_collectors().select(&args.only, &args.skip)?;
+
+    // 2. Connect to ClickHouse
+    let client = ChClient::new(&target.endpoint, &target.user, &target.password, "default");
     client.ping().await?;
-    eprintln!("Connected to ClickHouse at {}", args.endpoint);
+    eprintln!("Connected to ClickHouse at {}", target.endpoint);
 
-    // 2. Initialize report builder
+    // 3. Discover databases and tables
+    let filter = target.filter();
+    let databases = TableDiscovery::discover_databases(&client, &filter).await?;
+    anyhow::ensure!(
+        !databases.is_empty(),
+        "No databases match {}",
+        target.databases.join(", ")
+    );
+    let tables = TableDiscovery::discover(&client, &filter).await?;
+    for name in filter.unmatched(&tables) {
+        eprintln!(
+            "  Warning: table {} not found or not a MergeTree table",
+            name
+        );
+    }
+    // Databases with only Distributed or Kafka tables are still audited
+    if tables.is_empty() {
+        eprintln!(
+            "  Warning: no MergeTree tables found in {}; only database-wide collectors have data",
+            databases.join(", ")
+        );
+    }
+    eprintln!(
+        "Auditing {} tables in {} databases",
+        tables.len(),
+        databases.len()
+    );
+
+    // 4. Initialize report builder
     let targets = Targets {
-        endpoint: args.endpoint.clone(),
-        database: args.db.clone(),
-        tables: args.tables.clone(),
+        endpoint: target.endpoint.clone(),
+        database: target.databases.join(","),
+        tables: tables.iter().map(|t| t.to_string()).collect(),
     };
     let mut builder = ReportBuilder::new(targets);
 
-    // 3. Run collectors
-    eprintln!("Collecting parts metrics...");
-    let parts = PartsCollector::collect(&client, &args.db, &args.tables).await?;
-    let parts_sql = PartsCollector::sql(&args.db, &args.tables);
-    builder.with_parts(parts, &parts_sql);
-
-    eprintln!("Collecting merge metrics...");
-    let merges = MergesCollector::collect(&client, &args.db, &args.tables).await?;
-    let merges_sql = MergesCollector::sql(&args.db, &args.tables);
-    builder.with_merges(merges, &merges_sql);
-
-    eprintln!("Collecting mutation metrics...");
-    let mutations = MutationsCollector::collect(&client, &args.db, &args.tables).await?;
-    let mutations_sql = MutationsCollector::sql(&args.db, &args.tables);
-    builder.with_mutations(mutations, &mutations_sql);
-
-    eprintln!("Collecting disk metrics...");
-    let disk = DiskCollector::collect(&client).await?;
-    let disk_sql = DiskCollector::sql();
-    builder.with_disk(disk, &disk_sql);
-
-    eprintln!("Collecting query log metrics...");
-    let queries = QueryLogCollector::collect(&client, &args.db).await?;
-    let queries_sql = QueryLogCollector::sql(&args.db);
-    builder.with_queries(queries, &queries_sql);
-
-    eprintln!("Collecting MV dependency graph...");
-    let mv_dag = MvDagCollector::collect(&client, &args.db).await?;
-    let mv_dag_sql = MvDagCollector::sql(&args.db);
-    builder.with_mv_dag(mv_dag, &mv_dag_sql);
-
-    // 4. Run rules
+    // 5. Run collectors concurrently; a failing collector is recorded and the audit continues
+    let sql = match &args.sql_file {
+        Some(path) => Some(
+            std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read SQL file {:?}", path))?,
+        ),
+        None => None,
+    };
+    let scope = CollectScope {
+        tables,
+        databases,
+        sql,
+        query_window: QueryWindow {
+            window_sec: args.query_window,
+            limit: args.query_limit,
+        },
+        sample_columns: args.sample_columns,
+    };
+    eprintln!("Running collectors...");
+    for run in collectors.run_all(&client, &scope).await {
+        match &run.result {
+            Ok(_) => eprintln!(
+                "  {}: {} rows in {} ms",
+                run.name, run.stats.row_count, run.stats.duration_ms
+            ),
+            Err(e) => eprintln!("  Warning: {} failed: {:#}", run.name, e),
+        }
+        builder.with_run(run);
+    }
+
+    // 6. Run rules
     eprintln!("Running audit rules...");
-    let registry = RuleRegistry::with_default_rules();
     builder.run_rules(&registry);
 
-    // 5. Build report
+    // 7. Build report
     let report = builder.build();
 
-    // 6. Output
+    // 8. Output
     write_report(&report, &args.out)?;
     print_summary(&report, &args.out.to_string_lossy());
 
-    Ok(())
+    // 9. Gate on collection failures, then severity
+    let code = audit_exit_code(&report, args.fail_on);
+    match code {
+        exit_code::COLLECTION_FAILED => eprintln!(
+            "Failing: {} collector(s) failed, the report is partial",
+            report.collection.failed().count()
+        ),
+        exit_code::FINDINGS => eprintln!(
+            "Failing: report status is {:?} (--fail-on {:?})",
+            report.summary.status, args.fail_on
+        ),
+        _ => {}
+    }
+
+    Ok(code)
+}
+
+/// Compare two reports and return the process exit code
+fn run_diff(args: pipeaudit::cli::DiffArgs) -> Result<u8> {
+    let old = read_report(&args.old)?;
+    let new = read_report(&args.new)?;
+
+    let diff = ReportDiff::compute(&old, &new);
+
+    if let Some(out) = &args.out {
+        write_diff(&diff, out)?;
+    }
+    print_diff(&diff);
+
+    Ok(exit_code::SUCCESS)
 }
@@ -0,0 +1,146 @@
+use crate::report::{CountDelta, Finding, ReportDiff, Severity};
+
+/// Print human-readable report diff to stdout
+pub fn print_diff(diff: &ReportDiff) {
+    println!();
+    println!("╭───────────────────────────────────────────────────────────────╮");
+    println!("│                   PipeAudit Report Diff                       │");
+    println!("╰───────────────────────────────────────────────────────────────╯");
+    println!();
+
+    println!("Old: {} ({})", diff.old_report_id, diff.old_generated_at);
+    println!("New: {} ({})", diff.new_report_id, diff.new_generated_at);
+    println!();
+
+    if diff.is_empty() {
+        println!("No changes.");
+        println!();
+        return;
+    }
+
+    if !diff.new_findings.is_empty() {
+        println!("New findings ({}):", diff.new_findings.len());
+        for finding in &diff.new_findings {
+            println!("  + {}", format_finding(finding));
+        }
+        println!();
+    }
+
+    if !diff.resolved_findings.is_empty() {
+        println!("Resolved findings ({}):", diff.resolved_findings.len());
+        for finding in &diff.resolved_findings {
+            println!("  - {}", format_finding(finding));
+        }
+        println!();
+    }
+
+    if !diff.changed_findings.is_empty() {
+        println!("Changed findings ({}):", diff.changed_findings.len());
+        for change in &diff.changed_findings {
+            println!(
+                "  ~ [{}] {}: {:?} -> {:?}",
+                change.rule_id, change.target, change.old_severity, change.new_severity
+            );
+            if change.old_message != change.new_message {
+                println!("      was: {}", change.old_message);
+                println!("      now: {}", change.new_message);
+            }
+        }
+        println!();
+    }
+
+    let changed_tables: Vec<_> = diff
+        .tables
+        .iter()
+        .filter(|t| {
+            t.active_parts.change != 0
+                || t.bytes_on_disk.change != 0
+                || t.merges_in_queue.change != 0
+        })
+        .collect();
+    if !changed_tables.is_empty() {
+        println!("Table metrics:");
+        for table in changed_tables {
+            println!(
+                "  {}: active_parts {}, bytes_on_disk {}, merges_in_queue {}",
+                table.table,
+                format_count(&table.active_parts),
+                format_count(&table.bytes_on_disk),
+                format_count(&table.merges_in_queue)
+            );
+        }
+        println!();
+    }
+
+    let changed_disks: Vec<_> = diff
+        .disks
+        .iter()
+        .filter(|d| d.free_percent.change != 0.0)
+        .collect();
+    if !changed_disks.is_empty() {
+        println!("Disk free space:");
+        for disk in changed_disks {
+            println!(
+                "  {}: {} -> {} ({:+.1} pts)",
+                disk.disk_name,
+                format_percent(disk.free_percent.old),
+                format_percent(disk.free_percent.new),
+                disk.free_percent.change
+            );
+        }
+        println!();
+    }
+}
+
+fn format_finding(finding: &Finding) -> String {
+    let icon = match finding.severity {
+        Severity::Critical => "🚨",
+        Severity::Warning => "⚠️ ",
+    };
+    format!(
+        "{} [{}] {}: {}",
+        icon, finding.rule_id, finding.target, finding.message
+    )
+}
+
+fn format_count(delta: &CountDelta) -> String {
+    let side = |v: Option<u64>| v.map_or("-".to_string(), |v| v.to_string());
+    format!(
+        "{} -> {} ({:+})",
+        side(delta.old),
+        side(delta.new),
+        delta.change
+    )
+}
+
+fn format_percent(value: Option<f64>) -> String {
+    value.map_or("-".to_string(), |v| format!("{:.1}%", v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_count() {
+        let delta = CountDelta {
+            old: Some(100),
+            new: Some(40),
+            change: -60,
+        };
+        assert_eq!(format_count(&delta), "100 -> 40 (-60)");
+
+        let added = CountDelta {
+            old: None,
+            new: Some(3),
+            change: 3,
+        };
+        assert_eq!(format_count(&added), "- -> 3 (+3)");
+    }
+
+    #[test]
+    fn test_format_percent() {
+        assert_eq!(format_percent(Some(25.54)), "25.5%");
+        assert_eq!(format_percent(None), "-");
+    }
+}
@@ -1,4 +1,4 @@
-use crate::report::Report;
+use crate::report::{Report, ReportDiff};
 use anyhow::{Context, Result};
 use std::path::Path;
 
@@ -12,10 +12,27 @@ pub fn write_report(report: &Report, path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Read a report from a JSON file
+pub fn read_report(path: &Path) -> Result<Report> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read report from {:?}", path))?;
+
+    serde_json::from_str(&json).with_context(|| format!("Failed to parse report {:?}", path))
+}
+
+/// Write report diff to JSON file
+pub fn write_diff(diff: &ReportDiff, path: &Path) -> Result<()> {
+    let json = serde_json::to_string_pretty(diff).context("Failed to serialize diff to JSON")?;
+
+    std::fs::write(path, &json).with_context(|| format!("Failed to write diff to {:?}", path))?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::report::{Report, ReportStatus, Summary, Targets};
+    use crate::report::{Report, Targets};
     use tempfile::NamedTempFile;
 
     fn test_report() -> Report {
@@ -64,4 +81,23 @@ mod tests {
         // Pretty printed JSON has newlines
         assert!(content.contains('\n'));
     }
+
+    #[test]
+    fn test_read_report_roundtrip() {
+        let report = test_report();
+        let temp = NamedTempFile::new().unwrap();
+
+        write_report(&report, temp.path()).unwrap();
+        let parsed = read_report(temp.path()).unwrap();
+
+        assert_eq!(report, parsed);
+    }
+
+    #[test]
+    fn test_read_report_invalid_json() {
+        let temp = NamedTempFile::new().unwrap();
+        std::fs::write(temp.path(), "not json").unwrap();
+
+        assert!(read_report(temp.path()).is_err());
+    }
 }
@@ -1,5 +1,7 @@
+mod diff;
 mod json;
 mod summary;
 
-pub use json::write_report;
+pub use diff::print_diff;
+pub use json::{read_report, write_diff, write_report};
 pub use summary::print_summary;
@@ -53,9 +53,14 @@ pub fn print_summary(report: &Report, output_path: &str) {
         println!("Recommended Actions:");
         for (i, action) in report.actions.iter().enumerate() {
             println!(
-                "  {}. [{:?}] {}",
+                "  {}. [{:?}]{} {}",
                 i + 1,
                 action.priority,
+                if action.destructive {
+                    " [DESTRUCTIVE]"
+                } else {
+                    ""
+                },
                 action.description
             );
             if let Some(sql) = &action.sql {
@@ -65,6 +70,28 @@ pub fn print_summary(report: &Report, output_path: &str) {
         println!();
     }
 
+    // Replication summary
+    if let Some(replication) = &report.sections.replication {
+        let readonly = replication
+            .tables
+            .iter()
+            .filter(|t| t.is_readonly || t.is_session_expired)
+            .count();
+        let max_delay = replication
+            .tables
+            .iter()
+            .map(|t| t.absolute_delay)
+            .max()
+            .unwrap_or(0);
+        println!(
+            "Replication: {} replicated tables, {} readonly, max delay {}s",
+            replication.tables.len(),
+            readonly,
+            max_delay
+        );
+        println!();
+    }
+
     // MV DAG summary
     if let Some(dag) = &report.sections.mv_dag {
         println!(
@@ -74,6 +101,46 @@ pub fn print_summary(report: &Report, output_path: &str) {
         println!();
     }
 
+    // EXPLAIN summary
+    if let Some(explain) = &report.sections.explain {
+        let failed = explain
+            .statements
+            .iter()
+            .filter(|s| s.error.is_some())
+            .count();
+        println!(
+            "EXPLAIN: {} statements analyzed, {} failed",
+            explain.statements.len(),
+            failed
+        );
+        println!();
+    }
+
+    // Collection failures
+    let failed: Vec<_> = report.collection.failed().collect();
+    if !failed.is_empty() {
+        println!(
+            "Collection: {} of {} collectors failed (report is partial):",
+            failed.len(),
+            report.collection.collectors.len()
+        );
+        for collector in failed {
+            println!(
+                "  ✗ {}: {}",
+                collector.source,
+                truncate(collector.error.as_deref().unwrap_or("unknown error"), 80)
+            );
+        }
+        for rule in &report.collection.skipped_rules {
+            println!(
+                "  Skipped rule {} (missing {})",
+                rule.rule_id,
+                rule.missing_inputs.join(", ")
+            );
+        }
+        println!();
+    }
+
     println!("Full report written to: {}", output_path);
     println!();
 }
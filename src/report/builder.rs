@@ -1,20 +1,37 @@
 use super::types::*;
-use crate::collectors::EvidenceCollector;
+use crate::ch::QueryStats;
+use crate::collectors::{Collected, CollectorRun, EvidenceCollector};
 use crate::rules::{AuditContext, RuleRegistry};
 use std::collections::HashMap;
+use std::time::Duration;
 
 /// Builder for constructing audit reports
 pub struct ReportBuilder {
     targets: Targets,
     evidence: EvidenceCollector,
+    /// Evidence ID per metric source
+    evidence_ids: HashMap<String, String>,
     parts: HashMap<String, PartsMetrics>,
+    merge_tree_settings: HashMap<String, MergeTreeSettingsMetrics>,
     merges: HashMap<String, MergeMetrics>,
     mutations: HashMap<String, MutationMetrics>,
+    replication: HashMap<String, ReplicationMetrics>,
+    distribution_queue: HashMap<String, DistributionQueueMetrics>,
+    kafka: HashMap<String, KafkaTableMetrics>,
+    ttl: HashMap<String, TtlMetrics>,
+    detached_parts: HashMap<String, DetachedPartsMetrics>,
+    inserts: HashMap<String, InsertMetrics>,
+    columns: Vec<ColumnMetrics>,
     disk: Vec<DiskMetrics>,
     queries: Vec<QueryMetrics>,
+    failed_queries: Vec<FailedQueryMetrics>,
+    dictionaries: Vec<DictionaryMetrics>,
     mv_dag: Option<MvDagSection>,
+    server: Option<ServerSection>,
+    explain: Vec<ExplainMetrics>,
     findings: Vec<Finding>,
     actions: Vec<Action>,
+    collection: CollectionSection,
 }
 
 impl ReportBuilder {
@@ -23,20 +40,39 @@ impl ReportBuilder {
         Self {
             targets,
             evidence: EvidenceCollector::new(),
+            evidence_ids: HashMap::new(),
             parts: HashMap::new(),
+            merge_tree_settings: HashMap::new(),
             merges: HashMap::new(),
             mutations: HashMap::new(),
+            replication: HashMap::new(),
+            distribution_queue: HashMap::new(),
+            kafka: HashMap::new(),
+            ttl: HashMap::new(),
+            detached_parts: HashMap::new(),
+            inserts: HashMap::new(),
+            columns: Vec::new(),
             disk: Vec::new(),
             queries: Vec::new(),
+            failed_queries: Vec::new(),
+            dictionaries: Vec::new(),
             mv_dag: None,
+            server: None,
+            explain: Vec::new(),
             findings: Vec::new(),
             actions: Vec::new(),
+            collection: CollectionSection::default(),
         }
     }
 
     /// Add parts metrics
-    pub fn with_parts(&mut self, metrics: Vec<PartsMetrics>, sql: &str) -> &mut Self {
-        self.evidence.record("system.parts", sql);
+    pub fn with_parts(
+        &mut self,
+        metrics: Vec<PartsMetrics>,
+        sql: &str,
+        stats: &QueryStats,
+    ) -> &mut Self {
+        self.record_evidence("system.parts", sql, stats);
         for m in metrics {
             let key = format!("{}.{}", m.database, m.table);
             self.parts.insert(key, m);
@@ -45,8 +81,13 @@ impl ReportBuilder {
     }
 
     /// Add merge metrics
-    pub fn with_merges(&mut self, metrics: Vec<MergeMetrics>, sql: &str) -> &mut Self {
-        self.evidence.record("system.merges", sql);
+    pub fn with_merges(
+        &mut self,
+        metrics: Vec<MergeMetrics>,
+        sql: &str,
+        stats: &QueryStats,
+    ) -> &mut Self {
+        self.record_evidence("system.merges", sql, stats);
         for m in metrics {
             let key = format!("{}.{}", m.database, m.table);
             self.merges.insert(key, m);
@@ -55,8 +96,13 @@ impl ReportBuilder {
     }
 
     /// Add mutation metrics
-    pub fn with_mutations(&mut self, metrics: Vec<MutationMetrics>, sql: &str) -> &mut Self {
-        self.evidence.record("system.mutations", sql);
+    pub fn with_mutations(
+        &mut self,
+        metrics: Vec<MutationMetrics>,
+        sql: &str,
+        stats: &QueryStats,
+    ) -> &mut Self {
+        self.record_evidence("system.mutations", sql, stats);
         for m in metrics {
             let key = format!("{}.{}", m.database, m.table);
             self.mutations.insert(key, m);
@@ -64,31 +110,264 @@ impl ReportBuilder {
         self
     }
 
+    /// Add replication metrics
+    pub fn with_replication(
+        &mut self,
+        metrics: Vec<ReplicationMetrics>,
+        sql: &str,
+        stats: &QueryStats,
+    ) -> &mut Self {
+        self.record_evidence("system.replicas", sql, stats);
+        for m in metrics {
+            let key = format!("{}.{}", m.database, m.table);
+            self.replication.insert(key, m);
+        }
+        self
+    }
+
+    /// Add Distributed send queue metrics
+    pub fn with_distribution_queue(
+        &mut self,
+        metrics: Vec<DistributionQueueMetrics>,
+        sql: &str,
+        stats: &QueryStats,
+    ) -> &mut Self {
+        self.record_evidence("system.distribution_queue", sql, stats);
+        for m in metrics {
+            let key = format!("{}.{}", m.database, m.table);
+            self.distribution_queue.insert(key, m);
+        }
+        self
+    }
+
+    /// Add Kafka consumer metrics
+    pub fn with_kafka(
+        &mut self,
+        metrics: Vec<KafkaTableMetrics>,
+        sql: &str,
+        stats: &QueryStats,
+    ) -> &mut Self {
+        self.record_evidence("system.kafka_consumers", sql, stats);
+        for m in metrics {
+            let key = format!("{}.{}", m.database, m.table);
+            self.kafka.insert(key, m);
+        }
+        self
+    }
+
+    /// Add effective merge_tree part limits
+    pub fn with_merge_tree_settings(
+        &mut self,
+        metrics: Vec<MergeTreeSettingsMetrics>,
+        sql: &str,
+        stats: &QueryStats,
+    ) -> &mut Self {
+        self.record_evidence("system.merge_tree_settings", sql, stats);
+        for m in metrics {
+            let key = format!("{}.{}", m.database, m.table);
+            self.merge_tree_settings.insert(key, m);
+        }
+        self
+    }
+
+    /// Add TTL state
+    pub fn with_ttl(
+        &mut self,
+        metrics: Vec<TtlMetrics>,
+        sql: &str,
+        stats: &QueryStats,
+    ) -> &mut Self {
+        self.record_evidence("ttl", sql, stats);
+        for m in metrics {
+            let key = format!("{}.{}", m.database, m.table);
+            self.ttl.insert(key, m);
+        }
+        self
+    }
+
+    /// Add detached parts
+    pub fn with_detached_parts(
+        &mut self,
+        metrics: Vec<DetachedPartsMetrics>,
+        sql: &str,
+        stats: &QueryStats,
+    ) -> &mut Self {
+        self.record_evidence("system.detached_parts", sql, stats);
+        for m in metrics {
+            let key = format!("{}.{}", m.database, m.table);
+            self.detached_parts.insert(key, m);
+        }
+        self
+    }
+
+    /// Add insert patterns
+    pub fn with_inserts(
+        &mut self,
+        metrics: Vec<InsertMetrics>,
+        sql: &str,
+        stats: &QueryStats,
+    ) -> &mut Self {
+        self.record_evidence("inserts", sql, stats);
+        for m in metrics {
+            let key = format!("{}.{}", m.database, m.table);
+            self.inserts.insert(key, m);
+        }
+        self
+    }
+
+    /// Add column storage metrics
+    pub fn with_columns(
+        &mut self,
+        metrics: Vec<ColumnMetrics>,
+        sql: &str,
+        stats: &QueryStats,
+    ) -> &mut Self {
+        self.record_evidence("system.columns", sql, stats);
+        self.columns = metrics;
+        self
+    }
+
     /// Add disk metrics
-    pub fn with_disk(&mut self, metrics: Vec<DiskMetrics>, sql: &str) -> &mut Self {
-        self.evidence.record("system.disks", sql);
+    pub fn with_disk(
+        &mut self,
+        metrics: Vec<DiskMetrics>,
+        sql: &str,
+        stats: &QueryStats,
+    ) -> &mut Self {
+        self.record_evidence("system.disks", sql, stats);
         self.disk = metrics;
         self
     }
 
     /// Add query metrics
-    pub fn with_queries(&mut self, metrics: Vec<QueryMetrics>, sql: &str) -> &mut Self {
-        self.evidence.record("system.query_log", sql);
+    pub fn with_queries(
+        &mut self,
+        metrics: Vec<QueryMetrics>,
+        sql: &str,
+        stats: &QueryStats,
+    ) -> &mut Self {
+        self.record_evidence("system.query_log", sql, stats);
         self.queries = metrics;
         self
     }
 
+    /// Add failed query metrics
+    pub fn with_failed_queries(
+        &mut self,
+        metrics: Vec<FailedQueryMetrics>,
+        sql: &str,
+        stats: &QueryStats,
+    ) -> &mut Self {
+        self.record_evidence("failed_queries", sql, stats);
+        self.failed_queries = metrics;
+        self
+    }
+
+    /// Add dictionary metrics
+    pub fn with_dictionaries(
+        &mut self,
+        metrics: Vec<DictionaryMetrics>,
+        sql: &str,
+        stats: &QueryStats,
+    ) -> &mut Self {
+        self.record_evidence("system.dictionaries", sql, stats);
+        self.dictionaries = metrics;
+        self
+    }
+
     /// Add MV DAG
-    pub fn with_mv_dag(&mut self, dag: MvDagSection, sql: &str) -> &mut Self {
-        self.evidence.record("system.tables", sql);
+    pub fn with_mv_dag(&mut self, dag: MvDagSection, sql: &str, stats: &QueryStats) -> &mut Self {
+        self.record_evidence("system.tables", sql, stats);
         self.mv_dag = Some(dag);
         self
     }
 
+    /// Add server resource usage
+    pub fn with_server(
+        &mut self,
+        server: ServerSection,
+        sql: &str,
+        stats: &QueryStats,
+    ) -> &mut Self {
+        self.record_evidence("server", sql, stats);
+        self.server = Some(server);
+        self
+    }
+
+    /// Add EXPLAIN results
+    pub fn with_explain(
+        &mut self,
+        metrics: Vec<ExplainMetrics>,
+        sql: &str,
+        stats: &QueryStats,
+    ) -> &mut Self {
+        self.record_evidence("explain", sql, stats);
+        self.explain = metrics;
+        self
+    }
+
+    /// Add the output of a collector run, recording a failure if it errored
+    pub fn with_run(&mut self, run: CollectorRun) -> &mut Self {
+        let (sql, stats) = (&run.sql, &run.stats);
+        match run.result {
+            Ok(Collected::Parts(metrics)) => self.with_parts(metrics, sql, stats),
+            Ok(Collected::Merges(metrics)) => self.with_merges(metrics, sql, stats),
+            Ok(Collected::Mutations(metrics)) => self.with_mutations(metrics, sql, stats),
+            Ok(Collected::Disk(metrics)) => self.with_disk(metrics, sql, stats),
+            Ok(Collected::Queries(metrics)) => self.with_queries(metrics, sql, stats),
+            Ok(Collected::FailedQueries(metrics)) => self.with_failed_queries(metrics, sql, stats),
+            Ok(Collected::MvDag(dag)) => self.with_mv_dag(dag, sql, stats),
+            Ok(Collected::Server(server)) => self.with_server(server, sql, stats),
+            Ok(Collected::Dictionaries(metrics)) => self.with_dictionaries(metrics, sql, stats),
+            Ok(Collected::Explain(metrics)) => self.with_explain(metrics, sql, stats),
+            Ok(Collected::Replication(metrics)) => self.with_replication(metrics, sql, stats),
+            Ok(Collected::DistributionQueue(metrics)) => {
+                self.with_distribution_queue(metrics, sql, stats)
+            }
+            Ok(Collected::Kafka(metrics)) => self.with_kafka(metrics, sql, stats),
+            Ok(Collected::MergeTreeSettings(metrics)) => {
+                self.with_merge_tree_settings(metrics, sql, stats)
+            }
+            Ok(Collected::Columns(metrics)) => self.with_columns(metrics, sql, stats),
+            Ok(Collected::Ttl(metrics)) => self.with_ttl(metrics, sql, stats),
+            Ok(Collected::DetachedParts(metrics)) => self.with_detached_parts(metrics, sql, stats),
+            Ok(Collected::Inserts(metrics)) => self.with_inserts(metrics, sql, stats),
+            Err(e) => self.with_failure(run.source, &e, run.duration),
+        }
+    }
+
+    /// Record a collector that failed; rules reading this source are skipped
+    pub fn with_failure(
+        &mut self,
+        source: &str,
+        error: &anyhow::Error,
+        duration: Duration,
+    ) -> &mut Self {
+        self.collection.collectors.push(CollectorStatus {
+            source: source.to_string(),
+            status: CollectionStatus::Failed,
+            error: Some(format!("{:#}", error)),
+            duration_ms: duration.as_millis() as u64,
+        });
+        self
+    }
+
+    fn record_evidence(&mut self, source: &str, sql: &str, stats: &QueryStats) {
+        let id = self.evidence.record_query(source, sql, stats);
+        self.evidence_ids.insert(source.to_string(), id);
+        self.collection.collectors.push(CollectorStatus {
+            source: source.to_string(),
+            status: CollectionStatus::Ok,
+            error: None,
+            duration_ms: stats.duration_ms,
+        });
+    }
+
     /// Run rules and collect findings
     pub fn run_rules(&mut self, registry: &RuleRegistry) -> &mut Self {
         let ctx = self.build_context();
         let results = registry.evaluate_all(&ctx);
+        self.collection.skipped_rules = registry.skipped_rules(&ctx);
 
         for result in results {
             self.findings.push(result.finding);
@@ -104,14 +383,51 @@ impl ReportBuilder {
         for m in self.parts.values() {
             ctx.add_parts(m.clone());
         }
+        for m in self.merge_tree_settings.values() {
+            ctx.add_merge_tree_settings(m.clone());
+        }
         for m in self.merges.values() {
             ctx.add_merges(m.clone());
         }
         for m in self.mutations.values() {
             ctx.add_mutations(m.clone());
         }
+        for m in self.replication.values() {
+            ctx.add_replication(m.clone());
+        }
+        for m in self.distribution_queue.values() {
+            ctx.add_distribution_queue(m.clone());
+        }
+        for m in self.kafka.values() {
+            ctx.add_kafka(m.clone());
+        }
+        ctx.set_dictionaries(self.dictionaries.clone());
+        if let Some(dag) = &self.mv_dag {
+            ctx.set_mv_dag(dag.clone());
+        }
+        if let Some(server) = &self.server {
+            ctx.set_server(server.clone());
+        }
+        for m in self.ttl.values() {
+            ctx.add_ttl(m.clone());
+        }
+        for m in self.detached_parts.values() {
+            ctx.add_detached_parts(m.clone());
+        }
+        for m in self.inserts.values() {
+            ctx.add_inserts(m.clone());
+        }
+        ctx.set_columns(self.columns.clone());
         ctx.set_disk(self.disk.clone());
         ctx.set_queries(self.queries.clone());
+        ctx.set_failed_queries(self.failed_queries.clone());
+        ctx.set_explain(self.explain.clone());
+        for (source, id) in &self.evidence_ids {
+            ctx.set_evidence(source, id);
+        }
+        for collector in self.collection.failed() {
+            ctx.mark_failed(&collector.source);
+        }
 
         ctx
     }
@@ -155,7 +471,85 @@ impl ReportBuilder {
                     queries: self.queries,
                 })
             },
+            replication: if self.replication.is_empty() {
+                None
+            } else {
+                Some(ReplicationSection {
+                    tables: self.replication.into_values().collect(),
+                })
+            },
+            distribution_queue: if self.distribution_queue.is_empty() {
+                None
+            } else {
+                Some(DistributionQueueSection {
+                    tables: self.distribution_queue.into_values().collect(),
+                })
+            },
+            kafka: if self.kafka.is_empty() {
+                None
+            } else {
+                Some(KafkaSection {
+                    tables: self.kafka.into_values().collect(),
+                })
+            },
+            merge_tree_settings: if self.merge_tree_settings.is_empty() {
+                None
+            } else {
+                Some(MergeTreeSettingsSection {
+                    tables: self.merge_tree_settings.into_values().collect(),
+                })
+            },
+            columns: if self.columns.is_empty() {
+                None
+            } else {
+                Some(ColumnsSection {
+                    columns: self.columns,
+                })
+            },
+            ttl: if self.ttl.is_empty() {
+                None
+            } else {
+                Some(TtlSection {
+                    tables: self.ttl.into_values().collect(),
+                })
+            },
+            detached_parts: if self.detached_parts.is_empty() {
+                None
+            } else {
+                Some(DetachedPartsSection {
+                    tables: self.detached_parts.into_values().collect(),
+                })
+            },
+            failed_queries: if self.failed_queries.is_empty() {
+                None
+            } else {
+                Some(FailedQueriesSection {
+                    queries: self.failed_queries,
+                })
+            },
+            inserts: if self.inserts.is_empty() {
+                None
+            } else {
+                Some(InsertsSection {
+                    tables: self.inserts.into_values().collect(),
+                })
+            },
+            dictionaries: if self.dictionaries.is_empty() {
+                None
+            } else {
+                Some(DictionariesSection {
+                    dictionaries: self.dictionaries,
+                })
+            },
             mv_dag: self.mv_dag,
+            server: self.server,
+            explain: if self.explain.is_empty() {
+                None
+            } else {
+                Some(ExplainSection {
+                    statements: self.explain,
+                })
+            },
         };
 
         // Set findings and actions
@@ -187,8 +581,9 @@ impl ReportBuilder {
             warning_count,
         };
 
-        // Set evidence
+        // Set evidence and collection status
         report.evidence = self.evidence.get_all();
+        report.collection = self.collection;
 
         report
     }
@@ -227,6 +622,7 @@ mod tests {
                 ..Default::default()
             }],
             "SELECT * FROM system.parts",
+            &QueryStats::default(),
         );
 
         let report = builder.build();
@@ -245,6 +641,7 @@ mod tests {
                 ..Default::default()
             }],
             "sql",
+            &QueryStats::default(),
         );
 
         let registry = RuleRegistry::with_default_rules();
@@ -268,6 +665,7 @@ mod tests {
                 ..Default::default()
             }],
             "sql",
+            &QueryStats::default(),
         );
 
         // Add disk with warning threshold
@@ -280,6 +678,7 @@ mod tests {
                 free_percent: 15.0,
             }],
             "sql",
+            &QueryStats::default(),
         );
 
         let registry = RuleRegistry::with_default_rules();
@@ -294,11 +693,102 @@ mod tests {
     fn test_builder_evidence_tracking() {
         let mut builder = ReportBuilder::new(targets());
         builder
-            .with_parts(vec![], "sql1")
-            .with_merges(vec![], "sql2")
-            .with_disk(vec![], "sql3");
+            .with_parts(vec![], "sql1", &QueryStats::default())
+            .with_merges(vec![], "sql2", &QueryStats::default())
+            .with_disk(vec![], "sql3", &QueryStats::default());
 
         let report = builder.build();
         assert_eq!(report.evidence.len(), 3);
     }
+
+    #[test]
+    fn test_builder_findings_reference_evidence() {
+        let mut builder = ReportBuilder::new(targets());
+        builder
+            .with_disk(vec![], "sql1", &QueryStats::default())
+            .with_parts(
+                vec![PartsMetrics {
+                    database: "testdb".to_string(),
+                    table: "events".to_string(),
+                    active_parts: 1500,
+                    ..Default::default()
+                }],
+                "sql2",
+                &QueryStats {
+                    query_ids: vec!["q-1".to_string()],
+                    row_count: 1,
+                    duration_ms: 4,
+                },
+            );
+        builder.run_rules(&RuleRegistry::with_default_rules());
+
+        let report = builder.build();
+        let finding = &report.findings[0];
+        assert_eq!(finding.evidence_refs, vec!["ev-002"]);
+
+        let evidence = report
+            .evidence
+            .iter()
+            .find(|e| e.id == finding.evidence_refs[0])
+            .unwrap();
+        assert_eq!(evidence.source, "system.parts");
+        assert_eq!(evidence.query_ids, vec!["q-1"]);
+        assert_eq!(evidence.row_count, 1);
+    }
+
+    #[test]
+    fn test_builder_with_run() {
+        let mut builder = ReportBuilder::new(targets());
+        builder
+            .with_run(CollectorRun {
+                name: "disk",
+                source: "system.disks",
+                sql: "SELECT * FROM system.disks".to_string(),
+                result: Ok(Collected::Disk(vec![DiskMetrics::default()])),
+                stats: QueryStats::default(),
+                duration: Duration::from_millis(3),
+            })
+            .with_run(CollectorRun {
+                name: "query_log",
+                source: "system.query_log",
+                sql: "SELECT * FROM system.query_log".to_string(),
+                result: Err(anyhow::anyhow!("Table system.query_log does not exist")),
+                stats: QueryStats::default(),
+                duration: Duration::from_millis(5),
+            });
+
+        let report = builder.build();
+        assert!(report.sections.disk.is_some());
+        assert_eq!(report.evidence.len(), 1);
+        assert_eq!(report.collection.failed().count(), 1);
+    }
+
+    #[test]
+    fn test_builder_records_failed_collector() {
+        let mut builder = ReportBuilder::new(targets());
+        builder
+            .with_failure(
+                "system.parts",
+                &anyhow::anyhow!("Not enough privileges"),
+                Duration::from_millis(12),
+            )
+            .with_disk(vec![], "sql", &QueryStats::default());
+        builder.run_rules(&RuleRegistry::with_default_rules());
+
+        let report = builder.build();
+        let collectors = &report.collection.collectors;
+        assert_eq!(collectors.len(), 2);
+        assert_eq!(collectors[0].status, CollectionStatus::Failed);
+        assert_eq!(collectors[0].duration_ms, 12);
+        assert!(collectors[0]
+            .error
+            .as_ref()
+            .unwrap()
+            .contains("Not enough privileges"));
+        assert_eq!(collectors[1].status, CollectionStatus::Ok);
+
+        let skipped = &report.collection.skipped_rules;
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].rule_id, "parts_explosion");
+    }
 }
@@ -0,0 +1,380 @@
+use super::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Differences between two audit reports
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ReportDiff {
+    pub old_report_id: String,
+    pub new_report_id: String,
+    pub old_generated_at: String,
+    pub new_generated_at: String,
+    /// Findings present only in the new report
+    pub new_findings: Vec<Finding>,
+    /// Findings present only in the old report
+    pub resolved_findings: Vec<Finding>,
+    /// Findings present in both reports with a different severity or message
+    pub changed_findings: Vec<FindingChange>,
+    pub tables: Vec<TableDelta>,
+    pub disks: Vec<DiskDelta>,
+}
+
+/// A finding matched by rule_id and target whose details changed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FindingChange {
+    pub rule_id: String,
+    pub target: String,
+    pub old_severity: Severity,
+    pub new_severity: Severity,
+    pub old_message: String,
+    pub new_message: String,
+}
+
+/// Per-table metric changes
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TableDelta {
+    pub table: String,
+    pub active_parts: CountDelta,
+    pub bytes_on_disk: CountDelta,
+    pub merges_in_queue: CountDelta,
+}
+
+/// Per-disk free space changes
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DiskDelta {
+    pub disk_name: String,
+    pub free_percent: PercentDelta,
+}
+
+/// Change in an integer metric; a missing side counts as zero
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CountDelta {
+    pub old: Option<u64>,
+    pub new: Option<u64>,
+    pub change: i64,
+}
+
+/// Change in a percentage metric; a missing side counts as zero
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PercentDelta {
+    pub old: Option<f64>,
+    pub new: Option<f64>,
+    pub change: f64,
+}
+
+impl CountDelta {
+    fn new(old: Option<u64>, new: Option<u64>) -> Self {
+        let change = new.unwrap_or(0) as i64 - old.unwrap_or(0) as i64;
+        Self { old, new, change }
+    }
+}
+
+impl PercentDelta {
+    fn new(old: Option<f64>, new: Option<f64>) -> Self {
+        let change = new.unwrap_or(0.0) - old.unwrap_or(0.0);
+        Self { old, new, change }
+    }
+}
+
+impl ReportDiff {
+    /// Compare two reports, matching findings by rule_id and target
+    pub fn compute(old: &Report, new: &Report) -> Self {
+        let old_findings = index_findings(&old.findings);
+        let new_findings = index_findings(&new.findings);
+
+        let mut diff = ReportDiff {
+            old_report_id: old.report_id.clone(),
+            new_report_id: new.report_id.clone(),
+            old_generated_at: old.generated_at.clone(),
+            new_generated_at: new.generated_at.clone(),
+            ..Default::default()
+        };
+
+        for (key, finding) in &new_findings {
+            match old_findings.get(key) {
+                None => diff.new_findings.push((*finding).clone()),
+                Some(previous) => {
+                    if previous.severity != finding.severity || previous.message != finding.message
+                    {
+                        diff.changed_findings.push(FindingChange {
+                            rule_id: finding.rule_id.clone(),
+                            target: finding.target.clone(),
+                            old_severity: previous.severity.clone(),
+                            new_severity: finding.severity.clone(),
+                            old_message: previous.message.clone(),
+                            new_message: finding.message.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        for (key, finding) in &old_findings {
+            if !new_findings.contains_key(key) {
+                diff.resolved_findings.push((*finding).clone());
+            }
+        }
+
+        diff.tables = table_deltas(old, new);
+        diff.disks = disk_deltas(old, new);
+        diff
+    }
+
+    /// Whether anything changed between the two reports
+    pub fn is_empty(&self) -> bool {
+        self.new_findings.is_empty()
+            && self.resolved_findings.is_empty()
+            && self.changed_findings.is_empty()
+            && self.tables.iter().all(|t| {
+                t.active_parts.change == 0
+                    && t.bytes_on_disk.change == 0
+                    && t.merges_in_queue.change == 0
+            })
+            && self.disks.iter().all(|d| d.free_percent.change == 0.0)
+    }
+}
+
+fn index_findings(findings: &[Finding]) -> BTreeMap<(&str, &str), &Finding> {
+    findings
+        .iter()
+        .map(|f| ((f.rule_id.as_str(), f.target.as_str()), f))
+        .collect()
+}
+
+fn parts_by_table(report: &Report) -> BTreeMap<String, &PartsMetrics> {
+    report
+        .sections
+        .parts
+        .iter()
+        .flat_map(|s| &s.tables)
+        .map(|m| (format!("{}.{}", m.database, m.table), m))
+        .collect()
+}
+
+fn merges_by_table(report: &Report) -> BTreeMap<String, &MergeMetrics> {
+    report
+        .sections
+        .merges
+        .iter()
+        .flat_map(|s| &s.tables)
+        .map(|m| (format!("{}.{}", m.database, m.table), m))
+        .collect()
+}
+
+fn table_deltas(old: &Report, new: &Report) -> Vec<TableDelta> {
+    let (old_parts, new_parts) = (parts_by_table(old), parts_by_table(new));
+    let (old_merges, new_merges) = (merges_by_table(old), merges_by_table(new));
+
+    let tables: BTreeSet<&String> = old_parts
+        .keys()
+        .chain(new_parts.keys())
+        .chain(old_merges.keys())
+        .chain(new_merges.keys())
+        .collect();
+
+    tables
+        .into_iter()
+        .map(|table| TableDelta {
+            table: table.clone(),
+            active_parts: CountDelta::new(
+                old_parts.get(table).map(|m| m.active_parts),
+                new_parts.get(table).map(|m| m.active_parts),
+            ),
+            bytes_on_disk: CountDelta::new(
+                old_parts.get(table).map(|m| m.bytes_on_disk),
+                new_parts.get(table).map(|m| m.bytes_on_disk),
+            ),
+            merges_in_queue: CountDelta::new(
+                old_merges.get(table).map(|m| m.merges_in_queue),
+                new_merges.get(table).map(|m| m.merges_in_queue),
+            ),
+        })
+        .collect()
+}
+
+fn disk_deltas(old: &Report, new: &Report) -> Vec<DiskDelta> {
+    let by_name = |report: &Report| -> BTreeMap<String, f64> {
+        report
+            .sections
+            .disk
+            .iter()
+            .flat_map(|s| &s.disks)
+            .map(|d| (d.disk_name.clone(), d.free_percent))
+            .collect()
+    };
+    let (old_disks, new_disks) = (by_name(old), by_name(new));
+
+    let names: BTreeSet<&String> = old_disks.keys().chain(new_disks.keys()).collect();
+
+    names
+        .into_iter()
+        .map(|name| DiskDelta {
+            disk_name: name.clone(),
+            free_percent: PercentDelta::new(
+                old_disks.get(name).copied(),
+                new_disks.get(name).copied(),
+            ),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> Report {
+        Report::new(Targets {
+            endpoint: "http://localhost:8123".to_string(),
+            database: "testdb".to_string(),
+            tables: vec!["events".to_string()],
+        })
+    }
+
+    fn finding(rule_id: &str, target: &str, severity: Severity, message: &str) -> Finding {
+        Finding {
+            id: format!("f-{}", rule_id),
+            rule_id: rule_id.to_string(),
+            severity,
+            target: target.to_string(),
+            message: message.to_string(),
+            evidence_refs: vec![],
+            confidence: 1.0,
+        }
+    }
+
+    fn with_parts(mut report: Report, table: &str, active_parts: u64, bytes: u64) -> Report {
+        report
+            .sections
+            .parts
+            .get_or_insert_with(Default::default)
+            .tables
+            .push(PartsMetrics {
+                database: "testdb".to_string(),
+                table: table.to_string(),
+                active_parts,
+                bytes_on_disk: bytes,
+                ..Default::default()
+            });
+        report
+    }
+
+    #[test]
+    fn test_diff_identical_reports() {
+        let r = with_parts(report(), "events", 10, 1000);
+        let diff = ReportDiff::compute(&r, &r);
+        assert!(diff.is_empty());
+        assert_eq!(diff.tables.len(), 1);
+    }
+
+    #[test]
+    fn test_diff_new_and_resolved_findings() {
+        let mut old = report();
+        old.findings.push(finding(
+            "disk_headroom",
+            "default",
+            Severity::Warning,
+            "low",
+        ));
+
+        let mut new = report();
+        new.findings.push(finding(
+            "parts_explosion",
+            "testdb.events",
+            Severity::Critical,
+            "too many",
+        ));
+
+        let diff = ReportDiff::compute(&old, &new);
+        assert_eq!(diff.new_findings.len(), 1);
+        assert_eq!(diff.new_findings[0].rule_id, "parts_explosion");
+        assert_eq!(diff.resolved_findings.len(), 1);
+        assert_eq!(diff.resolved_findings[0].rule_id, "disk_headroom");
+        assert!(diff.changed_findings.is_empty());
+    }
+
+    #[test]
+    fn test_diff_changed_finding() {
+        let mut old = report();
+        old.findings.push(finding(
+            "parts_explosion",
+            "testdb.events",
+            Severity::Warning,
+            "Table has 500 active parts",
+        ));
+        let mut new = report();
+        new.findings.push(finding(
+            "parts_explosion",
+            "testdb.events",
+            Severity::Critical,
+            "Table has 1500 active parts",
+        ));
+
+        let diff = ReportDiff::compute(&old, &new);
+        assert!(diff.new_findings.is_empty());
+        assert!(diff.resolved_findings.is_empty());
+        assert_eq!(diff.changed_findings.len(), 1);
+        assert_eq!(diff.changed_findings[0].old_severity, Severity::Warning);
+        assert_eq!(diff.changed_findings[0].new_severity, Severity::Critical);
+    }
+
+    #[test]
+    fn test_diff_ignores_finding_id_changes() {
+        let mut old = report();
+        let mut f = finding("parts_explosion", "testdb.events", Severity::Warning, "m");
+        old.findings.push(f.clone());
+        let mut new = report();
+        f.id = "f-parts-7".to_string();
+        new.findings.push(f);
+
+        assert!(ReportDiff::compute(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn test_diff_table_deltas() {
+        let old = with_parts(report(), "events", 100, 5_000);
+        let mut new = with_parts(report(), "events", 40, 7_000);
+        new = with_parts(new, "orders", 3, 100);
+        new.sections.merges = Some(MergesSection {
+            tables: vec![MergeMetrics {
+                database: "testdb".to_string(),
+                table: "events".to_string(),
+                merges_in_queue: 4,
+                ..Default::default()
+            }],
+        });
+
+        let diff = ReportDiff::compute(&old, &new);
+        assert_eq!(diff.tables.len(), 2);
+
+        let events = &diff.tables[0];
+        assert_eq!(events.table, "testdb.events");
+        assert_eq!(events.active_parts.change, -60);
+        assert_eq!(events.bytes_on_disk.change, 2_000);
+        assert_eq!(events.merges_in_queue.old, None);
+        assert_eq!(events.merges_in_queue.change, 4);
+
+        let orders = &diff.tables[1];
+        assert_eq!(orders.active_parts.old, None);
+        assert_eq!(orders.active_parts.new, Some(3));
+    }
+
+    #[test]
+    fn test_diff_disk_deltas() {
+        let disk = |free_percent| DiskSection {
+            disks: vec![DiskMetrics {
+                disk_name: "default".to_string(),
+                free_percent,
+                ..Default::default()
+            }],
+        };
+        let mut old = report();
+        old.sections.disk = Some(disk(30.0));
+        let mut new = report();
+        new.sections.disk = Some(disk(25.5));
+
+        let diff = ReportDiff::compute(&old, &new);
+        assert_eq!(diff.disks.len(), 1);
+        assert_eq!(diff.disks[0].free_percent.change, -4.5);
+        assert!(!diff.is_empty());
+    }
+}
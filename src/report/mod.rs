@@ -1,5 +1,7 @@
 mod builder;
+mod diff;
 mod types;
 
 pub use builder::ReportBuilder;
+pub use diff::{CountDelta, DiskDelta, FindingChange, PercentDelta, ReportDiff, TableDelta};
 pub use types::*;
@@ -12,6 +12,8 @@ pub struct Report {
     pub findings: Vec<Finding>,
     pub actions: Vec<Action>,
     pub evidence: Vec<Evidence>,
+    #[serde(default)]
+    pub collection: CollectionSection,
 }
 
 impl Report {
@@ -26,6 +28,7 @@ impl Report {
             findings: Vec::new(),
             actions: Vec::new(),
             evidence: Vec::new(),
+            collection: CollectionSection::default(),
         }
     }
 }
@@ -72,6 +75,30 @@ pub struct Sections {
     pub query_log: Option<QueryLogSection>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mv_dag: Option<MvDagSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explain: Option<ExplainSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replication: Option<ReplicationSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distribution_queue: Option<DistributionQueueSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kafka: Option<KafkaSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merge_tree_settings: Option<MergeTreeSettingsSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns: Option<ColumnsSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<TtlSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detached_parts: Option<DetachedPartsSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inserts: Option<InsertsSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failed_queries: Option<FailedQueriesSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<ServerSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dictionaries: Option<DictionariesSection>,
 }
 
 /// Parts metrics section
@@ -92,6 +119,96 @@ pub struct PartsMetrics {
     pub oldest_part: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub newest_part: Option<String>,
+    /// Active parts per partition, most fragmented first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub partitions: Vec<PartitionMetrics>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PartitionMetrics {
+    pub partition_id: String,
+    pub partition: String,
+    pub active_parts: u64,
+    pub rows: u64,
+    pub bytes_on_disk: u64,
+    pub min_part_bytes: u64,
+    pub max_part_bytes: u64,
+}
+
+/// Effective merge_tree part limits section
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct MergeTreeSettingsSection {
+    pub tables: Vec<MergeTreeSettingsMetrics>,
+}
+
+/// Part limits in effect for a table: server defaults plus its own `SETTINGS`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct MergeTreeSettingsMetrics {
+    pub database: String,
+    pub table: String,
+    pub parts_to_delay_insert: u64,
+    pub parts_to_throw_insert: u64,
+    pub max_parts_in_total: u64,
+    /// Settings overridden in the table's CREATE statement
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overrides: Vec<String>,
+}
+
+/// Column storage section
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ColumnsSection {
+    pub columns: Vec<ColumnMetrics>,
+}
+
+/// Storage of a single column across the table's active parts
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ColumnMetrics {
+    pub database: String,
+    pub table: String,
+    pub column: String,
+    pub column_type: String,
+    /// Explicit codec, e.g. `CODEC(Delta(4), ZSTD(1))`; `None` means the server default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub codec: Option<String>,
+    pub compressed_bytes: u64,
+    pub uncompressed_bytes: u64,
+    /// Uncompressed / compressed bytes (0 for empty columns)
+    pub compression_ratio: f64,
+    pub rows: u64,
+    pub is_in_sorting_key: bool,
+    /// Distinct values in a sample of the table (String columns only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distinct_values: Option<u64>,
+}
+
+/// TTL and retention section
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TtlSection {
+    pub tables: Vec<TtlMetrics>,
+}
+
+/// Table-level TTL and the state of its active parts
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TtlMetrics {
+    pub database: String,
+    pub table: String,
+    /// Table TTL clause, e.g. `ts + toIntervalDay(30)`; `None` if the table has none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<String>,
+    pub partition_key: String,
+    pub ttl_only_drop_parts: bool,
+    pub bytes_on_disk: u64,
+    /// Seconds since the newest active part was written; `None` for empty tables
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub newest_part_age_sec: Option<u64>,
+    /// Parts whose rows have all passed the delete TTL
+    pub expired_parts: u64,
+    pub expired_bytes: u64,
+    pub oldest_expired_age_sec: u64,
+    /// Parts past a move TTL that are still on the first volume of their policy
+    pub unmoved_parts: u64,
+    pub unmoved_bytes: u64,
+    pub oldest_unmoved_age_sec: u64,
 }
 
 /// Merges metrics section
@@ -126,6 +243,24 @@ pub struct MutationMetrics {
     pub latest_mutation_time: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub oldest_active_mutation_age_sec: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub active: Vec<ActiveMutation>,
+}
+
+/// A mutation that has not finished yet
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ActiveMutation {
+    pub mutation_id: String,
+    pub command: String,
+    pub create_time: String,
+    pub age_sec: u64,
+    pub parts_to_do: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latest_fail_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latest_fail_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latest_fail_age_sec: Option<u64>,
 }
 
 /// Disk metrics section
@@ -143,6 +278,32 @@ pub struct DiskMetrics {
     pub free_percent: f64,
 }
 
+/// Server-wide resource usage section
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ServerSection {
+    /// Memory tracked by the server (MemoryTracking)
+    pub memory_tracked: u64,
+    /// Effective max_server_memory_usage (0 if unknown)
+    pub memory_limit: u64,
+    pub pools: Vec<PoolMetrics>,
+    /// Hits / (hits + misses) since server start; `None` if the cache was never used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mark_cache_hit_ratio: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uncompressed_cache_hit_ratio: Option<f64>,
+    /// Files currently open for reading or writing
+    pub open_files: u64,
+}
+
+/// Usage of one background thread pool
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PoolMetrics {
+    /// "merges_mutations", "fetches" or "moves"
+    pub name: String,
+    pub tasks: u64,
+    pub size: u64,
+}
+
 /// Query log metrics section
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct QueryLogSection {
@@ -161,6 +322,95 @@ pub struct QueryMetrics {
     pub avg_memory_bytes: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sample_query: Option<String>,
+    /// Length of the current window; the previous window has the same length
+    #[serde(default)]
+    pub window_sec: u64,
+    #[serde(default)]
+    pub p50_duration_ms: f64,
+    #[serde(default)]
+    pub p95_duration_ms: f64,
+    #[serde(default)]
+    pub p99_duration_ms: f64,
+    #[serde(default)]
+    pub p50_memory_bytes: u64,
+    #[serde(default)]
+    pub p95_memory_bytes: u64,
+    #[serde(default)]
+    pub p99_memory_bytes: u64,
+    #[serde(default)]
+    pub previous_execution_count: u64,
+    /// None when the query did not run in the previous window
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_p95_duration_ms: Option<f64>,
+    /// Per-run averages of ProfileEvents SelectedMarks, SelectedParts and SelectedRanges
+    #[serde(default)]
+    pub avg_selected_marks: f64,
+    #[serde(default)]
+    pub avg_selected_parts: f64,
+    #[serde(default)]
+    pub avg_selected_ranges: f64,
+    /// Tables read, as database.table
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tables: Vec<String>,
+    /// Active marks and bytes of the audited tables in `tables`, at collection time
+    #[serde(default)]
+    pub table_marks: u64,
+    #[serde(default)]
+    pub table_bytes: u64,
+    /// Fraction of table marks selected per run; None when no audited table was read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub marks_scanned_ratio: Option<f64>,
+}
+
+/// Insert patterns section
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct InsertsSection {
+    pub tables: Vec<InsertMetrics>,
+}
+
+/// Inserts into a single table over the collection window
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct InsertMetrics {
+    pub database: String,
+    pub table: String,
+    pub window_sec: u64,
+    /// INSERT queries that finished (system.query_log)
+    pub inserts: u64,
+    pub async_inserts: u64,
+    pub inserts_per_sec: f64,
+    pub rows: u64,
+    pub median_rows_per_insert: u64,
+    /// Parts written by inserts (NewPart events in system.part_log)
+    pub new_parts: u64,
+    pub parts_per_insert: f64,
+    /// Inserts that wrote to more than one partition
+    pub multi_partition_inserts: u64,
+    pub max_partitions_per_insert: u64,
+}
+
+/// Failed queries section
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FailedQueriesSection {
+    pub queries: Vec<FailedQueryMetrics>,
+}
+
+/// Failures of one normalized query with one error code for one user
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FailedQueryMetrics {
+    pub query_fingerprint: String,
+    pub normalized_query_hash: u64,
+    pub exception_code: i32,
+    /// e.g. "MEMORY_LIMIT_EXCEEDED"
+    pub exception_name: String,
+    pub user: String,
+    pub failures: u64,
+    pub first_seen: String,
+    pub last_seen: String,
+    /// Message of the most recent failure
+    pub sample_exception: String,
+    pub databases: Vec<String>,
+    /// Tables the query touched, as "database.table"
+    pub tables: Vec<String>,
 }
 
 /// MV DAG section
@@ -195,6 +445,206 @@ pub struct MvDagEdge {
     pub to: String,
 }
 
+/// EXPLAIN analysis section (from --sql-file)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ExplainSection {
+    pub statements: Vec<ExplainMetrics>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ExplainMetrics {
+    /// 1-based position of the statement in the SQL file
+    pub statement_index: usize,
+    pub statement: String,
+    pub reads: Vec<ExplainRead>,
+    pub estimates: Vec<ExplainEstimate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pipeline: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A single ReadFromMergeTree step from `EXPLAIN indexes = 1`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ExplainRead {
+    pub table: String,
+    pub indexes: Vec<ExplainIndex>,
+    /// Skip indexes defined on the table (from system.data_skipping_indices)
+    pub available_skip_indexes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ExplainIndex {
+    /// PrimaryKey, MinMax, Partition or Skip
+    pub index_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    pub parts_selected: u64,
+    pub parts_total: u64,
+    pub granules_selected: u64,
+    pub granules_total: u64,
+}
+
+/// A row from `EXPLAIN ESTIMATE`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ExplainEstimate {
+    pub database: String,
+    pub table: String,
+    pub parts: u64,
+    pub rows: u64,
+    pub marks: u64,
+}
+
+/// Replication metrics section (ReplicatedMergeTree tables only)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ReplicationSection {
+    pub tables: Vec<ReplicationMetrics>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ReplicationMetrics {
+    pub database: String,
+    pub table: String,
+    pub replica_name: String,
+    /// Seconds this replica is behind the most recent part on any replica
+    pub absolute_delay: u64,
+    pub queue_size: u64,
+    pub inserts_in_queue: u64,
+    pub merges_in_queue: u64,
+    pub is_readonly: bool,
+    pub is_session_expired: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oldest_queue_entry: Option<ReplicationQueueEntry>,
+    /// The entry with the most tries, which may be younger than the oldest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub most_retried_queue_entry: Option<ReplicationQueueEntry>,
+}
+
+/// An entry in a table's replication queue
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ReplicationQueueEntry {
+    /// GET_PART, MERGE_PARTS, MUTATE_PART, ...
+    pub entry_type: String,
+    pub create_time: String,
+    pub age_sec: u64,
+    pub num_tries: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_exception: Option<String>,
+}
+
+/// Distributed table send queue section
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DistributionQueueSection {
+    pub tables: Vec<DistributionQueueMetrics>,
+}
+
+/// Data waiting on this node to be sent to shards, per Distributed table
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DistributionQueueMetrics {
+    pub database: String,
+    pub table: String,
+    /// Local directory holding the pending files
+    pub data_path: String,
+    pub is_blocked: bool,
+    pub pending_files: u64,
+    pub pending_bytes: u64,
+    pub broken_files: u64,
+    pub error_count: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_exception: Option<String>,
+}
+
+/// Kafka engine consumers section
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct KafkaSection {
+    pub tables: Vec<KafkaTableMetrics>,
+}
+
+/// Consumers of a single Kafka-engine table
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct KafkaTableMetrics {
+    pub database: String,
+    pub table: String,
+    pub consumers: Vec<KafkaConsumerMetrics>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct KafkaConsumerMetrics {
+    pub consumer_id: String,
+    pub assignments: Vec<KafkaAssignment>,
+    pub last_poll_time: String,
+    pub last_commit_time: String,
+    /// Seconds since the last commit; `None` if the consumer never committed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_age_sec: Option<u64>,
+    pub num_commits: u64,
+    pub num_messages_read: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_exception: Option<String>,
+    pub is_currently_used: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct KafkaAssignment {
+    pub topic: String,
+    pub partition_id: i64,
+    pub current_offset: i64,
+}
+
+/// Detached parts section
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DetachedPartsSection {
+    pub tables: Vec<DetachedPartsMetrics>,
+}
+
+/// Detached parts of a single table, grouped by reason
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DetachedPartsMetrics {
+    pub database: String,
+    pub table: String,
+    pub reasons: Vec<DetachedReasonMetrics>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DetachedReasonMetrics {
+    /// e.g. "broken", "unexpected", "ignored", "clone"; empty for parts detached with DETACH PART
+    pub reason: String,
+    pub parts: u64,
+    pub bytes_on_disk: u64,
+    /// Directory names under `detached/` (first few only)
+    pub part_names: Vec<String>,
+}
+
+/// Dictionaries section
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DictionariesSection {
+    pub dictionaries: Vec<DictionaryMetrics>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DictionaryMetrics {
+    pub database: String,
+    pub name: String,
+    /// e.g. "LOADED", "FAILED", "FAILED_AND_RELOADING", "NOT_LOADED"
+    pub status: String,
+    /// Layout, e.g. "Hashed"
+    pub dictionary_type: String,
+    pub source: String,
+    pub lifetime_min: u64,
+    pub lifetime_max: u64,
+    pub last_successful_update_time: String,
+    /// Seconds since the last successful update; `None` if never loaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_age_sec: Option<u64>,
+    pub loading_duration_sec: f64,
+    pub bytes_allocated: u64,
+    pub element_count: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_exception: Option<String>,
+}
+
 /// Finding from rule evaluation
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Finding {
@@ -225,6 +675,9 @@ pub struct Action {
     pub description: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sql: Option<String>,
+    /// The SQL irreversibly deletes data; review before running
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub destructive: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -242,6 +695,48 @@ pub enum Priority {
     Low,
 }
 
+/// Outcome of metric collection: which sources failed and which rules were skipped
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CollectionSection {
+    pub collectors: Vec<CollectorStatus>,
+    pub skipped_rules: Vec<SkippedRule>,
+}
+
+/// Status of a single collector run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CollectorStatus {
+    /// Metric source, e.g. "system.parts"
+    pub source: String,
+    pub status: CollectionStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CollectionStatus {
+    Ok,
+    Failed,
+}
+
+/// A rule that was not evaluated because its inputs could not be collected
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SkippedRule {
+    pub rule_id: String,
+    /// Sources that failed to collect
+    pub missing_inputs: Vec<String>,
+}
+
+impl CollectionSection {
+    /// Sources whose collector failed
+    pub fn failed(&self) -> impl Iterator<Item = &CollectorStatus> {
+        self.collectors
+            .iter()
+            .filter(|c| c.status == CollectionStatus::Failed)
+    }
+}
+
 /// Evidence linking findings to source data
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Evidence {
@@ -249,6 +744,15 @@ pub struct Evidence {
     pub source: String,
     pub sql: String,
     pub collected_at: String,
+    /// Rows returned by the source queries
+    #[serde(default)]
+    pub row_count: u64,
+    /// Time spent running the source queries
+    #[serde(default)]
+    pub duration_ms: u64,
+    /// Server-side `query_id` of each source query, for lookup in system.query_log
+    #[serde(default)]
+    pub query_ids: Vec<String>,
 }
 
 #[cfg(test)]
@@ -280,6 +784,15 @@ mod tests {
         assert_eq!(report.summary.status, parsed.summary.status);
     }
 
+    #[test]
+    fn test_report_without_collection_section_parses() {
+        let mut json = serde_json::to_value(sample_report()).unwrap();
+        json.as_object_mut().unwrap().remove("collection");
+
+        let parsed: Report = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.collection, CollectionSection::default());
+    }
+
     #[test]
     fn test_report_status_serialization() {
         assert_eq!(
@@ -330,6 +843,15 @@ mod tests {
             bytes_on_disk: 500_000_000,
             oldest_part: Some("2024-01-01T00:00:00Z".to_string()),
             newest_part: Some("2024-01-15T00:00:00Z".to_string()),
+            partitions: vec![PartitionMetrics {
+                partition_id: "202401".to_string(),
+                partition: "202401".to_string(),
+                active_parts: 50,
+                rows: 1_000_000,
+                bytes_on_disk: 500_000_000,
+                min_part_bytes: 1_000_000,
+                max_part_bytes: 100_000_000,
+            }],
         };
 
         let json = serde_json::to_string(&metrics).unwrap();
@@ -367,6 +889,7 @@ mod tests {
             priority: Priority::High,
             description: "Run OPTIMIZE TABLE".to_string(),
             sql: Some("OPTIMIZE TABLE testdb.events FINAL".to_string()),
+            destructive: false,
         };
 
         let json = serde_json::to_string(&action).unwrap();
@@ -386,11 +909,13 @@ mod tests {
             bytes_on_disk: 500_000_000,
             oldest_part: None,
             newest_part: None,
+            partitions: vec![],
         };
 
         let json = serde_json::to_string(&metrics).unwrap();
         assert!(!json.contains("oldest_part"));
         assert!(!json.contains("newest_part"));
+        assert!(!json.contains("partitions"));
     }
 
     #[test]
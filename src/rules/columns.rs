@@ -0,0 +1,544 @@
+use super::{action_id, finding_id, AuditContext, Rule, RuleResult};
+use crate::ch::sql::{identifier, table_name};
+use crate::config::RuleConfig;
+use crate::report::{Action, ActionType, ColumnMetrics, Finding, Priority, Severity};
+
+const GIB: f64 = 1_073_741_824.0;
+const MIB: f64 = 1_048_576.0;
+
+/// Defaults for poorly compressed columns
+const POOR_COMPRESSION_MIN_BYTES: f64 = GIB;
+const POOR_COMPRESSION_MIN_RATIO: f64 = 2.0;
+
+/// Defaults for LowCardinality candidates
+const LOW_CARDINALITY_MAX_DISTINCT: u64 = 10_000;
+const LOW_CARDINALITY_MIN_ROWS: u64 = 1_000_000;
+
+/// Default size above which a specialized codec is worth proposing
+const CODEC_MIN_BYTES: f64 = 100.0 * MIB;
+
+/// Rule to detect large columns that compress poorly with the default codec
+#[derive(Default)]
+pub struct PoorCompressionRule {
+    config: RuleConfig,
+}
+
+/// Rule to detect String columns with few distinct values
+#[derive(Default)]
+pub struct LowCardinalityRule {
+    config: RuleConfig,
+}
+
+/// Rule to detect Nullable columns in the sorting key
+#[derive(Default)]
+pub struct NullableSortingKeyRule {
+    config: RuleConfig,
+}
+
+/// Rule to detect time and sequence columns that would benefit from Delta/DoubleDelta
+#[derive(Default)]
+pub struct CodecRule {
+    config: RuleConfig,
+}
+
+impl PoorCompressionRule {
+    pub const ID: &'static str = "column_poor_compression";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl LowCardinalityRule {
+    pub const ID: &'static str = "column_low_cardinality";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl NullableSortingKeyRule {
+    pub const ID: &'static str = "nullable_sorting_key";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl CodecRule {
+    pub const ID: &'static str = "column_codec";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Rule for PoorCompressionRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Poorly Compressed Column"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.columns"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["min_bytes", "min_ratio"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for column in &ctx.columns {
+            let table_key = table_key(column);
+            if !self.config.enabled_for(&table_key) {
+                continue;
+            }
+
+            let min_bytes =
+                self.config
+                    .threshold(&table_key, "min_bytes", POOR_COMPRESSION_MIN_BYTES);
+            let min_ratio =
+                self.config
+                    .threshold(&table_key, "min_ratio", POOR_COMPRESSION_MIN_RATIO);
+
+            // Data that ZSTD cannot compress is not going to improve
+            let has_zstd = column.codec.as_deref().is_some_and(|c| c.contains("ZSTD"));
+            if has_zstd
+                || (column.compressed_bytes as f64) < min_bytes
+                || column.compression_ratio >= min_ratio
+            {
+                continue;
+            }
+
+            results.push(proposal(
+                self.id(),
+                ctx,
+                column,
+                format!(
+                    "Column {} ({}) takes {:.1}GB compressed with a ratio of only {:.2}",
+                    column.column,
+                    column.column_type,
+                    column.compressed_bytes as f64 / GIB,
+                    column.compression_ratio
+                ),
+                "Compress the column with ZSTD",
+                Some(format!("{} CODEC(ZSTD(3))", column.column_type)),
+            ));
+        }
+
+        results
+    }
+}
+
+impl Rule for LowCardinalityRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "LowCardinality Candidate"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.columns"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["max_distinct", "min_rows"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for column in &ctx.columns {
+            let table_key = table_key(column);
+            if !self.config.enabled_for(&table_key) {
+                continue;
+            }
+
+            let Some(distinct) = column.distinct_values else {
+                continue;
+            };
+            // Changing the type of a key column is not allowed in place
+            if column.is_in_sorting_key {
+                continue;
+            }
+
+            let max_distinct = self.config.threshold(
+                &table_key,
+                "max_distinct",
+                LOW_CARDINALITY_MAX_DISTINCT as f64,
+            ) as u64;
+            let min_rows =
+                self.config
+                    .threshold(&table_key, "min_rows", LOW_CARDINALITY_MIN_ROWS as f64)
+                    as u64;
+            if distinct > max_distinct || column.rows < min_rows {
+                continue;
+            }
+
+            let ddl = format!(
+                "LowCardinality({}){}",
+                column.column_type,
+                codec_suffix(column)
+            );
+            results.push(proposal(
+                self.id(),
+                ctx,
+                column,
+                format!(
+                    "Column {} ({}) has about {} distinct values in {} rows",
+                    column.column, column.column_type, distinct, column.rows
+                ),
+                "Store the column as LowCardinality to dictionary-encode it",
+                Some(ddl),
+            ));
+        }
+
+        results
+    }
+}
+
+impl Rule for NullableSortingKeyRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Nullable Sorting Key Column"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.columns"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for column in &ctx.columns {
+            if !self.config.enabled_for(&table_key(column)) || !column.is_in_sorting_key {
+                continue;
+            }
+            let Some(inner) = unwrap_type(&column.column_type, "Nullable") else {
+                continue;
+            };
+
+            results.push(proposal(
+                self.id(),
+                ctx,
+                column,
+                format!(
+                    "Sorting key column {} is {}: the null map is stored and checked for every granule",
+                    column.column, column.column_type
+                ),
+                &format!(
+                    "Key columns cannot be altered in place: create a new table with {} {}{} and a DEFAULT, copy the data with INSERT ... SELECT coalesce({}, ...), then swap the tables",
+                    identifier(&column.column),
+                    inner,
+                    codec_suffix(column),
+                    identifier(&column.column)
+                ),
+                None,
+            ));
+        }
+
+        results
+    }
+}
+
+impl Rule for CodecRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Specialized Codec Candidate"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.columns"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["min_bytes"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for column in &ctx.columns {
+            let table_key = table_key(column);
+            if !self.config.enabled_for(&table_key) || column.codec.is_some() {
+                continue;
+            }
+
+            let min_bytes = self
+                .config
+                .threshold(&table_key, "min_bytes", CODEC_MIN_BYTES);
+            if (column.compressed_bytes as f64) < min_bytes {
+                continue;
+            }
+
+            let Some((codec, reason)) = suggested_codec(column) else {
+                continue;
+            };
+
+            results.push(proposal(
+                self.id(),
+                ctx,
+                column,
+                format!(
+                    "Column {} ({}) takes {:.1}MB with the default codec; {}",
+                    column.column,
+                    column.column_type,
+                    column.compressed_bytes as f64 / MIB,
+                    reason
+                ),
+                "Add a specialized codec",
+                Some(format!("{} CODEC({})", column.column_type, codec)),
+            ));
+        }
+
+        results
+    }
+}
+
+/// Codec for columns whose values change slowly from row to row
+fn suggested_codec(column: &ColumnMetrics) -> Option<(&'static str, &'static str)> {
+    let base = unwrap_type(&column.column_type, "Nullable").unwrap_or(&column.column_type);
+
+    if base.starts_with("Date") {
+        Some((
+            "DoubleDelta, ZSTD(1)",
+            "timestamps compress far better as delta-of-delta",
+        ))
+    } else if column.is_in_sorting_key && (base.starts_with("Int") || base.starts_with("UInt")) {
+        Some((
+            "Delta, ZSTD(1)",
+            "sorted integers compress far better as deltas",
+        ))
+    } else {
+        None
+    }
+}
+
+/// `T` from `Wrapper(T)`
+fn unwrap_type<'a>(column_type: &'a str, wrapper: &str) -> Option<&'a str> {
+    column_type
+        .strip_prefix(wrapper)?
+        .strip_prefix('(')?
+        .strip_suffix(')')
+}
+
+/// Keep an explicit codec when proposing a new type
+fn codec_suffix(column: &ColumnMetrics) -> String {
+    column
+        .codec
+        .as_ref()
+        .map(|c| format!(" {}", c))
+        .unwrap_or_default()
+}
+
+fn table_key(column: &ColumnMetrics) -> String {
+    format!("{}.{}", column.database, column.table)
+}
+
+fn target(column: &ColumnMetrics) -> String {
+    format!("{}.{}.{}", column.database, column.table, column.column)
+}
+
+/// Warning with a single `ALTER TABLE ... MODIFY COLUMN` proposal
+fn proposal(
+    rule_id: &str,
+    ctx: &AuditContext,
+    column: &ColumnMetrics,
+    message: String,
+    description: &str,
+    definition: Option<String>,
+) -> RuleResult {
+    let id = finding_id(rule_id, &target(column), &Severity::Warning);
+    RuleResult {
+        finding: Finding {
+            id: id.clone(),
+            rule_id: rule_id.to_string(),
+            severity: Severity::Warning,
+            target: target(column),
+            message,
+            evidence_refs: ctx.evidence_refs("system.columns"),
+            confidence: 1.0,
+        },
+        actions: vec![Action {
+            id: action_id(&id, 0),
+            finding_ref: id,
+            action_type: ActionType::DdlProposal,
+            priority: Priority::Low,
+            description: description.to_string(),
+            sql: definition.map(|definition| {
+                format!(
+                    "ALTER TABLE {} MODIFY COLUMN {} {}",
+                    table_name(&column.database, &column.table),
+                    identifier(&column.column),
+                    definition
+                )
+            }),
+            destructive: false,
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, column_type: &str) -> ColumnMetrics {
+        ColumnMetrics {
+            database: "testdb".to_string(),
+            table: "events".to_string(),
+            column: name.to_string(),
+            column_type: column_type.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn ctx_with(columns: Vec<ColumnMetrics>) -> AuditContext {
+        let mut ctx = AuditContext::new();
+        ctx.set_columns(columns);
+        ctx
+    }
+
+    #[test]
+    fn test_poor_compression() {
+        let ctx = ctx_with(vec![ColumnMetrics {
+            compressed_bytes: 2 * GIB as u64,
+            compression_ratio: 1.2,
+            ..column("payload", "String")
+        }]);
+
+        let results = PoorCompressionRule::default().evaluate(&ctx);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].finding.target, "testdb.events.payload");
+        assert_eq!(results[0].actions[0].action_type, ActionType::DdlProposal);
+        assert_eq!(
+            results[0].actions[0].sql.as_deref(),
+            Some("ALTER TABLE testdb.events MODIFY COLUMN payload String CODEC(ZSTD(3))")
+        );
+    }
+
+    #[test]
+    fn test_poor_compression_ignores_small_and_zstd_columns() {
+        let small = ColumnMetrics {
+            compressed_bytes: 1000,
+            compression_ratio: 1.2,
+            ..column("payload", "String")
+        };
+        let zstd = ColumnMetrics {
+            compressed_bytes: 2 * GIB as u64,
+            compression_ratio: 1.2,
+            codec: Some("CODEC(ZSTD(1))".to_string()),
+            ..column("blob", "String")
+        };
+        assert!(PoorCompressionRule::default()
+            .evaluate(&ctx_with(vec![small, zstd]))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_low_cardinality_candidate() {
+        let ctx = ctx_with(vec![ColumnMetrics {
+            rows: 50_000_000,
+            distinct_values: Some(180),
+            ..column("country", "Nullable(String)")
+        }]);
+
+        let results = LowCardinalityRule::default().evaluate(&ctx);
+        assert_eq!(
+            results[0].actions[0].sql.as_deref(),
+            Some(
+                "ALTER TABLE testdb.events MODIFY COLUMN country LowCardinality(Nullable(String))"
+            )
+        );
+        assert!(results[0]
+            .finding
+            .message
+            .contains("about 180 distinct values"));
+    }
+
+    #[test]
+    fn test_low_cardinality_skips_unique_and_key_columns() {
+        let unique = ColumnMetrics {
+            rows: 50_000_000,
+            distinct_values: Some(99_000),
+            ..column("session_id", "String")
+        };
+        let key = ColumnMetrics {
+            rows: 50_000_000,
+            distinct_values: Some(10),
+            is_in_sorting_key: true,
+            ..column("tenant", "String")
+        };
+        assert!(LowCardinalityRule::default()
+            .evaluate(&ctx_with(vec![unique, key]))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_nullable_sorting_key() {
+        let ctx = ctx_with(vec![
+            ColumnMetrics {
+                is_in_sorting_key: true,
+                ..column("user_id", "Nullable(UInt64)")
+            },
+            column("referrer", "Nullable(String)"),
+        ]);
+
+        let results = NullableSortingKeyRule::default().evaluate(&ctx);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].actions[0].sql, None);
+        assert!(results[0].actions[0]
+            .description
+            .contains("create a new table with user_id UInt64"));
+    }
+
+    #[test]
+    fn test_codec_candidates() {
+        let large = |c: ColumnMetrics| ColumnMetrics {
+            compressed_bytes: 500 * MIB as u64,
+            ..c
+        };
+        let ctx = ctx_with(vec![
+            large(column("ts", "DateTime64(3)")),
+            large(ColumnMetrics {
+                is_in_sorting_key: true,
+                ..column("id", "UInt64")
+            }),
+            large(column("amount", "UInt64")),
+            large(ColumnMetrics {
+                codec: Some("CODEC(Delta(8), LZ4)".to_string()),
+                ..column("created", "DateTime")
+            }),
+        ]);
+
+        let mut results = CodecRule::default().evaluate(&ctx);
+        results.sort_by(|a, b| a.finding.target.cmp(&b.finding.target));
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[0].actions[0].sql.as_deref(),
+            Some("ALTER TABLE testdb.events MODIFY COLUMN id UInt64 CODEC(Delta, ZSTD(1))")
+        );
+        assert_eq!(
+            results[1].actions[0].sql.as_deref(),
+            Some("ALTER TABLE testdb.events MODIFY COLUMN ts DateTime64(3) CODEC(DoubleDelta, ZSTD(1))")
+        );
+    }
+
+    #[test]
+    fn test_unwrap_type() {
+        assert_eq!(unwrap_type("Nullable(String)", "Nullable"), Some("String"));
+        assert_eq!(unwrap_type("String", "Nullable"), None);
+    }
+}
@@ -1,19 +1,54 @@
-use crate::report::{DiskMetrics, MergeMetrics, MutationMetrics, PartsMetrics, QueryMetrics};
-use std::collections::HashMap;
+use crate::report::{
+    ColumnMetrics, DetachedPartsMetrics, DictionaryMetrics, DiskMetrics, DistributionQueueMetrics,
+    ExplainMetrics, FailedQueryMetrics, InsertMetrics, KafkaTableMetrics, MergeMetrics,
+    MergeTreeSettingsMetrics, MutationMetrics, MvDagSection, PartsMetrics, QueryMetrics,
+    ReplicationMetrics, ServerSection, TtlMetrics,
+};
+use std::collections::{HashMap, HashSet};
 
 /// Context passed to rules for evaluation
 #[derive(Debug, Default)]
 pub struct AuditContext {
     /// Parts metrics per table (key: "database.table")
     pub parts: HashMap<String, PartsMetrics>,
+    /// Effective part limits per table
+    pub merge_tree_settings: HashMap<String, MergeTreeSettingsMetrics>,
     /// Merge metrics per table
     pub merges: HashMap<String, MergeMetrics>,
     /// Mutation metrics per table
     pub mutations: HashMap<String, MutationMetrics>,
+    /// Replication metrics per table (ReplicatedMergeTree only)
+    pub replication: HashMap<String, ReplicationMetrics>,
+    /// Send queue metrics per Distributed table
+    pub distribution_queue: HashMap<String, DistributionQueueMetrics>,
+    /// Kafka consumers per Kafka-engine table
+    pub kafka: HashMap<String, KafkaTableMetrics>,
+    /// Dictionaries in the audited databases
+    pub dictionaries: Vec<DictionaryMetrics>,
+    /// MV dependency graph, if collected
+    pub mv_dag: Option<MvDagSection>,
+    /// TTL state per table
+    pub ttl: HashMap<String, TtlMetrics>,
+    /// Detached parts per table (tables without detached parts are absent)
+    pub detached_parts: HashMap<String, DetachedPartsMetrics>,
+    /// Column storage metrics
+    pub columns: Vec<ColumnMetrics>,
+    /// Server resource usage (global), if collected
+    pub server: Option<ServerSection>,
     /// Disk metrics (global)
     pub disk: Vec<DiskMetrics>,
+    /// Insert patterns per table (tables without inserts are absent)
+    pub inserts: HashMap<String, InsertMetrics>,
     /// Query metrics
     pub queries: Vec<QueryMetrics>,
+    /// Failed queries, grouped by query, error code and user
+    pub failed_queries: Vec<FailedQueryMetrics>,
+    /// EXPLAIN results for statements from --sql-file
+    pub explain: Vec<ExplainMetrics>,
+    /// Evidence ID per metric source (key: e.g. "system.parts")
+    pub evidence: HashMap<String, String>,
+    /// Metric sources whose collector failed
+    pub failed: HashSet<String>,
 }
 
 impl AuditContext {
@@ -28,6 +63,30 @@ impl AuditContext {
         self.parts.insert(key, metrics);
     }
 
+    /// Add effective part limits for a table
+    pub fn add_merge_tree_settings(&mut self, metrics: MergeTreeSettingsMetrics) {
+        let key = format!("{}.{}", metrics.database, metrics.table);
+        self.merge_tree_settings.insert(key, metrics);
+    }
+
+    /// Add TTL state for a table
+    pub fn add_ttl(&mut self, metrics: TtlMetrics) {
+        let key = format!("{}.{}", metrics.database, metrics.table);
+        self.ttl.insert(key, metrics);
+    }
+
+    /// Add detached parts for a table
+    pub fn add_detached_parts(&mut self, metrics: DetachedPartsMetrics) {
+        let key = format!("{}.{}", metrics.database, metrics.table);
+        self.detached_parts.insert(key, metrics);
+    }
+
+    /// Add insert patterns for a table
+    pub fn add_inserts(&mut self, metrics: InsertMetrics) {
+        let key = format!("{}.{}", metrics.database, metrics.table);
+        self.inserts.insert(key, metrics);
+    }
+
     /// Add merge metrics for a table
     pub fn add_merges(&mut self, metrics: MergeMetrics) {
         let key = format!("{}.{}", metrics.database, metrics.table);
@@ -40,15 +99,84 @@ impl AuditContext {
         self.mutations.insert(key, metrics);
     }
 
+    /// Add replication metrics for a table
+    pub fn add_replication(&mut self, metrics: ReplicationMetrics) {
+        let key = format!("{}.{}", metrics.database, metrics.table);
+        self.replication.insert(key, metrics);
+    }
+
+    /// Add send queue metrics for a Distributed table
+    pub fn add_distribution_queue(&mut self, metrics: DistributionQueueMetrics) {
+        let key = format!("{}.{}", metrics.database, metrics.table);
+        self.distribution_queue.insert(key, metrics);
+    }
+
+    /// Add consumer metrics for a Kafka-engine table
+    pub fn add_kafka(&mut self, metrics: KafkaTableMetrics) {
+        let key = format!("{}.{}", metrics.database, metrics.table);
+        self.kafka.insert(key, metrics);
+    }
+
+    /// Set dictionary metrics
+    pub fn set_dictionaries(&mut self, metrics: Vec<DictionaryMetrics>) {
+        self.dictionaries = metrics;
+    }
+
+    /// Set the MV dependency graph
+    pub fn set_mv_dag(&mut self, dag: MvDagSection) {
+        self.mv_dag = Some(dag);
+    }
+
+    /// Set server resource usage
+    pub fn set_server(&mut self, server: ServerSection) {
+        self.server = Some(server);
+    }
+
     /// Set disk metrics
     pub fn set_disk(&mut self, metrics: Vec<DiskMetrics>) {
         self.disk = metrics;
     }
 
+    /// Set column storage metrics
+    pub fn set_columns(&mut self, metrics: Vec<ColumnMetrics>) {
+        self.columns = metrics;
+    }
+
     /// Set query metrics
     pub fn set_queries(&mut self, metrics: Vec<QueryMetrics>) {
         self.queries = metrics;
     }
+
+    /// Set failed query metrics
+    pub fn set_failed_queries(&mut self, metrics: Vec<FailedQueryMetrics>) {
+        self.failed_queries = metrics;
+    }
+
+    /// Set EXPLAIN results
+    pub fn set_explain(&mut self, metrics: Vec<ExplainMetrics>) {
+        self.explain = metrics;
+    }
+
+    /// Set the evidence ID for a metric source
+    pub fn set_evidence(&mut self, source: &str, evidence_id: &str) {
+        self.evidence
+            .insert(source.to_string(), evidence_id.to_string());
+    }
+
+    /// Mark a metric source as failed to collect
+    pub fn mark_failed(&mut self, source: &str) {
+        self.failed.insert(source.to_string());
+    }
+
+    /// Whether the collector for a metric source failed
+    pub fn is_failed(&self, source: &str) -> bool {
+        self.failed.contains(source)
+    }
+
+    /// Evidence references for findings derived from the given source
+    pub fn evidence_refs(&self, source: &str) -> Vec<String> {
+        self.evidence.get(source).cloned().into_iter().collect()
+    }
 }
 
 #[cfg(test)]
@@ -93,4 +221,13 @@ mod tests {
 
         assert_eq!(ctx.parts.len(), 2);
     }
+
+    #[test]
+    fn test_context_evidence_refs() {
+        let mut ctx = AuditContext::new();
+        ctx.set_evidence("system.parts", "ev-001");
+
+        assert_eq!(ctx.evidence_refs("system.parts"), vec!["ev-001"]);
+        assert!(ctx.evidence_refs("system.disks").is_empty());
+    }
 }
@@ -0,0 +1,302 @@
+use super::{action_id, finding_id, AuditContext, Rule, RuleResult};
+use crate::ch::sql::{literal, table_name};
+use crate::config::RuleConfig;
+use crate::report::{
+    Action, ActionType, DetachedPartsMetrics, DetachedReasonMetrics, Finding, Priority, Severity,
+};
+
+const GIB: f64 = 1_073_741_824.0;
+
+/// Default detached size that is worth cleaning up
+const WARNING_BYTES: f64 = GIB;
+
+/// Rule to detect broken detached parts and detached data wasting disk
+#[derive(Default)]
+pub struct DetachedPartsRule {
+    config: RuleConfig,
+}
+
+impl DetachedPartsRule {
+    pub const ID: &'static str = "detached_parts";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Rule for DetachedPartsRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Detached Parts"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.detached_parts"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["warning_bytes"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for (table_key, detached) in &ctx.detached_parts {
+            if !self.config.enabled_for(table_key) {
+                continue;
+            }
+
+            let warning_bytes = self
+                .config
+                .threshold(table_key, "warning_bytes", WARNING_BYTES);
+            let broken: u64 = detached
+                .reasons
+                .iter()
+                .filter(|r| is_broken(&r.reason))
+                .map(|r| r.parts)
+                .sum();
+            let total_bytes: u64 = detached.reasons.iter().map(|r| r.bytes_on_disk).sum();
+
+            let severity = if broken > 0 {
+                Severity::Critical
+            } else if total_bytes as f64 >= warning_bytes {
+                Severity::Warning
+            } else {
+                continue;
+            };
+
+            let id = finding_id(self.id(), table_key, &severity);
+            let mut actions = Vec::new();
+            if broken > 0 {
+                actions.push(Action {
+                    id: action_id(&id, actions.len()),
+                    finding_ref: id.clone(),
+                    action_type: ActionType::Recommendation,
+                    priority: Priority::High,
+                    description: "Check the server log for why the parts were broken; a replicated table fetches them again from another replica, otherwise their rows are missing from the table".to_string(),
+                    sql: Some(format!(
+                        "SELECT name, reason, disk, path, bytes_on_disk FROM system.detached_parts WHERE database = {} AND table = {} AND startsWith(reason, 'broken')",
+                        literal(&detached.database),
+                        literal(&detached.table)
+                    )),
+                    destructive: false,
+                });
+            }
+            for reason in &detached.reasons {
+                actions.push(reason_action(detached, reason, &id, actions.len()));
+            }
+
+            results.push(RuleResult {
+                finding: Finding {
+                    id,
+                    rule_id: self.id().to_string(),
+                    severity,
+                    target: table_key.clone(),
+                    message: message(detached, broken, total_bytes),
+                    evidence_refs: ctx.evidence_refs("system.detached_parts"),
+                    confidence: 1.0,
+                },
+                actions,
+            });
+        }
+
+        results
+    }
+}
+
+/// Parts detached because they failed a checksum or could not be read
+fn is_broken(reason: &str) -> bool {
+    reason.starts_with("broken")
+}
+
+fn message(detached: &DetachedPartsMetrics, broken: u64, total_bytes: u64) -> String {
+    let reasons: Vec<String> = detached
+        .reasons
+        .iter()
+        .map(|r| {
+            format!(
+                "{} {}",
+                r.parts,
+                if r.reason.is_empty() {
+                    "manually detached"
+                } else {
+                    &r.reason
+                }
+            )
+        })
+        .collect();
+    let summary = format!(
+        "{:.1}GB of detached parts ({})",
+        total_bytes as f64 / GIB,
+        reasons.join(", ")
+    );
+    if broken > 0 {
+        format!("{} broken parts were detached; {}", broken, summary)
+    } else {
+        format!("{} are wasting disk", summary)
+    }
+}
+
+/// Reattach manually detached parts; drop everything else
+///
+/// Parts detached with DETACH PART keep a plain part name and can be
+/// attached again. Other reasons prefix the directory name, and those parts
+/// are either covered by other parts or damaged.
+fn reason_action(
+    detached: &DetachedPartsMetrics,
+    reason: &DetachedReasonMetrics,
+    finding_id: &str,
+    n: usize,
+) -> Action {
+    let table = table_name(&detached.database, &detached.table);
+    let listed = if reason.parts > reason.part_names.len() as u64 {
+        format!("the first {} of {}", reason.part_names.len(), reason.parts)
+    } else {
+        reason.parts.to_string()
+    };
+
+    let (priority, description, statements, destructive) = if reason.reason.is_empty() {
+        (
+            Priority::Medium,
+            format!(
+                "Attach {} manually detached parts back if they are still needed, otherwise drop them",
+                listed
+            ),
+            reason
+                .part_names
+                .iter()
+                .map(|name| format!("ALTER TABLE {} ATTACH PART {}", table, literal(name)))
+                .collect::<Vec<_>>(),
+            false,
+        )
+    } else {
+        (
+            Priority::Low,
+            format!(
+                "Delete {} detached '{}' parts ({:.1}GB) from disk{}",
+                listed,
+                reason.reason,
+                reason.bytes_on_disk as f64 / GIB,
+                if is_broken(&reason.reason) {
+                    " once they are confirmed unrecoverable"
+                } else {
+                    ""
+                }
+            ),
+            reason
+                .part_names
+                .iter()
+                .map(|name| {
+                    format!(
+                        "ALTER TABLE {} DROP DETACHED PART {} SETTINGS allow_drop_detached = 1",
+                        table,
+                        literal(name)
+                    )
+                })
+                .collect(),
+            true,
+        )
+    };
+
+    Action {
+        id: action_id(finding_id, n),
+        finding_ref: finding_id.to_string(),
+        action_type: ActionType::Recommendation,
+        priority,
+        description,
+        sql: Some(statements.join(";\n")),
+        destructive,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reason(reason: &str, parts: u64, bytes_on_disk: u64) -> DetachedReasonMetrics {
+        DetachedReasonMetrics {
+            reason: reason.to_string(),
+            parts,
+            bytes_on_disk,
+            part_names: (0..parts.min(2))
+                .map(|i| {
+                    if reason.is_empty() {
+                        format!("all_{}_{}_0", i, i)
+                    } else {
+                        format!("{}_all_{}_{}_0", reason, i, i)
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    fn ctx_with(reasons: Vec<DetachedReasonMetrics>) -> AuditContext {
+        let mut ctx = AuditContext::new();
+        ctx.add_detached_parts(DetachedPartsMetrics {
+            database: "testdb".to_string(),
+            table: "events".to_string(),
+            reasons,
+        });
+        ctx
+    }
+
+    #[test]
+    fn test_broken_parts_are_critical() {
+        let ctx = ctx_with(vec![reason("broken-on-start", 1, 1000)]);
+        let results = DetachedPartsRule::default().evaluate(&ctx);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+        assert!(results[0]
+            .finding
+            .message
+            .starts_with("1 broken parts were detached"));
+
+        let actions = &results[0].actions;
+        assert_eq!(actions.len(), 2);
+        assert!(!actions[0].destructive);
+        assert!(actions[1].destructive);
+        assert!(actions[1]
+            .description
+            .starts_with("Delete 1 detached 'broken-on-start' parts"));
+        assert_eq!(
+            actions[1].sql.as_deref(),
+            Some("ALTER TABLE testdb.events DROP DETACHED PART 'broken-on-start_all_0_0_0' SETTINGS allow_drop_detached = 1")
+        );
+    }
+
+    #[test]
+    fn test_large_detached_data_is_warning() {
+        let ctx = ctx_with(vec![
+            reason("ignored", 5, 2 * GIB as u64),
+            reason("", 2, 1000),
+        ]);
+        let results = DetachedPartsRule::default().evaluate(&ctx);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+        assert!(results[0]
+            .finding
+            .message
+            .contains("(5 ignored, 2 manually detached) are wasting disk"));
+
+        let actions = &results[0].actions;
+        assert_eq!(actions.len(), 2);
+        assert!(actions[0].description.contains("the first 2 of 5"));
+        assert!(actions[0].destructive);
+        assert!(!actions[1].destructive);
+        assert_eq!(
+            actions[1].sql.as_deref(),
+            Some("ALTER TABLE testdb.events ATTACH PART 'all_0_0_0';\nALTER TABLE testdb.events ATTACH PART 'all_1_1_0'")
+        );
+    }
+
+    #[test]
+    fn test_small_detached_data_is_ignored() {
+        let ctx = ctx_with(vec![reason("clone", 1, 1000)]);
+        assert!(DetachedPartsRule::default().evaluate(&ctx).is_empty());
+    }
+}
@@ -0,0 +1,329 @@
+use super::{action_id, finding_id, AuditContext, Rule, RuleResult};
+use crate::ch::sql::{identifier, table_name};
+use crate::config::RuleConfig;
+use crate::report::{Action, ActionType, DictionaryMetrics, Finding, Priority, Severity};
+
+const GIB: f64 = 1_073_741_824.0;
+
+/// A dictionary is stale after this many `lifetime_max` periods without an update
+const STALE_LIFETIMES: f64 = 3.0;
+
+/// Default in-memory size of a very large dictionary
+const LARGE_BYTES: f64 = 10.0 * GIB;
+
+/// Rule to detect dictionaries that failed to load or stopped updating
+///
+/// dictGet keeps returning the last loaded data, so materialized views that
+/// enrich rows with it write stale values without any error.
+#[derive(Default)]
+pub struct DictionaryHealthRule {
+    config: RuleConfig,
+}
+
+/// Rule to detect dictionaries taking a lot of server memory
+#[derive(Default)]
+pub struct LargeDictionaryRule {
+    config: RuleConfig,
+}
+
+impl DictionaryHealthRule {
+    pub const ID: &'static str = "dictionary_health";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl LargeDictionaryRule {
+    pub const ID: &'static str = "dictionary_large";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Rule for DictionaryHealthRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Failed or Stale Dictionary"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.dictionaries"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["stale_lifetimes"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for dict in &ctx.dictionaries {
+            let key = key(dict);
+            if !self.config.enabled_for(&key) {
+                continue;
+            }
+
+            let stale_lifetimes = self
+                .config
+                .threshold(&key, "stale_lifetimes", STALE_LIFETIMES);
+            let failed = dict.status.starts_with("FAILED") || dict.last_exception.is_some();
+            let (severity, mut message) = if failed {
+                (
+                    Severity::Critical,
+                    format!(
+                        "Dictionary is {}: {}",
+                        dict.status,
+                        dict.last_exception
+                            .as_deref()
+                            .and_then(|e| e.lines().next())
+                            .unwrap_or("no exception recorded")
+                    ),
+                )
+            } else {
+                match dict.update_age_sec {
+                    Some(age)
+                        if dict.lifetime_max > 0
+                            && age as f64 > dict.lifetime_max as f64 * stale_lifetimes =>
+                    {
+                        (
+                            Severity::Warning,
+                            format!(
+                                "Dictionary was last updated {}s ago, {:.0}x its lifetime of {}s",
+                                age,
+                                age as f64 / dict.lifetime_max as f64,
+                                dict.lifetime_max
+                            ),
+                        )
+                    }
+                    _ => continue,
+                }
+            };
+            if dict.update_age_sec.is_some() {
+                message.push_str(&format!(
+                    "; dictGet returns data from {}",
+                    dict.last_successful_update_time
+                ));
+            }
+
+            let id = finding_id(self.id(), &key, &severity);
+            results.push(RuleResult {
+                finding: Finding {
+                    id: id.clone(),
+                    rule_id: self.id().to_string(),
+                    severity,
+                    target: key,
+                    message,
+                    evidence_refs: ctx.evidence_refs("system.dictionaries"),
+                    confidence: 1.0,
+                },
+                actions: vec![Action {
+                    id: action_id(&id, 0),
+                    finding_ref: id,
+                    action_type: ActionType::Recommendation,
+                    priority: Priority::High,
+                    description: format!(
+                        "Check that the source ({}) is reachable and returns data, then reload the dictionary",
+                        dict.source
+                    ),
+                    sql: Some(format!("SYSTEM RELOAD DICTIONARY {}", sql_name(dict))),
+                    destructive: false,
+                }],
+            });
+        }
+
+        results
+    }
+}
+
+impl Rule for LargeDictionaryRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Large In-Memory Dictionary"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.dictionaries"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["max_bytes"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for dict in &ctx.dictionaries {
+            let key = key(dict);
+            if !self.config.enabled_for(&key) {
+                continue;
+            }
+
+            let max_bytes = self.config.threshold(&key, "max_bytes", LARGE_BYTES);
+            if (dict.bytes_allocated as f64) < max_bytes {
+                continue;
+            }
+
+            let id = finding_id(self.id(), &key, &Severity::Warning);
+            results.push(RuleResult {
+                finding: Finding {
+                    id: id.clone(),
+                    rule_id: self.id().to_string(),
+                    severity: Severity::Warning,
+                    target: key,
+                    message: message(dict),
+                    evidence_refs: ctx.evidence_refs("system.dictionaries"),
+                    confidence: 1.0,
+                },
+                actions: vec![Action {
+                    id: action_id(&id, 0),
+                    finding_ref: id,
+                    action_type: ActionType::Recommendation,
+                    priority: Priority::Medium,
+                    description: "Switch to a more compact layout (sparse_hashed, hashed_array) or to cache/ssd_cache so only hot keys are kept in memory".to_string(),
+                    sql: None,
+                    destructive: false,
+                }],
+            });
+        }
+
+        results
+    }
+}
+
+/// `database.name`, or just the name for dictionaries defined in the server config
+fn key(dict: &DictionaryMetrics) -> String {
+    if dict.database.is_empty() {
+        dict.name.clone()
+    } else {
+        format!("{}.{}", dict.database, dict.name)
+    }
+}
+
+fn sql_name(dict: &DictionaryMetrics) -> String {
+    if dict.database.is_empty() {
+        identifier(&dict.name)
+    } else {
+        table_name(&dict.database, &dict.name)
+    }
+}
+
+fn message(dict: &DictionaryMetrics) -> String {
+    format!(
+        "{} dictionary holds {} elements in {:.1}GB of memory (loaded in {:.0}s)",
+        dict.dictionary_type,
+        dict.element_count,
+        dict.bytes_allocated as f64 / GIB,
+        dict.loading_duration_sec
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dict(status: &str, update_age_sec: Option<u64>) -> DictionaryMetrics {
+        DictionaryMetrics {
+            database: "testdb".to_string(),
+            name: "countries".to_string(),
+            status: status.to_string(),
+            dictionary_type: "Hashed".to_string(),
+            source: "ClickHouse: testdb.countries_src".to_string(),
+            lifetime_min: 300,
+            lifetime_max: 600,
+            last_successful_update_time: "2024-01-01 00:00:00".to_string(),
+            update_age_sec,
+            loading_duration_sec: 12.0,
+            bytes_allocated: 1024,
+            element_count: 250,
+            last_exception: None,
+        }
+    }
+
+    fn evaluate<R: Rule>(rule: R, dicts: Vec<DictionaryMetrics>) -> Vec<RuleResult> {
+        let mut ctx = AuditContext::new();
+        ctx.set_dictionaries(dicts);
+        rule.evaluate(&ctx)
+    }
+
+    #[test]
+    fn test_failed_dictionary_is_critical() {
+        let mut failed = dict("FAILED_AND_RELOADING", Some(7200));
+        failed.last_exception =
+            Some("Code: 210. DB::NetException: Connection refused\nStack trace:".to_string());
+        let results = evaluate(DictionaryHealthRule::default(), vec![failed]);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+        assert_eq!(
+            results[0].finding.message,
+            "Dictionary is FAILED_AND_RELOADING: Code: 210. DB::NetException: Connection refused; dictGet returns data from 2024-01-01 00:00:00"
+        );
+        assert_eq!(
+            results[0].actions[0].sql.as_deref(),
+            Some("SYSTEM RELOAD DICTIONARY testdb.countries")
+        );
+    }
+
+    #[test]
+    fn test_config_file_dictionary() {
+        let mut failed = dict("FAILED", None);
+        failed.database = String::new();
+        let results = evaluate(DictionaryHealthRule::default(), vec![failed]);
+
+        assert_eq!(results[0].finding.target, "countries");
+        assert_eq!(
+            results[0].actions[0].sql.as_deref(),
+            Some("SYSTEM RELOAD DICTIONARY countries")
+        );
+    }
+
+    #[test]
+    fn test_stale_dictionary_is_warning() {
+        let results = evaluate(
+            DictionaryHealthRule::default(),
+            vec![dict("LOADED", Some(6000))],
+        );
+
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+        assert!(results[0]
+            .finding
+            .message
+            .starts_with("Dictionary was last updated 6000s ago, 10x its lifetime of 600s"));
+    }
+
+    #[test]
+    fn test_healthy_or_static_dictionary() {
+        let mut fixed = dict("LOADED", Some(1_000_000));
+        fixed.lifetime_max = 0;
+        let results = evaluate(
+            DictionaryHealthRule::default(),
+            vec![dict("LOADED", Some(300)), dict("NOT_LOADED", None), fixed],
+        );
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_large_dictionary() {
+        let mut large = dict("LOADED", Some(300));
+        large.bytes_allocated = 20 * GIB as u64;
+        large.element_count = 500_000_000;
+        let results = evaluate(
+            LargeDictionaryRule::default(),
+            vec![large, dict("LOADED", Some(300))],
+        );
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].finding.message,
+            "Hashed dictionary holds 500000000 elements in 20.0GB of memory (loaded in 12s)"
+        );
+    }
+}
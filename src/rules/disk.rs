@@ -1,32 +1,95 @@
-use super::{AuditContext, Rule, RuleResult};
+use super::ttl::largest_without_ttl;
+use super::{action_id, finding_id, AuditContext, Rule, RuleResult};
+use crate::config::RuleConfig;
 use crate::report::{Action, ActionType, Finding, Priority, Severity};
 
-const DISK_WARNING_PCT: f64 = 20.0;
-const DISK_CRITICAL_PCT: f64 = 10.0;
+pub(super) const DISK_WARNING_PCT: f64 = 20.0;
+pub(super) const DISK_CRITICAL_PCT: f64 = 10.0;
+
+/// Severity for a disk with `free_pct` percent free, if any
+pub(super) fn headroom_severity(
+    free_pct: f64,
+    warning_pct: f64,
+    critical_pct: f64,
+) -> Option<Severity> {
+    if free_pct < critical_pct {
+        Some(Severity::Critical)
+    } else if free_pct < warning_pct {
+        Some(Severity::Warning)
+    } else {
+        None
+    }
+}
 
 /// Rule to detect low disk headroom
-pub struct DiskHeadroomRule;
+#[derive(Default)]
+pub struct DiskHeadroomRule {
+    config: RuleConfig,
+}
+
+impl DiskHeadroomRule {
+    pub const ID: &'static str = "disk_headroom";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
 
 impl Rule for DiskHeadroomRule {
     fn id(&self) -> &'static str {
-        "disk_headroom"
+        Self::ID
     }
 
     fn name(&self) -> &'static str {
         "Disk Headroom"
     }
 
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.disks"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["warning_pct", "critical_pct"]
+    }
+
     fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
         let mut results = Vec::new();
 
+        // Point the TTL advice at concrete tables when the TTL collector ran
+        let no_ttl: Vec<String> = largest_without_ttl(ctx, 3)
+            .iter()
+            .map(|t| format!("{}.{}", t.database, t.table))
+            .collect();
+        let no_ttl = if no_ttl.is_empty() {
+            String::new()
+        } else {
+            format!(" (largest tables without TTL: {})", no_ttl.join(", "))
+        };
+        let mut evidence_refs = ctx.evidence_refs("system.disks");
+        if !no_ttl.is_empty() {
+            evidence_refs.extend(ctx.evidence_refs("ttl"));
+        }
+
         for disk in &ctx.disk {
+            if !self.config.enabled_for(&disk.disk_name) {
+                continue;
+            }
+
             let free_pct = disk.free_percent;
             let free_gb = disk.free_space as f64 / 1_073_741_824.0;
+            let warning_pct =
+                self.config
+                    .threshold(&disk.disk_name, "warning_pct", DISK_WARNING_PCT);
+            let critical_pct =
+                self.config
+                    .threshold(&disk.disk_name, "critical_pct", DISK_CRITICAL_PCT);
 
-            if free_pct < DISK_CRITICAL_PCT {
+            let severity = headroom_severity(free_pct, warning_pct, critical_pct);
+            if severity == Some(Severity::Critical) {
+                let id = finding_id(self.id(), &disk.disk_name, &Severity::Critical);
                 results.push(RuleResult {
                     finding: Finding {
-                        id: format!("f-disk-{}", results.len() + 1),
+                        id: id.clone(),
                         rule_id: self.id().to_string(),
                         severity: Severity::Critical,
                         target: disk.disk_name.clone(),
@@ -34,22 +97,27 @@ impl Rule for DiskHeadroomRule {
                             "Disk {} has only {:.1}% free ({:.1}GB)",
                             disk.disk_name, free_pct, free_gb
                         ),
-                        evidence_refs: vec![],
+                        evidence_refs: evidence_refs.clone(),
                         confidence: 1.0,
                     },
                     actions: vec![Action {
-                        id: format!("a-disk-{}", results.len() + 1),
-                        finding_ref: format!("f-disk-{}", results.len() + 1),
+                        id: action_id(&id, 0),
+                        finding_ref: id,
                         action_type: ActionType::Recommendation,
                         priority: Priority::High,
-                        description: "Expand storage or implement TTL policy urgently".to_string(),
+                        description: format!(
+                            "Expand storage or implement TTL policy urgently{}",
+                            no_ttl
+                        ),
                         sql: None,
+                        destructive: false,
                     }],
                 });
-            } else if free_pct < DISK_WARNING_PCT {
+            } else if severity == Some(Severity::Warning) {
+                let id = finding_id(self.id(), &disk.disk_name, &Severity::Warning);
                 results.push(RuleResult {
                     finding: Finding {
-                        id: format!("f-disk-{}", results.len() + 1),
+                        id: id.clone(),
                         rule_id: self.id().to_string(),
                         severity: Severity::Warning,
                         target: disk.disk_name.clone(),
@@ -57,16 +125,20 @@ impl Rule for DiskHeadroomRule {
                             "Disk {} has only {:.1}% free ({:.1}GB)",
                             disk.disk_name, free_pct, free_gb
                         ),
-                        evidence_refs: vec![],
+                        evidence_refs: evidence_refs.clone(),
                         confidence: 1.0,
                     },
                     actions: vec![Action {
-                        id: format!("a-disk-{}", results.len() + 1),
-                        finding_ref: format!("f-disk-{}", results.len() + 1),
+                        id: action_id(&id, 0),
+                        finding_ref: id,
                         action_type: ActionType::Recommendation,
                         priority: Priority::Medium,
-                        description: "Consider expanding storage or implementing TTL".to_string(),
+                        description: format!(
+                            "Consider expanding storage or implementing TTL{}",
+                            no_ttl
+                        ),
                         sql: None,
+                        destructive: false,
                     }],
                 });
             }
@@ -97,7 +169,7 @@ mod tests {
 
     #[test]
     fn test_disk_healthy() {
-        let rule = DiskHeadroomRule;
+        let rule = DiskHeadroomRule::default();
         let ctx = ctx_with_disk(50.0);
         let results = rule.evaluate(&ctx);
         assert!(results.is_empty());
@@ -105,7 +177,7 @@ mod tests {
 
     #[test]
     fn test_disk_warning() {
-        let rule = DiskHeadroomRule;
+        let rule = DiskHeadroomRule::default();
         let ctx = ctx_with_disk(15.0);
         let results = rule.evaluate(&ctx);
         assert_eq!(results.len(), 1);
@@ -114,7 +186,7 @@ mod tests {
 
     #[test]
     fn test_disk_critical() {
-        let rule = DiskHeadroomRule;
+        let rule = DiskHeadroomRule::default();
         let ctx = ctx_with_disk(5.0);
         let results = rule.evaluate(&ctx);
         assert_eq!(results.len(), 1);
@@ -123,9 +195,27 @@ mod tests {
 
     #[test]
     fn test_disk_at_warning_threshold() {
-        let rule = DiskHeadroomRule;
+        let rule = DiskHeadroomRule::default();
         let ctx = ctx_with_disk(20.0);
         let results = rule.evaluate(&ctx);
         assert!(results.is_empty());
     }
+
+    #[test]
+    fn test_disk_names_tables_without_ttl() {
+        let rule = DiskHeadroomRule::default();
+        let mut ctx = ctx_with_disk(5.0);
+        ctx.add_ttl(crate::report::TtlMetrics {
+            database: "testdb".to_string(),
+            table: "events".to_string(),
+            bytes_on_disk: 1000,
+            ..Default::default()
+        });
+
+        let results = rule.evaluate(&ctx);
+        assert_eq!(
+            results[0].actions[0].description,
+            "Expand storage or implement TTL policy urgently (largest tables without TTL: testdb.events)"
+        );
+    }
 }
@@ -0,0 +1,300 @@
+use super::disk::{headroom_severity, DISK_CRITICAL_PCT, DISK_WARNING_PCT};
+use super::{action_id, finding_id, AuditContext, Rule, RuleResult};
+use crate::ch::sql::{literal, table_name};
+use crate::config::RuleConfig;
+use crate::report::{
+    Action, ActionType, DiskMetrics, DistributionQueueMetrics, Finding, Priority, Severity,
+};
+
+const FILES_WARNING: u64 = 100;
+const FILES_CRITICAL: u64 = 10_000;
+
+/// Rule to detect data piling up on the initiator node of Distributed tables
+///
+/// The queue is stored on local disk, so a backlog on a disk that is already
+/// low on headroom (same thresholds as `disk_headroom`) is escalated.
+#[derive(Default)]
+pub struct DistributionQueueRule {
+    config: RuleConfig,
+}
+
+impl DistributionQueueRule {
+    pub const ID: &'static str = "distribution_queue_backlog";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Rule for DistributionQueueRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Distributed Send Queue Backlog"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.distribution_queue"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &[
+            "files_warning",
+            "files_critical",
+            "disk_warning_pct",
+            "disk_critical_pct",
+        ]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for (table_key, queue) in &ctx.distribution_queue {
+            if !self.config.enabled_for(table_key) || queue.pending_files == 0 {
+                continue;
+            }
+
+            let files_warning =
+                self.config
+                    .threshold(table_key, "files_warning", FILES_WARNING as f64)
+                    as u64;
+            let files_critical =
+                self.config
+                    .threshold(table_key, "files_critical", FILES_CRITICAL as f64)
+                    as u64;
+
+            let backlog = if queue.is_blocked || queue.pending_files > files_critical {
+                Some(Severity::Critical)
+            } else if queue.pending_files > files_warning || queue.error_count > 0 {
+                Some(Severity::Warning)
+            } else {
+                None
+            };
+            let Some(backlog) = backlog else {
+                continue;
+            };
+
+            let disk = disk_for_path(&ctx.disk, &queue.data_path);
+            let headroom = disk.and_then(|d| {
+                headroom_severity(
+                    d.free_percent,
+                    self.config
+                        .threshold(table_key, "disk_warning_pct", DISK_WARNING_PCT),
+                    self.config
+                        .threshold(table_key, "disk_critical_pct", DISK_CRITICAL_PCT),
+                )
+            });
+            let severity = if headroom.is_some() {
+                Severity::Critical
+            } else {
+                backlog
+            };
+
+            let id = finding_id(self.id(), table_key, &severity);
+            results.push(RuleResult {
+                finding: Finding {
+                    id: id.clone(),
+                    rule_id: self.id().to_string(),
+                    severity: severity.clone(),
+                    target: table_key.clone(),
+                    message: message(queue, disk.filter(|_| headroom.is_some())),
+                    evidence_refs: evidence_refs(ctx, headroom.is_some()),
+                    confidence: 1.0,
+                },
+                actions: vec![
+                    Action {
+                        id: action_id(&id, 0),
+                        finding_ref: id.clone(),
+                        action_type: ActionType::Recommendation,
+                        priority: if severity == Severity::Critical {
+                            Priority::High
+                        } else {
+                            Priority::Medium
+                        },
+                        description: "Check which shard is failing and why".to_string(),
+                        sql: Some(format!(
+                            "SELECT data_path, is_blocked, data_files, error_count, last_exception FROM system.distribution_queue WHERE database = {} AND table = {}",
+                            literal(&queue.database),
+                            literal(&queue.table)
+                        )),
+                        destructive: false,
+                    },
+                    Action {
+                        id: action_id(&id, 1),
+                        finding_ref: id,
+                        action_type: ActionType::Recommendation,
+                        priority: Priority::Medium,
+                        description: "Once the shard is reachable, flush the queue".to_string(),
+                        sql: Some(format!(
+                            "SYSTEM FLUSH DISTRIBUTED {}",
+                            table_name(&queue.database, &queue.table)
+                        )),
+                        destructive: false,
+                    },
+                ],
+            });
+        }
+
+        results
+    }
+}
+
+/// The disk whose path is the longest prefix of `data_path`
+fn disk_for_path<'a>(disks: &'a [DiskMetrics], data_path: &str) -> Option<&'a DiskMetrics> {
+    disks
+        .iter()
+        .filter(|d| !d.path.is_empty() && data_path.starts_with(&d.path))
+        .max_by_key(|d| d.path.len())
+}
+
+fn message(queue: &DistributionQueueMetrics, low_disk: Option<&DiskMetrics>) -> String {
+    let mut message = format!(
+        "{} files ({:.1}MB) waiting to be sent to shards",
+        queue.pending_files,
+        queue.pending_bytes as f64 / 1_048_576.0
+    );
+    if queue.is_blocked {
+        message.push_str(", queue is blocked");
+    }
+    if queue.error_count > 0 {
+        message.push_str(&format!(", {} send errors", queue.error_count));
+    }
+    if let Some(disk) = low_disk {
+        message.push_str(&format!(
+            ", disk {} has only {:.1}% free",
+            disk.disk_name, disk.free_percent
+        ));
+    }
+    if let Some(exception) = &queue.last_exception {
+        message.push_str(&format!(
+            ": {}",
+            exception.lines().next().unwrap_or_default()
+        ));
+    }
+    message
+}
+
+fn evidence_refs(ctx: &AuditContext, low_disk: bool) -> Vec<String> {
+    let mut refs = ctx.evidence_refs("system.distribution_queue");
+    if low_disk {
+        refs.extend(ctx.evidence_refs("system.disks"));
+    }
+    refs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx_with_queue(queue: DistributionQueueMetrics) -> AuditContext {
+        let mut ctx = AuditContext::new();
+        ctx.add_distribution_queue(DistributionQueueMetrics {
+            database: "testdb".to_string(),
+            table: "events_dist".to_string(),
+            data_path: "/var/lib/clickhouse/store/abc/shard1_replica1/".to_string(),
+            ..queue
+        });
+        ctx
+    }
+
+    fn disk(path: &str, free_percent: f64) -> DiskMetrics {
+        DiskMetrics {
+            disk_name: "default".to_string(),
+            path: path.to_string(),
+            free_percent,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_empty_queue() {
+        let ctx = ctx_with_queue(DistributionQueueMetrics::default());
+        assert!(DistributionQueueRule::default().evaluate(&ctx).is_empty());
+    }
+
+    #[test]
+    fn test_small_queue_without_errors() {
+        let ctx = ctx_with_queue(DistributionQueueMetrics {
+            pending_files: 5,
+            ..Default::default()
+        });
+        assert!(DistributionQueueRule::default().evaluate(&ctx).is_empty());
+    }
+
+    #[test]
+    fn test_backlog_warning() {
+        let ctx = ctx_with_queue(DistributionQueueMetrics {
+            pending_files: 500,
+            pending_bytes: 10_485_760,
+            ..Default::default()
+        });
+        let results = DistributionQueueRule::default().evaluate(&ctx);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+        assert!(results[0].finding.message.starts_with("500 files (10.0MB)"));
+        assert_eq!(
+            results[0].actions[1].sql.as_deref(),
+            Some("SYSTEM FLUSH DISTRIBUTED testdb.events_dist")
+        );
+    }
+
+    #[test]
+    fn test_errors_raise_warning() {
+        let ctx = ctx_with_queue(DistributionQueueMetrics {
+            pending_files: 5,
+            error_count: 3,
+            last_exception: Some("Code: 210. Connection refused".to_string()),
+            ..Default::default()
+        });
+        let results = DistributionQueueRule::default().evaluate(&ctx);
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+        assert!(results[0]
+            .finding
+            .message
+            .ends_with("3 send errors: Code: 210. Connection refused"));
+    }
+
+    #[test]
+    fn test_blocked_queue_is_critical() {
+        let ctx = ctx_with_queue(DistributionQueueMetrics {
+            pending_files: 5,
+            is_blocked: true,
+            ..Default::default()
+        });
+        let results = DistributionQueueRule::default().evaluate(&ctx);
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+    }
+
+    #[test]
+    fn test_low_disk_escalates_backlog() {
+        let mut ctx = ctx_with_queue(DistributionQueueMetrics {
+            pending_files: 500,
+            ..Default::default()
+        });
+        ctx.set_evidence("system.distribution_queue", "ev-001");
+        ctx.set_evidence("system.disks", "ev-002");
+        ctx.set_disk(vec![disk("/var/lib/clickhouse/", 15.0)]);
+
+        let results = DistributionQueueRule::default().evaluate(&ctx);
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+        assert!(results[0]
+            .finding
+            .message
+            .contains("disk default has only 15.0% free"));
+        assert_eq!(results[0].finding.evidence_refs, vec!["ev-001", "ev-002"]);
+    }
+
+    #[test]
+    fn test_disk_for_path_uses_longest_prefix() {
+        let disks = vec![
+            disk("/var/lib/clickhouse/", 50.0),
+            disk("/var/lib/clickhouse/store/", 5.0),
+            disk("/mnt/cold/", 1.0),
+        ];
+        let found = disk_for_path(&disks, "/var/lib/clickhouse/store/abc/shard1/").unwrap();
+        assert_eq!(found.path, "/var/lib/clickhouse/store/");
+        assert!(disk_for_path(&disks, "/srv/data/").is_none());
+    }
+}
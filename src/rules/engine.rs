@@ -1,5 +1,5 @@
 use super::context::AuditContext;
-use crate::report::{Action, Finding};
+use crate::report::{Action, Finding, Severity, SkippedRule};
 
 /// Result of a rule evaluation
 #[derive(Debug)]
@@ -8,6 +8,41 @@ pub struct RuleResult {
     pub actions: Vec<Action>,
 }
 
+/// Build a finding ID from its rule, target and severity.
+///
+/// The ID is a hash of the finding's content, so the same problem gets the
+/// same ID on every run regardless of evaluation order.
+pub fn finding_id(rule_id: &str, target: &str, severity: &Severity) -> String {
+    let severity = match severity {
+        Severity::Warning => "warning",
+        Severity::Critical => "critical",
+    };
+    format!("f-{:016x}", fnv1a(&[rule_id, target, severity]))
+}
+
+/// Build the ID of the `index`-th action attached to a finding
+pub fn action_id(finding_id: &str, index: usize) -> String {
+    let hash = finding_id.strip_prefix("f-").unwrap_or(finding_id);
+    format!("a-{}-{}", hash, index + 1)
+}
+
+/// 64-bit FNV-1a over the given fields; stable across builds and platforms
+fn fnv1a(fields: &[&str]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    let mut hash = OFFSET_BASIS;
+    for (i, field) in fields.iter().enumerate() {
+        // Separator keeps ("ab", "c") and ("a", "bc") apart
+        let separator: &[u8] = if i == 0 { &[] } else { &[0] };
+        for &byte in separator.iter().chain(field.as_bytes()) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    hash
+}
+
 /// Trait for implementing audit rules
 pub trait Rule: Send + Sync {
     /// Unique identifier for the rule
@@ -16,6 +51,18 @@ pub trait Rule: Send + Sync {
     /// Human-readable name
     fn name(&self) -> &'static str;
 
+    /// Metric sources the rule reads (e.g. "system.parts").
+    ///
+    /// The rule is skipped when any of them failed to collect.
+    fn inputs(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Threshold names the rule reads from the config file (e.g. "warning")
+    fn thresholds(&self) -> &'static [&'static str] {
+        &[]
+    }
+
     /// Evaluate the rule against the audit context
     fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult>;
 }
@@ -37,11 +84,35 @@ impl RuleRegistry {
         self.rules.push(rule);
     }
 
-    /// Evaluate all rules against the context
+    /// Evaluate all rules against the context, skipping rules with missing inputs.
+    ///
+    /// Results are grouped by rule in registration order and sorted by target
+    /// within each rule, so output is stable across runs.
     pub fn evaluate_all(&self, ctx: &AuditContext) -> Vec<RuleResult> {
         self.rules
             .iter()
-            .flat_map(|rule| rule.evaluate(ctx))
+            .filter(|rule| missing_inputs(rule.as_ref(), ctx).is_empty())
+            .flat_map(|rule| {
+                let mut results = rule.evaluate(ctx);
+                results.sort_by(|a, b| {
+                    (&a.finding.target, &a.finding.id).cmp(&(&b.finding.target, &b.finding.id))
+                });
+                results
+            })
+            .collect()
+    }
+
+    /// Rules that `evaluate_all` skips because their inputs failed to collect
+    pub fn skipped_rules(&self, ctx: &AuditContext) -> Vec<SkippedRule> {
+        self.rules
+            .iter()
+            .filter_map(|rule| {
+                let missing = missing_inputs(rule.as_ref(), ctx);
+                (!missing.is_empty()).then(|| SkippedRule {
+                    rule_id: rule.id().to_string(),
+                    missing_inputs: missing,
+                })
+            })
             .collect()
     }
 
@@ -59,12 +130,27 @@ impl RuleRegistry {
     pub fn rule_ids(&self) -> Vec<&'static str> {
         self.rules.iter().map(|r| r.id()).collect()
     }
+
+    /// Registered rule IDs with the threshold names each accepts
+    pub fn rule_thresholds(&self) -> Vec<(&'static str, &'static [&'static str])> {
+        self.rules
+            .iter()
+            .map(|r| (r.id(), r.thresholds()))
+            .collect()
+    }
+}
+
+fn missing_inputs(rule: &dyn Rule, ctx: &AuditContext) -> Vec<String> {
+    rule.inputs()
+        .iter()
+        .filter(|source| ctx.is_failed(source))
+        .map(|source| source.to_string())
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::report::Severity;
 
     // Mock rule for testing
     struct MockRule {
@@ -80,6 +166,10 @@ mod tests {
             "Mock Rule"
         }
 
+        fn inputs(&self) -> &'static [&'static str] {
+            &["system.parts"]
+        }
+
         fn evaluate(&self, _ctx: &AuditContext) -> Vec<RuleResult> {
             if self.should_trigger {
                 vec![RuleResult {
@@ -170,6 +260,64 @@ mod tests {
         assert_eq!(results.len(), 2);
     }
 
+    #[test]
+    fn test_registry_skips_rules_with_failed_inputs() {
+        let mut registry = RuleRegistry::new();
+        registry.register(Box::new(MockRule {
+            should_trigger: true,
+        }));
+
+        let mut ctx = AuditContext::new();
+        assert!(registry.skipped_rules(&ctx).is_empty());
+
+        ctx.mark_failed("system.parts");
+        assert!(registry.evaluate_all(&ctx).is_empty());
+
+        let skipped = registry.skipped_rules(&ctx);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].rule_id, "mock_rule");
+        assert_eq!(skipped[0].missing_inputs, vec!["system.parts"]);
+    }
+
+    #[test]
+    fn test_finding_id_is_stable() {
+        let id = finding_id("parts_explosion", "testdb.events", &Severity::Warning);
+        assert_eq!(
+            id,
+            finding_id("parts_explosion", "testdb.events", &Severity::Warning)
+        );
+        assert!(id.starts_with("f-"));
+        assert_eq!(id.len(), 18);
+    }
+
+    #[test]
+    fn test_finding_id_depends_on_content() {
+        let base = finding_id("parts_explosion", "testdb.events", &Severity::Warning);
+        assert_ne!(
+            base,
+            finding_id("parts_explosion", "testdb.events", &Severity::Critical)
+        );
+        assert_ne!(
+            base,
+            finding_id("merge_backlog", "testdb.events", &Severity::Warning)
+        );
+        assert_ne!(
+            base,
+            finding_id("parts_explosion", "testdb.orders", &Severity::Warning)
+        );
+        assert_ne!(
+            finding_id("ab", "c", &Severity::Warning),
+            finding_id("a", "bc", &Severity::Warning)
+        );
+    }
+
+    #[test]
+    fn test_action_id_references_finding() {
+        let id = finding_id("parts_explosion", "testdb.events", &Severity::Warning);
+        let action = action_id(&id, 0);
+        assert_eq!(action, format!("a-{}-1", &id[2..]));
+    }
+
     #[test]
     fn test_registry_rule_ids() {
         let mut registry = RuleRegistry::new();
@@ -0,0 +1,395 @@
+use super::{action_id, finding_id, AuditContext, Rule, RuleResult};
+use crate::config::RuleConfig;
+use crate::report::{Action, ActionType, ExplainMetrics, ExplainRead, Finding, Priority, Severity};
+
+/// Full scans above this many granules are critical (~80M rows at default granularity)
+const FULL_SCAN_CRITICAL_GRANULES: u64 = 10_000;
+
+/// Rule to detect statements that do not use the primary key
+#[derive(Default)]
+pub struct ExplainPrimaryKeyRule {
+    config: RuleConfig,
+}
+
+/// Rule to detect statements that read every granule of a table
+#[derive(Default)]
+pub struct ExplainFullScanRule {
+    config: RuleConfig,
+}
+
+/// Rule to detect statements that do not use available skip indexes
+#[derive(Default)]
+pub struct ExplainSkipIndexRule {
+    config: RuleConfig,
+}
+
+impl ExplainPrimaryKeyRule {
+    pub const ID: &'static str = "explain_primary_key_unused";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl ExplainFullScanRule {
+    pub const ID: &'static str = "explain_full_scan";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl ExplainSkipIndexRule {
+    pub const ID: &'static str = "explain_skip_index_unused";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Rule for ExplainPrimaryKeyRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Primary Key Not Used"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["explain"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for (stmt, read) in reads(ctx) {
+            if !self.config.enabled_for(&read.table) {
+                continue;
+            }
+
+            let pk = read.indexes.iter().find(|i| i.index_type == "PrimaryKey");
+            let used = pk
+                .and_then(|i| i.condition.as_deref())
+                .is_some_and(|c| c != "true");
+
+            if !used {
+                let id = finding_id(self.id(), &target(stmt, read), &Severity::Warning);
+                results.push(RuleResult {
+                    finding: Finding {
+                        id: id.clone(),
+                        rule_id: self.id().to_string(),
+                        severity: Severity::Warning,
+                        target: target(stmt, read),
+                        message: format!(
+                            "Statement {} does not filter {} by its primary key",
+                            stmt.statement_index, read.table
+                        ),
+                        evidence_refs: ctx.evidence_refs("explain"),
+                        confidence: 1.0,
+                    },
+                    actions: vec![Action {
+                        id: action_id(&id, 0),
+                        finding_ref: id,
+                        action_type: ActionType::Recommendation,
+                        priority: Priority::Medium,
+                        description:
+                            "Add a WHERE condition on a prefix of the table's ORDER BY key"
+                                .to_string(),
+                        sql: None,
+                        destructive: false,
+                    }],
+                });
+            }
+        }
+
+        results
+    }
+}
+
+impl Rule for ExplainFullScanRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Full Table Scan"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["explain"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["critical_granules"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for (stmt, read) in reads(ctx) {
+            if !self.config.enabled_for(&read.table) {
+                continue;
+            }
+
+            // The last index stage reports the granules that will actually be read
+            let Some(index) = read.indexes.iter().rev().find(|i| i.granules_total > 0) else {
+                continue;
+            };
+
+            if index.granules_selected < index.granules_total {
+                continue;
+            }
+
+            let critical_granules = self.config.threshold(
+                &read.table,
+                "critical_granules",
+                FULL_SCAN_CRITICAL_GRANULES as f64,
+            ) as u64;
+            let (severity, priority) = if index.granules_total > critical_granules {
+                (Severity::Critical, Priority::High)
+            } else {
+                (Severity::Warning, Priority::Medium)
+            };
+
+            let id = finding_id(self.id(), &target(stmt, read), &severity);
+            results.push(RuleResult {
+                finding: Finding {
+                    id: id.clone(),
+                    rule_id: self.id().to_string(),
+                    severity,
+                    target: target(stmt, read),
+                    message: format!(
+                        "Statement {} reads all {} granules of {}",
+                        stmt.statement_index, index.granules_total, read.table
+                    ),
+                    evidence_refs: ctx.evidence_refs("explain"),
+                    confidence: 1.0,
+                },
+                actions: vec![Action {
+                    id: action_id(&id, 0),
+                    finding_ref: id,
+                    action_type: ActionType::Recommendation,
+                    priority,
+                    description:
+                        "Restrict the statement by primary key or partition key before release"
+                            .to_string(),
+                    sql: None,
+                    destructive: false,
+                }],
+            });
+        }
+
+        results
+    }
+}
+
+impl Rule for ExplainSkipIndexRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Skip Index Not Used"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["explain"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for (stmt, read) in reads(ctx) {
+            if !self.config.enabled_for(&read.table) {
+                continue;
+            }
+
+            let unused: Vec<&str> = read
+                .available_skip_indexes
+                .iter()
+                .filter(|name| {
+                    !read
+                        .indexes
+                        .iter()
+                        .any(|i| i.index_type == "Skip" && i.name.as_ref() == Some(*name))
+                })
+                .map(String::as_str)
+                .collect();
+
+            if unused.is_empty() {
+                continue;
+            }
+
+            let id = finding_id(self.id(), &target(stmt, read), &Severity::Warning);
+            results.push(RuleResult {
+                finding: Finding {
+                    id: id.clone(),
+                    rule_id: self.id().to_string(),
+                    severity: Severity::Warning,
+                    target: target(stmt, read),
+                    message: format!(
+                        "Statement {} does not use skip indexes on {}: {}",
+                        stmt.statement_index,
+                        read.table,
+                        unused.join(", ")
+                    ),
+                    evidence_refs: ctx.evidence_refs("explain"),
+                    // The index may simply not apply to this statement's predicates
+                    confidence: 0.7,
+                },
+                actions: vec![Action {
+                    id: action_id(&id, 0),
+                    finding_ref: id,
+                    action_type: ActionType::Recommendation,
+                    priority: Priority::Low,
+                    description:
+                        "Check whether predicates can be written against the skip index expression"
+                            .to_string(),
+                    sql: None,
+                    destructive: false,
+                }],
+            });
+        }
+
+        results
+    }
+}
+
+/// Iterate over every MergeTree read of every successfully explained statement
+fn reads(ctx: &AuditContext) -> impl Iterator<Item = (&ExplainMetrics, &ExplainRead)> {
+    ctx.explain
+        .iter()
+        .filter(|stmt| stmt.error.is_none())
+        .flat_map(|stmt| stmt.reads.iter().map(move |read| (stmt, read)))
+}
+
+fn target(stmt: &ExplainMetrics, read: &ExplainRead) -> String {
+    format!("{} (statement {})", read.table, stmt.statement_index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::ExplainIndex;
+
+    fn index(index_type: &str, condition: &str, selected: u64, total: u64) -> ExplainIndex {
+        ExplainIndex {
+            index_type: index_type.to_string(),
+            condition: Some(condition.to_string()),
+            granules_selected: selected,
+            granules_total: total,
+            ..Default::default()
+        }
+    }
+
+    fn ctx_with_read(read: ExplainRead) -> AuditContext {
+        let mut ctx = AuditContext::new();
+        ctx.set_explain(vec![ExplainMetrics {
+            statement_index: 1,
+            statement: "SELECT * FROM testdb.events".to_string(),
+            reads: vec![read],
+            ..Default::default()
+        }]);
+        ctx
+    }
+
+    fn read(indexes: Vec<ExplainIndex>) -> ExplainRead {
+        ExplainRead {
+            table: "testdb.events".to_string(),
+            indexes,
+            available_skip_indexes: vec![],
+        }
+    }
+
+    #[test]
+    fn test_primary_key_used() {
+        let ctx = ctx_with_read(read(vec![index(
+            "PrimaryKey",
+            "(user_id in [1, 1])",
+            1,
+            100,
+        )]));
+        assert!(ExplainPrimaryKeyRule::default().evaluate(&ctx).is_empty());
+    }
+
+    #[test]
+    fn test_primary_key_unused() {
+        let ctx = ctx_with_read(read(vec![index("PrimaryKey", "true", 100, 100)]));
+        let results = ExplainPrimaryKeyRule::default().evaluate(&ctx);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+        assert_eq!(results[0].finding.target, "testdb.events (statement 1)");
+    }
+
+    #[test]
+    fn test_primary_key_missing() {
+        let ctx = ctx_with_read(read(vec![]));
+        assert_eq!(ExplainPrimaryKeyRule::default().evaluate(&ctx).len(), 1);
+    }
+
+    #[test]
+    fn test_full_scan_warning() {
+        let ctx = ctx_with_read(read(vec![index("PrimaryKey", "true", 100, 100)]));
+        let results = ExplainFullScanRule::default().evaluate(&ctx);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+        assert!(results[0].finding.message.contains("100 granules"));
+    }
+
+    #[test]
+    fn test_full_scan_critical() {
+        let ctx = ctx_with_read(read(vec![index("PrimaryKey", "true", 50_000, 50_000)]));
+        let results = ExplainFullScanRule::default().evaluate(&ctx);
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+    }
+
+    #[test]
+    fn test_full_scan_uses_last_index_stage() {
+        // Primary key reads everything, but the skip index prunes granules
+        let ctx = ctx_with_read(read(vec![
+            index("PrimaryKey", "true", 100, 100),
+            index("Skip", "(payload = 'x')", 3, 100),
+        ]));
+        assert!(ExplainFullScanRule::default().evaluate(&ctx).is_empty());
+    }
+
+    #[test]
+    fn test_skip_index_unused() {
+        let mut r = read(vec![index("PrimaryKey", "true", 100, 100)]);
+        r.available_skip_indexes = vec!["idx_payload".to_string()];
+        let ctx = ctx_with_read(r);
+
+        let results = ExplainSkipIndexRule::default().evaluate(&ctx);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].finding.message.contains("idx_payload"));
+    }
+
+    #[test]
+    fn test_skip_index_used() {
+        let mut skip = index("Skip", "(payload = 'x')", 3, 100);
+        skip.name = Some("idx_payload".to_string());
+        let mut r = read(vec![skip]);
+        r.available_skip_indexes = vec!["idx_payload".to_string()];
+        let ctx = ctx_with_read(r);
+
+        assert!(ExplainSkipIndexRule::default().evaluate(&ctx).is_empty());
+    }
+
+    #[test]
+    fn test_failed_statement_has_no_findings() {
+        let mut ctx = AuditContext::new();
+        ctx.set_explain(vec![ExplainMetrics {
+            statement_index: 1,
+            statement: "SELEC 1".to_string(),
+            error: Some("Syntax error".to_string()),
+            // Reads left over from a partial parse are ignored too
+            reads: vec![read(vec![])],
+            ..Default::default()
+        }]);
+
+        assert!(ExplainPrimaryKeyRule::default().evaluate(&ctx).is_empty());
+        assert!(ExplainFullScanRule::default().evaluate(&ctx).is_empty());
+        assert!(ExplainSkipIndexRule::default().evaluate(&ctx).is_empty());
+    }
+}
@@ -0,0 +1,312 @@
+use super::{action_id, finding_id, AuditContext, Rule, RuleResult};
+use crate::ch::sql::literal;
+use crate::config::RuleConfig;
+use crate::report::{Action, ActionType, FailedQueryMetrics, Finding, Priority, Severity};
+
+/// Default failure counts (over the query_log window) for recurring failures
+const FAILURES_WARNING: u64 = 5;
+const FAILURES_CRITICAL: u64 = 100;
+
+/// Affected tables named in the target before the rest are summarised
+const MAX_TARGET_TABLES: usize = 3;
+
+/// Remedies for errors with a well-known cause
+const KNOWN_ERRORS: &[(&str, &str)] = &[
+    (
+        "MEMORY_LIMIT_EXCEEDED",
+        "Reduce the query's memory use (filter earlier, set max_bytes_before_external_group_by / max_bytes_before_external_sort) or raise max_memory_usage for this user",
+    ),
+    (
+        "TOO_MANY_PARTS",
+        "Inserts are being rejected because a partition has too many parts: batch inserts and check the parts_explosion and insert_small_batches findings",
+    ),
+    (
+        "TIMEOUT_EXCEEDED",
+        "Check the query plan with EXPLAIN indexes = 1, or raise max_execution_time if the work is expected to take this long",
+    ),
+];
+
+/// Rule to surface queries that fail repeatedly
+///
+/// Pipeline jobs often retry failed queries without alerting anyone, so the
+/// failures only show up in system.query_log.
+///
+/// Config is looked up by each affected table (or database when no table is
+/// named): the rule applies if any of them enables it, with the strictest of
+/// their thresholds.
+#[derive(Default)]
+pub struct FailedQueryRule {
+    config: RuleConfig,
+}
+
+impl FailedQueryRule {
+    pub const ID: &'static str = "query_failures";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Rule for FailedQueryRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Recurring Query Failures"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["failed_queries"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["warning_failures", "critical_failures"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for failed in &ctx.failed_queries {
+            let target = target(failed);
+            let affected: Vec<&str> = match config_keys(failed) {
+                [] => vec![target.as_str()],
+                keys => keys.iter().map(String::as_str).collect(),
+            };
+            let keys: Vec<&str> = affected
+                .into_iter()
+                .filter(|key| self.config.enabled_for(key))
+                .collect();
+            if keys.is_empty() {
+                continue;
+            }
+
+            let threshold = |name: &str, default: u64| {
+                keys.iter()
+                    .map(|key| self.config.threshold(key, name, default as f64) as u64)
+                    .min()
+                    .unwrap_or(default)
+            };
+            let warning = threshold("warning_failures", FAILURES_WARNING);
+            let critical = threshold("critical_failures", FAILURES_CRITICAL);
+
+            // Rejected inserts lose data unless the client retries them all
+            let severity = if failed.failures >= critical
+                || (failed.exception_name == "TOO_MANY_PARTS" && failed.failures >= warning)
+            {
+                Severity::Critical
+            } else if failed.failures >= warning {
+                Severity::Warning
+            } else {
+                continue;
+            };
+
+            let id = finding_id(self.id(), &target, &severity);
+            let remedy = KNOWN_ERRORS
+                .iter()
+                .find(|(name, _)| *name == failed.exception_name)
+                .map_or(
+                    "Investigate the error: the client may be retrying it without reporting",
+                    |(_, remedy)| remedy,
+                );
+
+            results.push(RuleResult {
+                finding: Finding {
+                    id: id.clone(),
+                    rule_id: self.id().to_string(),
+                    severity: severity.clone(),
+                    target,
+                    message: format!(
+                        "{} failures with {} ({}) for user {} between {} and {}: {}",
+                        failed.failures,
+                        failed.exception_name,
+                        failed.exception_code,
+                        failed.user,
+                        failed.first_seen,
+                        failed.last_seen,
+                        first_line(&failed.sample_exception)
+                    ),
+                    evidence_refs: ctx.evidence_refs("failed_queries"),
+                    confidence: 1.0,
+                },
+                actions: vec![Action {
+                    id: action_id(&id, 0),
+                    finding_ref: id,
+                    action_type: ActionType::Recommendation,
+                    priority: if severity == Severity::Critical {
+                        Priority::High
+                    } else {
+                        Priority::Medium
+                    },
+                    description: remedy.to_string(),
+                    sql: Some(format!(
+                        "SELECT event_time, query_id, exception FROM system.query_log WHERE type IN ('ExceptionBeforeStart', 'ExceptionWhileProcessing') AND normalized_query_hash = {} AND exception_code = {} AND user = {} ORDER BY event_time DESC LIMIT 10",
+                        failed.normalized_query_hash,
+                        failed.exception_code,
+                        literal(&failed.user)
+                    )),
+                    destructive: false,
+                }],
+            });
+        }
+
+        results
+    }
+}
+
+/// Affected table keys, or databases when the query named no table
+fn config_keys(failed: &FailedQueryMetrics) -> &[String] {
+    if failed.tables.is_empty() {
+        &failed.databases
+    } else {
+        &failed.tables
+    }
+}
+
+/// Affected tables (or databases), error name, user and query hash
+///
+/// The same tables, error and user can come from several queries, so the
+/// normalized_query_hash keeps one target per failing query.
+fn target(failed: &FailedQueryMetrics) -> String {
+    let objects = config_keys(failed);
+    let mut scope = objects
+        .iter()
+        .take(MAX_TARGET_TABLES)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if objects.len() > MAX_TARGET_TABLES {
+        scope.push_str(&format!(" +{} more", objects.len() - MAX_TARGET_TABLES));
+    }
+    format!(
+        "{} {} ({}, query {})",
+        scope, failed.exception_name, failed.user, failed.normalized_query_hash
+    )
+}
+
+/// First line of an exception message, without the stack trace
+fn first_line(exception: &str) -> &str {
+    exception.lines().next().unwrap_or_default().trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failed(exception_name: &str, failures: u64) -> FailedQueryMetrics {
+        FailedQueryMetrics {
+            query_fingerprint: "INSERT INTO testdb.events FORMAT Native".to_string(),
+            normalized_query_hash: 42,
+            exception_code: 241,
+            exception_name: exception_name.to_string(),
+            user: "etl".to_string(),
+            failures,
+            first_seen: "2024-01-01 00:00:00".to_string(),
+            last_seen: "2024-01-02 00:00:00".to_string(),
+            sample_exception: "Code: 241. DB::Exception: Memory limit exceeded\nStack trace:"
+                .to_string(),
+            databases: vec!["testdb".to_string()],
+            tables: vec!["testdb.events".to_string()],
+        }
+    }
+
+    fn evaluate(metrics: Vec<FailedQueryMetrics>) -> Vec<RuleResult> {
+        let mut ctx = AuditContext::new();
+        ctx.set_failed_queries(metrics);
+        FailedQueryRule::default().evaluate(&ctx)
+    }
+
+    #[test]
+    fn test_recurring_failure_is_warning() {
+        let results = evaluate(vec![failed("MEMORY_LIMIT_EXCEEDED", 10)]);
+
+        assert_eq!(results.len(), 1);
+        let finding = &results[0].finding;
+        assert_eq!(finding.severity, Severity::Warning);
+        assert_eq!(
+            finding.target,
+            "testdb.events MEMORY_LIMIT_EXCEEDED (etl, query 42)"
+        );
+        assert!(finding
+            .message
+            .ends_with("for user etl between 2024-01-01 00:00:00 and 2024-01-02 00:00:00: Code: 241. DB::Exception: Memory limit exceeded"));
+        assert!(results[0].actions[0]
+            .description
+            .contains("max_bytes_before_external_group_by"));
+        assert!(results[0].actions[0]
+            .sql
+            .as_ref()
+            .unwrap()
+            .contains("normalized_query_hash = 42 AND exception_code = 241 AND user = 'etl'"));
+    }
+
+    #[test]
+    fn test_frequent_or_rejected_inserts_are_critical() {
+        let results = evaluate(vec![
+            failed("TIMEOUT_EXCEEDED", 150),
+            failed("TOO_MANY_PARTS", 5),
+        ]);
+        assert!(results
+            .iter()
+            .all(|r| r.finding.severity == Severity::Critical));
+    }
+
+    #[test]
+    fn test_one_target_per_query() {
+        let mut other = failed("MEMORY_LIMIT_EXCEEDED", 10);
+        other.query_fingerprint = "SELECT count() FROM testdb.events".to_string();
+        other.normalized_query_hash = 7;
+        let results = evaluate(vec![failed("MEMORY_LIMIT_EXCEEDED", 10), other]);
+
+        assert_eq!(results.len(), 2);
+        assert_ne!(results[0].finding.target, results[1].finding.target);
+        assert_ne!(results[0].finding.id, results[1].finding.id);
+    }
+
+    #[test]
+    fn test_config_by_table_key() {
+        let config = crate::config::Config::parse(
+            r#"
+[[overrides]]
+pattern = "testdb.events*"
+[overrides.rules.query_failures]
+warning_failures = 50
+
+[[overrides]]
+pattern = "testdb.tmp_*"
+[overrides.rules.query_failures]
+enabled = false
+"#,
+        )
+        .unwrap();
+        let rule = FailedQueryRule::new(config.rule(FailedQueryRule::ID));
+        let mut ctx = AuditContext::new();
+        let mut tmp = failed("MEMORY_LIMIT_EXCEEDED", 10);
+        tmp.tables = vec!["testdb.tmp_load".to_string()];
+        let mut other = failed("MEMORY_LIMIT_EXCEEDED", 10);
+        other.tables = vec!["testdb.orders".to_string()];
+        ctx.set_failed_queries(vec![failed("MEMORY_LIMIT_EXCEEDED", 10), tmp, other]);
+
+        let results = rule.evaluate(&ctx);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].finding.target.starts_with("testdb.orders "));
+    }
+
+    #[test]
+    fn test_rare_failures_are_ignored() {
+        assert!(evaluate(vec![failed("TOO_MANY_PARTS", 2)]).is_empty());
+    }
+
+    #[test]
+    fn test_target_summarises_many_tables() {
+        let mut m = failed("UNKNOWN_TABLE", 10);
+        m.tables = (1..=5).map(|i| format!("testdb.t{}", i)).collect();
+        assert_eq!(
+            target(&m),
+            "testdb.t1, testdb.t2, testdb.t3 +2 more UNKNOWN_TABLE (etl, query 42)"
+        );
+
+        m.tables.clear();
+        assert_eq!(target(&m), "testdb UNKNOWN_TABLE (etl, query 42)");
+    }
+}
@@ -0,0 +1,247 @@
+use super::query::format_window;
+use super::{action_id, finding_id, AuditContext, Rule, RuleResult};
+use crate::config::RuleConfig;
+use crate::report::{Action, ActionType, Finding, InsertMetrics, Priority, Severity};
+
+/// Default thresholds for inserts that are too small and too frequent
+const INSERTS_PER_SEC_WARNING: f64 = 1.0;
+const INSERTS_PER_SEC_CRITICAL: f64 = 10.0;
+const MIN_ROWS_PER_INSERT: f64 = 1000.0;
+
+/// Rule to detect tables receiving many tiny inserts
+///
+/// Every insert creates at least one part per partition it touches, so
+/// frequent small inserts are what usually drives a parts explosion.
+#[derive(Default)]
+pub struct InsertBatchingRule {
+    config: RuleConfig,
+}
+
+impl InsertBatchingRule {
+    pub const ID: &'static str = "insert_small_batches";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+
+    /// Severity of a table's inserts by the configured thresholds, if they are
+    /// small and frequent enough to report
+    fn assess(&self, table_key: &str, inserts: &InsertMetrics) -> Option<Severity> {
+        if !self.config.enabled_for(table_key) {
+            return None;
+        }
+
+        let warning = self.config.threshold(
+            table_key,
+            "warning_inserts_per_sec",
+            INSERTS_PER_SEC_WARNING,
+        );
+        let critical = self.config.threshold(
+            table_key,
+            "critical_inserts_per_sec",
+            INSERTS_PER_SEC_CRITICAL,
+        );
+        let min_rows = self.min_rows(table_key);
+
+        // Batch size is only known from query_log
+        if inserts.rows == 0 || inserts.median_rows_per_insert as f64 >= min_rows {
+            return None;
+        }
+        if inserts.inserts_per_sec >= critical {
+            Some(Severity::Critical)
+        } else if inserts.inserts_per_sec >= warning {
+            Some(Severity::Warning)
+        } else {
+            None
+        }
+    }
+
+    fn min_rows(&self, table_key: &str) -> f64 {
+        self.config
+            .threshold(table_key, "min_rows_per_insert", MIN_ROWS_PER_INSERT)
+    }
+
+    /// Insert pattern behind a parts explosion, if this rule reports the
+    /// table's inserts as small and frequent
+    pub(super) fn small_inserts(&self, ctx: &AuditContext, table_key: &str) -> Option<String> {
+        let inserts = ctx.inserts.get(table_key)?;
+        self.assess(table_key, inserts).map(|_| describe(inserts))
+    }
+}
+
+impl Rule for InsertBatchingRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Small Frequent Inserts"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["inserts"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &[
+            "warning_inserts_per_sec",
+            "critical_inserts_per_sec",
+            "min_rows_per_insert",
+        ]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for (table_key, inserts) in &ctx.inserts {
+            let Some(severity) = self.assess(table_key, inserts) else {
+                continue;
+            };
+
+            let id = finding_id(self.id(), table_key, &severity);
+            let priority = if severity == Severity::Critical {
+                Priority::High
+            } else {
+                Priority::Medium
+            };
+            let mut actions = vec![Action {
+                id: action_id(&id, 0),
+                finding_ref: id.clone(),
+                action_type: ActionType::Recommendation,
+                priority,
+                description: if inserts.async_inserts * 2 < inserts.inserts {
+                    format!(
+                        "Batch at least {} rows per insert and at most one insert per second, or enable async_insert (with wait_for_async_insert = 1) so the server buffers small inserts",
+                        self.min_rows(table_key)
+                    )
+                } else {
+                    "Inserts already use async_insert: raise async_insert_busy_timeout_ms and async_insert_max_data_size so buffers are flushed as larger parts".to_string()
+                },
+                sql: None,
+                destructive: false,
+            }];
+            if inserts.multi_partition_inserts > 0 && inserts.parts_per_insert >= 2.0 {
+                actions.push(Action {
+                    id: action_id(&id, 1),
+                    finding_ref: id.clone(),
+                    action_type: ActionType::Recommendation,
+                    priority: Priority::Medium,
+                    description: format!(
+                        "Group rows by partition before inserting: {} inserts wrote to several partitions (up to {})",
+                        inserts.multi_partition_inserts, inserts.max_partitions_per_insert
+                    ),
+                    sql: None,
+                    destructive: false,
+                });
+            }
+
+            results.push(RuleResult {
+                finding: Finding {
+                    id,
+                    rule_id: self.id().to_string(),
+                    severity,
+                    target: table_key.clone(),
+                    message: format!(
+                        "{} over the last {}",
+                        describe(inserts),
+                        format_window(inserts.window_sec)
+                    ),
+                    evidence_refs: ctx.evidence_refs("inserts"),
+                    confidence: 1.0,
+                },
+                actions,
+            });
+        }
+
+        results
+    }
+}
+
+/// "12.5 inserts/s with a median of 40 rows, 2.0 parts per insert"
+fn describe(inserts: &InsertMetrics) -> String {
+    format!(
+        "{:.1} inserts/s with a median of {} rows, {:.1} parts per insert",
+        inserts.inserts_per_sec, inserts.median_rows_per_insert, inserts.parts_per_insert
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx_with(inserts_per_sec: f64, median_rows_per_insert: u64) -> AuditContext {
+        let mut ctx = AuditContext::new();
+        ctx.add_inserts(InsertMetrics {
+            database: "testdb".to_string(),
+            table: "events".to_string(),
+            window_sec: 86400,
+            inserts: (inserts_per_sec * 86400.0) as u64,
+            inserts_per_sec,
+            rows: 1_000_000,
+            median_rows_per_insert,
+            parts_per_insert: 1.0,
+            ..Default::default()
+        });
+        ctx
+    }
+
+    #[test]
+    fn test_small_frequent_inserts() {
+        let results = InsertBatchingRule::default().evaluate(&ctx_with(2.0, 10));
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+        assert_eq!(
+            results[0].finding.message,
+            "2.0 inserts/s with a median of 10 rows, 1.0 parts per insert over the last 1d"
+        );
+        assert!(results[0].actions[0].description.contains("async_insert"));
+        assert_eq!(results[0].actions.len(), 1);
+    }
+
+    #[test]
+    fn test_very_frequent_inserts_are_critical() {
+        let results = InsertBatchingRule::default().evaluate(&ctx_with(50.0, 10));
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+        assert_eq!(results[0].actions[0].priority, Priority::High);
+    }
+
+    #[test]
+    fn test_large_or_rare_inserts_are_fine() {
+        let rule = InsertBatchingRule::default();
+        assert!(rule.evaluate(&ctx_with(50.0, 100_000)).is_empty());
+        assert!(rule.evaluate(&ctx_with(0.1, 10)).is_empty());
+    }
+
+    #[test]
+    fn test_already_async_and_multi_partition() {
+        let mut ctx = ctx_with(2.0, 10);
+        let inserts = ctx.inserts.get_mut("testdb.events").unwrap();
+        inserts.async_inserts = inserts.inserts;
+        inserts.parts_per_insert = 3.0;
+        inserts.multi_partition_inserts = 500;
+        inserts.max_partitions_per_insert = 4;
+
+        let results = InsertBatchingRule::default().evaluate(&ctx);
+        let actions = &results[0].actions;
+        assert!(actions[0]
+            .description
+            .starts_with("Inserts already use async_insert"));
+        assert_eq!(actions.len(), 2);
+        assert!(actions[1].description.contains("(up to 4)"));
+    }
+
+    #[test]
+    fn test_small_inserts_for_parts_explosion() {
+        let rule = InsertBatchingRule::default();
+        assert!(rule
+            .small_inserts(&ctx_with(2.0, 10), "testdb.events")
+            .is_some());
+        assert!(rule
+            .small_inserts(&ctx_with(2.0, 10), "testdb.other")
+            .is_none());
+        assert!(rule
+            .small_inserts(&ctx_with(0.1, 10), "testdb.events")
+            .is_none());
+    }
+}
@@ -0,0 +1,350 @@
+use super::{action_id, finding_id, AuditContext, Rule, RuleResult};
+use crate::ch::sql::literal;
+use crate::config::RuleConfig;
+use crate::report::{
+    Action, ActionType, Finding, KafkaConsumerMetrics, KafkaTableMetrics, Priority, Severity,
+};
+
+const COMMIT_WARNING_SEC: u64 = 900; // 15 minutes
+const COMMIT_CRITICAL_SEC: u64 = 3600; // 1 hour
+
+/// Rule to detect Kafka tables that no materialized view reads from
+#[derive(Default)]
+pub struct KafkaOrphanRule {
+    config: RuleConfig,
+}
+
+/// Rule to detect Kafka consumers that have not committed offsets recently
+#[derive(Default)]
+pub struct KafkaCommitLagRule {
+    config: RuleConfig,
+}
+
+impl KafkaOrphanRule {
+    pub const ID: &'static str = "kafka_orphan_table";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl KafkaCommitLagRule {
+    pub const ID: &'static str = "kafka_commit_stale";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Rule for KafkaOrphanRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Kafka Table Without MV"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.kafka_consumers", "system.tables"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+        // Without the DAG (e.g. --skip mv_dag) every Kafka table would look orphaned
+        let Some(dag) = &ctx.mv_dag else {
+            return results;
+        };
+
+        for (table_key, kafka) in &ctx.kafka {
+            if !self.config.enabled_for(table_key) {
+                continue;
+            }
+
+            let attached = dag
+                .edges
+                .iter()
+                .any(|e| &e.from == table_key || &e.to == table_key);
+            if attached {
+                continue;
+            }
+
+            let messages_read: u64 = kafka.consumers.iter().map(|c| c.num_messages_read).sum();
+            let (severity, message) = if messages_read > 0 {
+                (
+                    Severity::Critical,
+                    format!(
+                        "No materialized view reads from this Kafka table; {} messages were consumed and dropped",
+                        messages_read
+                    ),
+                )
+            } else {
+                (
+                    Severity::Warning,
+                    "No materialized view reads from this Kafka table".to_string(),
+                )
+            };
+
+            let id = finding_id(self.id(), table_key, &severity);
+            results.push(RuleResult {
+                finding: Finding {
+                    id: id.clone(),
+                    rule_id: self.id().to_string(),
+                    severity,
+                    target: table_key.clone(),
+                    message,
+                    evidence_refs: [
+                        ctx.evidence_refs("system.kafka_consumers"),
+                        ctx.evidence_refs("system.tables"),
+                    ]
+                    .concat(),
+                    confidence: 1.0,
+                },
+                actions: vec![Action {
+                    id: action_id(&id, 0),
+                    finding_ref: id,
+                    action_type: ActionType::Recommendation,
+                    priority: Priority::High,
+                    description: "Attach a materialized view that writes the messages to a MergeTree table, or drop the Kafka table".to_string(),
+                    sql: None,
+                    destructive: false,
+                }],
+            });
+        }
+
+        results
+    }
+}
+
+impl Rule for KafkaCommitLagRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Stale Kafka Commits"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.kafka_consumers"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["commit_warning_sec", "commit_critical_sec"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for (table_key, kafka) in &ctx.kafka {
+            if !self.config.enabled_for(table_key) {
+                continue;
+            }
+
+            let warning =
+                self.config
+                    .threshold(table_key, "commit_warning_sec", COMMIT_WARNING_SEC as f64)
+                    as u64;
+            let critical =
+                self.config
+                    .threshold(table_key, "commit_critical_sec", COMMIT_CRITICAL_SEC as f64)
+                    as u64;
+
+            let Some((consumer, age)) = stalest_consumer(kafka) else {
+                continue;
+            };
+            let severity = match age {
+                Some(age) if age > critical => Severity::Critical,
+                Some(age) if age > warning => Severity::Warning,
+                Some(_) => continue,
+                None => Severity::Warning,
+            };
+
+            let mut message = match age {
+                Some(age) => format!(
+                    "Consumer {} has not committed for {}s (threshold: {}s)",
+                    consumer.consumer_id,
+                    age,
+                    if severity == Severity::Critical {
+                        critical
+                    } else {
+                        warning
+                    }
+                ),
+                None => format!(
+                    "Consumer {} read {} messages but never committed",
+                    consumer.consumer_id, consumer.num_messages_read
+                ),
+            };
+            if let Some(exception) = &consumer.last_exception {
+                message.push_str(&format!(
+                    ": {}",
+                    exception.lines().next().unwrap_or_default()
+                ));
+            }
+
+            let id = finding_id(self.id(), table_key, &severity);
+            results.push(RuleResult {
+                finding: Finding {
+                    id: id.clone(),
+                    rule_id: self.id().to_string(),
+                    severity,
+                    target: table_key.clone(),
+                    message,
+                    evidence_refs: ctx.evidence_refs("system.kafka_consumers"),
+                    confidence: 1.0,
+                },
+                actions: vec![Action {
+                    id: action_id(&id, 0),
+                    finding_ref: id,
+                    action_type: ActionType::Recommendation,
+                    priority: Priority::High,
+                    description:
+                        "Check consumer exceptions and whether the downstream MV insert is failing"
+                            .to_string(),
+                    sql: Some(format!(
+                        "SELECT consumer_id, assignments.topic, last_poll_time, last_commit_time, exceptions.text FROM system.kafka_consumers WHERE database = {} AND table = {}",
+                        literal(&kafka.database),
+                        literal(&kafka.table)
+                    )),
+                    destructive: false,
+                }],
+            });
+        }
+
+        results
+    }
+}
+
+/// The assigned consumer that committed least recently, with its commit age.
+///
+/// A consumer that read messages but never committed (`None` age) is the stalest.
+fn stalest_consumer(kafka: &KafkaTableMetrics) -> Option<(&KafkaConsumerMetrics, Option<u64>)> {
+    kafka
+        .consumers
+        .iter()
+        .filter(|c| !c.assignments.is_empty())
+        .filter(|c| c.commit_age_sec.is_some() || c.num_messages_read > 0)
+        .map(|c| (c, c.commit_age_sec))
+        .max_by_key(|(_, age)| age.unwrap_or(u64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::{KafkaAssignment, MvDagEdge, MvDagSection};
+
+    fn consumer(commit_age_sec: Option<u64>, messages: u64) -> KafkaConsumerMetrics {
+        KafkaConsumerMetrics {
+            consumer_id: "consumer-1".to_string(),
+            assignments: vec![KafkaAssignment {
+                topic: "events".to_string(),
+                partition_id: 0,
+                current_offset: 100,
+            }],
+            commit_age_sec,
+            num_messages_read: messages,
+            is_currently_used: true,
+            ..Default::default()
+        }
+    }
+
+    fn ctx_with_kafka(consumers: Vec<KafkaConsumerMetrics>) -> AuditContext {
+        let mut ctx = AuditContext::new();
+        ctx.add_kafka(KafkaTableMetrics {
+            database: "testdb".to_string(),
+            table: "events_queue".to_string(),
+            consumers,
+        });
+        ctx
+    }
+
+    fn dag(edges: &[(&str, &str)]) -> MvDagSection {
+        MvDagSection {
+            edges: edges
+                .iter()
+                .map(|(from, to)| MvDagEdge {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_orphan_requires_dag() {
+        let ctx = ctx_with_kafka(vec![consumer(Some(10), 100)]);
+        assert!(KafkaOrphanRule::default().evaluate(&ctx).is_empty());
+    }
+
+    #[test]
+    fn test_attached_kafka_table() {
+        let mut ctx = ctx_with_kafka(vec![consumer(Some(10), 100)]);
+        ctx.set_mv_dag(dag(&[("testdb.events_queue", "testdb.events_mv")]));
+        assert!(KafkaOrphanRule::default().evaluate(&ctx).is_empty());
+    }
+
+    #[test]
+    fn test_orphan_dropping_messages_is_critical() {
+        let mut ctx = ctx_with_kafka(vec![consumer(Some(10), 100)]);
+        ctx.set_mv_dag(dag(&[("testdb.events", "testdb.events_mv")]));
+
+        let results = KafkaOrphanRule::default().evaluate(&ctx);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+        assert!(results[0]
+            .finding
+            .message
+            .contains("100 messages were consumed and dropped"));
+    }
+
+    #[test]
+    fn test_orphan_without_reads_is_warning() {
+        let mut ctx = ctx_with_kafka(vec![consumer(None, 0)]);
+        ctx.set_mv_dag(dag(&[]));
+
+        let results = KafkaOrphanRule::default().evaluate(&ctx);
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_recent_commit() {
+        let ctx = ctx_with_kafka(vec![consumer(Some(60), 100)]);
+        assert!(KafkaCommitLagRule::default().evaluate(&ctx).is_empty());
+    }
+
+    #[test]
+    fn test_stale_commit_warning_and_critical() {
+        let ctx = ctx_with_kafka(vec![consumer(Some(60), 100), consumer(Some(1200), 100)]);
+        let results = KafkaCommitLagRule::default().evaluate(&ctx);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+        assert!(results[0].finding.message.contains("1200s"));
+
+        let ctx = ctx_with_kafka(vec![consumer(Some(7200), 100)]);
+        let results = KafkaCommitLagRule::default().evaluate(&ctx);
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+    }
+
+    #[test]
+    fn test_never_committed() {
+        let ctx = ctx_with_kafka(vec![consumer(None, 500)]);
+        let results = KafkaCommitLagRule::default().evaluate(&ctx);
+        assert!(results[0].finding.message.contains("never committed"));
+
+        // Idle consumer without reads is not stale
+        let ctx = ctx_with_kafka(vec![consumer(None, 0)]);
+        assert!(KafkaCommitLagRule::default().evaluate(&ctx).is_empty());
+    }
+
+    #[test]
+    fn test_commit_window_is_configurable() {
+        let config =
+            crate::config::Config::parse("[rules.kafka_commit_stale]\ncommit_warning_sec = 30")
+                .unwrap();
+        let rule = KafkaCommitLagRule::new(config.rule(KafkaCommitLagRule::ID));
+        let ctx = ctx_with_kafka(vec![consumer(Some(60), 100)]);
+        assert_eq!(rule.evaluate(&ctx).len(), 1);
+    }
+}
@@ -1,30 +1,62 @@
-use super::{AuditContext, Rule, RuleResult};
+use super::server::saturated_merge_pool;
+use super::{action_id, finding_id, AuditContext, Rule, RuleResult};
+use crate::config::RuleConfig;
 use crate::report::{Action, ActionType, Finding, Priority, Severity};
 
 const MERGE_QUEUE_WARNING: u64 = 10;
 const MERGE_ELAPSED_WARNING_SEC: f64 = 3600.0; // 1 hour
 
 /// Rule to detect merge backlog
-pub struct MergeBacklogRule;
+#[derive(Default)]
+pub struct MergeBacklogRule {
+    config: RuleConfig,
+}
+
+impl MergeBacklogRule {
+    pub const ID: &'static str = "merge_backlog";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
 
 impl Rule for MergeBacklogRule {
     fn id(&self) -> &'static str {
-        "merge_backlog"
+        Self::ID
     }
 
     fn name(&self) -> &'static str {
         "Merge Backlog"
     }
 
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.merges"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["queue_warning", "elapsed_warning_sec"]
+    }
+
     fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
         let mut results = Vec::new();
 
         for (table_key, metrics) in &ctx.merges {
-            let queue_high = metrics.merges_in_queue > MERGE_QUEUE_WARNING;
-            let elapsed_high = metrics.max_merge_elapsed_sec > MERGE_ELAPSED_WARNING_SEC;
+            if !self.config.enabled_for(table_key) {
+                continue;
+            }
+
+            let queue_warning =
+                self.config
+                    .threshold(table_key, "queue_warning", MERGE_QUEUE_WARNING as f64)
+                    as u64;
+            let elapsed_warning_sec =
+                self.config
+                    .threshold(table_key, "elapsed_warning_sec", MERGE_ELAPSED_WARNING_SEC);
+            let queue_high = metrics.merges_in_queue > queue_warning;
+            let elapsed_high = metrics.max_merge_elapsed_sec > elapsed_warning_sec;
 
             if queue_high || elapsed_high {
-                let message = if queue_high && elapsed_high {
+                let mut message = if queue_high && elapsed_high {
                     format!(
                         "Merge queue has {} items and longest merge running for {:.0}s",
                         metrics.merges_in_queue, metrics.max_merge_elapsed_sec
@@ -32,32 +64,50 @@ impl Rule for MergeBacklogRule {
                 } else if queue_high {
                     format!(
                         "Merge queue has {} items (threshold: {})",
-                        metrics.merges_in_queue, MERGE_QUEUE_WARNING
+                        metrics.merges_in_queue, queue_warning
                     )
                 } else {
                     format!(
                         "Longest merge running for {:.0}s (threshold: {:.0}s)",
-                        metrics.max_merge_elapsed_sec, MERGE_ELAPSED_WARNING_SEC
+                        metrics.max_merge_elapsed_sec, elapsed_warning_sec
                     )
                 };
 
+                // A full pool means merges wait for a slot, not for ingestion to slow down
+                let pool = saturated_merge_pool(ctx);
+                let mut evidence_refs = ctx.evidence_refs("system.merges");
+                let description = match pool {
+                    Some(pool) => {
+                        message.push_str(&format!(
+                            "; the background merge/mutation pool is saturated ({}/{} tasks)",
+                            pool.tasks, pool.size
+                        ));
+                        evidence_refs.extend(ctx.evidence_refs("server"));
+                        "Raise background_pool_size: merges are queued behind a full pool"
+                            .to_string()
+                    }
+                    None => "Review write rate, consider throttling ingestion".to_string(),
+                };
+
+                let id = finding_id(self.id(), table_key, &Severity::Warning);
                 results.push(RuleResult {
                     finding: Finding {
-                        id: format!("f-merge-{}", results.len() + 1),
+                        id: id.clone(),
                         rule_id: self.id().to_string(),
                         severity: Severity::Warning,
                         target: table_key.clone(),
                         message,
-                        evidence_refs: vec![],
+                        evidence_refs,
                         confidence: 1.0,
                     },
                     actions: vec![Action {
-                        id: format!("a-merge-{}", results.len() + 1),
-                        finding_ref: format!("f-merge-{}", results.len() + 1),
+                        id: action_id(&id, 0),
+                        finding_ref: id,
                         action_type: ActionType::Recommendation,
                         priority: Priority::Medium,
-                        description: "Review write rate, consider throttling ingestion".to_string(),
+                        description,
                         sql: None,
+                        destructive: false,
                     }],
                 });
             }
@@ -86,7 +136,7 @@ mod tests {
 
     #[test]
     fn test_merge_backlog_healthy() {
-        let rule = MergeBacklogRule;
+        let rule = MergeBacklogRule::default();
         let ctx = ctx_with_merges(5, 100.0);
         let results = rule.evaluate(&ctx);
         assert!(results.is_empty());
@@ -94,7 +144,7 @@ mod tests {
 
     #[test]
     fn test_merge_backlog_queue_warning() {
-        let rule = MergeBacklogRule;
+        let rule = MergeBacklogRule::default();
         let ctx = ctx_with_merges(15, 100.0);
         let results = rule.evaluate(&ctx);
         assert_eq!(results.len(), 1);
@@ -103,7 +153,7 @@ mod tests {
 
     #[test]
     fn test_merge_backlog_elapsed_warning() {
-        let rule = MergeBacklogRule;
+        let rule = MergeBacklogRule::default();
         let ctx = ctx_with_merges(5, 7200.0);
         let results = rule.evaluate(&ctx);
         assert_eq!(results.len(), 1);
@@ -112,11 +162,41 @@ mod tests {
 
     #[test]
     fn test_merge_backlog_both() {
-        let rule = MergeBacklogRule;
+        let rule = MergeBacklogRule::default();
         let ctx = ctx_with_merges(15, 7200.0);
         let results = rule.evaluate(&ctx);
         assert_eq!(results.len(), 1);
         assert!(results[0].finding.message.contains("15 items"));
         assert!(results[0].finding.message.contains("7200s"));
     }
+
+    #[test]
+    fn test_merge_backlog_saturated_pool() {
+        use crate::report::{PoolMetrics, ServerSection};
+
+        let rule = MergeBacklogRule::default();
+        let mut ctx = ctx_with_merges(15, 100.0);
+        ctx.set_evidence("server", "ev-server");
+        ctx.set_server(ServerSection {
+            pools: vec![PoolMetrics {
+                name: "merges_mutations".to_string(),
+                tasks: 32,
+                size: 32,
+            }],
+            ..Default::default()
+        });
+
+        let results = rule.evaluate(&ctx);
+        assert!(results[0]
+            .finding
+            .message
+            .ends_with("; the background merge/mutation pool is saturated (32/32 tasks)"));
+        assert!(results[0]
+            .finding
+            .evidence_refs
+            .contains(&"ev-server".to_string()));
+        assert!(results[0].actions[0]
+            .description
+            .starts_with("Raise background_pool_size"));
+    }
 }
@@ -1,28 +1,235 @@
+use crate::config::Config;
+use anyhow::Result;
+
+mod columns;
 mod context;
+mod detached;
+mod dictionaries;
 mod disk;
+mod distribution;
 mod engine;
+mod explain;
+mod failed_queries;
+mod inserts;
+mod kafka;
 mod merges;
 mod mutations;
 mod parts;
 mod query;
+mod replication;
+mod server;
+mod ttl;
 
+pub use columns::{CodecRule, LowCardinalityRule, NullableSortingKeyRule, PoorCompressionRule};
 pub use context::AuditContext;
+pub use detached::DetachedPartsRule;
+pub use dictionaries::{DictionaryHealthRule, LargeDictionaryRule};
 pub use disk::DiskHeadroomRule;
-pub use engine::{Rule, RuleRegistry, RuleResult};
+pub use distribution::DistributionQueueRule;
+pub use engine::{action_id, finding_id, Rule, RuleRegistry, RuleResult};
+pub use explain::{ExplainFullScanRule, ExplainPrimaryKeyRule, ExplainSkipIndexRule};
+pub use failed_queries::FailedQueryRule;
+pub use inserts::InsertBatchingRule;
+pub use kafka::{KafkaCommitLagRule, KafkaOrphanRule};
 pub use merges::MergeBacklogRule;
 pub use mutations::StuckMutationRule;
 pub use parts::PartsExplosionRule;
-pub use query::QueryAmplificationRule;
+pub use query::{LatencyRegressionRule, QueryAmplificationRule};
+pub use replication::{ReadonlyReplicaRule, ReplicationLagRule, StuckReplicationQueueRule};
+pub use server::{BackgroundPoolRule, MemoryHeadroomRule};
+pub use ttl::{ExpiredTtlRule, MissingTtlRule, TtlOnlyDropPartsRule};
 
 impl RuleRegistry {
     /// Create a registry with all default rules
     pub fn with_default_rules() -> Self {
+        Self::build(&Config::default())
+    }
+
+    /// Create a registry with rules configured from a config file.
+    ///
+    /// Rules disabled everywhere are not registered; unknown rule IDs and
+    /// threshold names are rejected.
+    pub fn with_config(config: &Config) -> Result<Self> {
+        config.validate(&Self::build(&Config::default()).rule_thresholds())?;
+        Ok(Self::build(config))
+    }
+
+    fn build(config: &Config) -> Self {
+        let rules: Vec<Box<dyn Rule>> = vec![
+            Box::new(PartsExplosionRule::new(
+                config.rule(PartsExplosionRule::ID),
+                InsertBatchingRule::new(config.rule(InsertBatchingRule::ID)),
+            )),
+            Box::new(MergeBacklogRule::new(config.rule(MergeBacklogRule::ID))),
+            Box::new(DiskHeadroomRule::new(config.rule(DiskHeadroomRule::ID))),
+            Box::new(BackgroundPoolRule::new(config.rule(BackgroundPoolRule::ID))),
+            Box::new(MemoryHeadroomRule::new(config.rule(MemoryHeadroomRule::ID))),
+            Box::new(InsertBatchingRule::new(config.rule(InsertBatchingRule::ID))),
+            Box::new(QueryAmplificationRule::new(
+                config.rule(QueryAmplificationRule::ID),
+            )),
+            Box::new(LatencyRegressionRule::new(
+                config.rule(LatencyRegressionRule::ID),
+            )),
+            Box::new(FailedQueryRule::new(config.rule(FailedQueryRule::ID))),
+            Box::new(StuckMutationRule::new(config.rule(StuckMutationRule::ID))),
+            Box::new(ReplicationLagRule::new(config.rule(ReplicationLagRule::ID))),
+            Box::new(ReadonlyReplicaRule::new(
+                config.rule(ReadonlyReplicaRule::ID),
+            )),
+            Box::new(StuckReplicationQueueRule::new(
+                config.rule(StuckReplicationQueueRule::ID),
+            )),
+            Box::new(DistributionQueueRule::new(
+                config.rule(DistributionQueueRule::ID),
+            )),
+            Box::new(KafkaOrphanRule::new(config.rule(KafkaOrphanRule::ID))),
+            Box::new(KafkaCommitLagRule::new(config.rule(KafkaCommitLagRule::ID))),
+            Box::new(DictionaryHealthRule::new(
+                config.rule(DictionaryHealthRule::ID),
+            )),
+            Box::new(LargeDictionaryRule::new(
+                config.rule(LargeDictionaryRule::ID),
+            )),
+            Box::new(PoorCompressionRule::new(
+                config.rule(PoorCompressionRule::ID),
+            )),
+            Box::new(LowCardinalityRule::new(config.rule(LowCardinalityRule::ID))),
+            Box::new(NullableSortingKeyRule::new(
+                config.rule(NullableSortingKeyRule::ID),
+            )),
+            Box::new(CodecRule::new(config.rule(CodecRule::ID))),
+            Box::new(MissingTtlRule::new(config.rule(MissingTtlRule::ID))),
+            Box::new(ExpiredTtlRule::new(config.rule(ExpiredTtlRule::ID))),
+            Box::new(TtlOnlyDropPartsRule::new(
+                config.rule(TtlOnlyDropPartsRule::ID),
+            )),
+            Box::new(DetachedPartsRule::new(config.rule(DetachedPartsRule::ID))),
+            Box::new(ExplainPrimaryKeyRule::new(
+                config.rule(ExplainPrimaryKeyRule::ID),
+            )),
+            Box::new(ExplainFullScanRule::new(
+                config.rule(ExplainFullScanRule::ID),
+            )),
+            Box::new(ExplainSkipIndexRule::new(
+                config.rule(ExplainSkipIndexRule::ID),
+            )),
+        ];
+
         let mut registry = Self::new();
-        registry.register(Box::new(PartsExplosionRule));
-        registry.register(Box::new(MergeBacklogRule));
-        registry.register(Box::new(DiskHeadroomRule));
-        registry.register(Box::new(QueryAmplificationRule));
-        registry.register(Box::new(StuckMutationRule));
+        for rule in rules {
+            if config.rule(rule.id()).enabled_anywhere() {
+                registry.register(rule);
+            }
+        }
         registry
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_config_skips_disabled_rules() {
+        let config = Config::parse("[rules.query_amplification]\nenabled = false").unwrap();
+        let registry = RuleRegistry::with_config(&config).unwrap();
+
+        assert!(!registry.rule_ids().contains(&"query_amplification"));
+        assert_eq!(registry.len(), RuleRegistry::with_default_rules().len() - 1);
+    }
+
+    #[test]
+    fn test_finding_ids_unique_across_rules() {
+        use crate::report::{DiskMetrics, MergeMetrics, PartsMetrics};
+
+        let mut ctx = AuditContext::new();
+        for table in ["events", "orders"] {
+            ctx.add_parts(PartsMetrics {
+                database: "testdb".to_string(),
+                table: table.to_string(),
+                active_parts: 1500,
+                ..Default::default()
+            });
+            ctx.add_merges(MergeMetrics {
+                database: "testdb".to_string(),
+                table: table.to_string(),
+                merges_in_queue: 500,
+                ..Default::default()
+            });
+        }
+        ctx.set_disk(vec![DiskMetrics {
+            disk_name: "default".to_string(),
+            free_percent: 5.0,
+            ..Default::default()
+        }]);
+
+        let results = RuleRegistry::with_default_rules().evaluate_all(&ctx);
+        let ids: std::collections::BTreeSet<_> =
+            results.iter().map(|r| r.finding.id.as_str()).collect();
+        assert_eq!(results.len(), 5);
+        assert_eq!(ids.len(), results.len());
+        for result in &results {
+            assert!(result
+                .actions
+                .iter()
+                .all(|a| a.finding_ref == result.finding.id));
+        }
+    }
+
+    #[test]
+    fn test_with_config_keeps_rules_enabled_by_override() {
+        let config = Config::parse(
+            r#"
+[rules.parts_explosion]
+enabled = false
+
+[[overrides]]
+pattern = "testdb.big_*"
+[overrides.rules.parts_explosion]
+enabled = true
+"#,
+        )
+        .unwrap();
+        let registry = RuleRegistry::with_config(&config).unwrap();
+        assert!(registry.rule_ids().contains(&"parts_explosion"));
+
+        let mut ctx = AuditContext::new();
+        for table in ["big_events", "events"] {
+            ctx.add_parts(crate::report::PartsMetrics {
+                database: "testdb".to_string(),
+                table: table.to_string(),
+                active_parts: 1500,
+                ..Default::default()
+            });
+        }
+        let targets: Vec<_> = registry
+            .evaluate_all(&ctx)
+            .into_iter()
+            .filter(|r| r.finding.rule_id == "parts_explosion")
+            .map(|r| r.finding.target)
+            .collect();
+        assert_eq!(targets, vec!["testdb.big_events"]);
+    }
+
+    #[test]
+    fn test_with_config_rejects_unknown_threshold() {
+        let config = Config::parse(
+            "[rules.parts_explosion]
+warning_part = 500",
+        )
+        .unwrap();
+        let Err(err) = RuleRegistry::with_config(&config) else {
+            panic!("unknown threshold accepted");
+        };
+        let err = err.to_string();
+        assert!(err.contains("warning_part"));
+        assert!(err.contains("parts_explosion"));
+    }
+
+    #[test]
+    fn test_with_config_rejects_unknown_rule() {
+        let config = Config::parse("[rules.no_such_rule]\nwarning = 1").unwrap();
+        assert!(RuleRegistry::with_config(&config).is_err());
+    }
+}
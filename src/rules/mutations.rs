@@ -1,55 +1,179 @@
-use super::{AuditContext, Rule, RuleResult};
-use crate::report::{Action, ActionType, Finding, Priority, Severity};
+use super::{action_id, finding_id, AuditContext, Rule, RuleResult};
+use crate::ch::sql::literal;
+use crate::config::RuleConfig;
+use crate::report::{Action, ActionType, ActiveMutation, Finding, Priority, Severity};
 
 const MUTATION_STUCK_SEC: u64 = 3600; // 1 hour
 
-/// Rule to detect stuck mutations
-pub struct StuckMutationRule;
+/// A failure this recent means the mutation is still being retried
+const FAIL_RECENT_SEC: u64 = 600; // 10 minutes
+
+/// Characters of the mutation command quoted in messages
+const MAX_COMMAND_LEN: usize = 80;
+
+/// Rule to detect failing and stuck mutations
+///
+/// A mutation that keeps failing is retried forever and blocks every later
+/// mutation on the table, so it is critical: it failed within the last
+/// `fail_recent_sec`, has been retried for longer than that and still has parts
+/// to do. One that is only slow, or failed long ago, is a warning past
+/// `stuck_sec`.
+#[derive(Default)]
+pub struct StuckMutationRule {
+    config: RuleConfig,
+}
+
+impl StuckMutationRule {
+    pub const ID: &'static str = "stuck_mutation";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
 
 impl Rule for StuckMutationRule {
     fn id(&self) -> &'static str {
-        "stuck_mutation"
+        Self::ID
     }
 
     fn name(&self) -> &'static str {
         "Stuck Mutation"
     }
 
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.mutations"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["stuck_sec", "fail_recent_sec"]
+    }
+
     fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
         let mut results = Vec::new();
 
         for (table_key, metrics) in &ctx.mutations {
-            if metrics.active_mutations > 0 {
-                if let Some(age) = metrics.oldest_active_mutation_age_sec {
-                    if age > MUTATION_STUCK_SEC {
-                        let hours = age as f64 / 3600.0;
-                        results.push(RuleResult {
-                            finding: Finding {
-                                id: format!("f-mutation-{}", results.len() + 1),
-                                rule_id: self.id().to_string(),
-                                severity: Severity::Critical,
-                                target: table_key.clone(),
-                                message: format!(
-                                    "Mutation running for {:.1} hours (threshold: 1 hour)",
-                                    hours
-                                ),
-                                evidence_refs: vec![],
-                                confidence: 1.0,
-                            },
-                            actions: vec![Action {
-                                id: format!("a-mutation-{}", results.len() + 1),
-                                finding_ref: format!("f-mutation-{}", results.len() + 1),
-                                action_type: ActionType::Recommendation,
-                                priority: Priority::High,
-                                description: "Investigate mutation, consider KILL MUTATION if stuck".to_string(),
-                                sql: Some(format!(
-                                    "SELECT * FROM system.mutations WHERE database = '{}' AND table = '{}' AND is_done = 0",
-                                    metrics.database, metrics.table
-                                )),
-                            }],
-                        });
-                    }
+            if !self.config.enabled_for(table_key) {
+                continue;
+            }
+
+            let stuck_sec = self
+                .config
+                .threshold(table_key, "stuck_sec", MUTATION_STUCK_SEC as f64)
+                as u64;
+
+            let fail_recent_sec =
+                self.config
+                    .threshold(table_key, "fail_recent_sec", FAIL_RECENT_SEC as f64)
+                    as u64;
+
+            for mutation in &metrics.active {
+                let hours = mutation.age_sec as f64 / 3600.0;
+                let reason = mutation
+                    .latest_fail_reason
+                    .as_deref()
+                    .map(|r| r.lines().next().unwrap_or_default());
+                let failing = reason.is_some()
+                    && mutation
+                        .latest_fail_age_sec
+                        .is_some_and(|age| age <= fail_recent_sec)
+                    && mutation.age_sec > fail_recent_sec
+                    && mutation.parts_to_do > 0;
+
+                let (severity, mut message) = if failing {
+                    (
+                        Severity::Critical,
+                        format!(
+                            "Mutation {} ({}) keeps failing, {} parts left after {:.1} hours",
+                            mutation.mutation_id,
+                            command(mutation),
+                            mutation.parts_to_do,
+                            hours
+                        ),
+                    )
+                } else if mutation.age_sec > stuck_sec {
+                    (
+                        Severity::Warning,
+                        format!(
+                            "Mutation {} ({}) running for {:.1} hours with {} parts left (threshold: {:.1} hours)",
+                            mutation.mutation_id,
+                            command(mutation),
+                            hours,
+                            mutation.parts_to_do,
+                            stuck_sec as f64 / 3600.0
+                        ),
+                    )
+                } else {
+                    continue;
+                };
+                if let Some(reason) = reason {
+                    message.push_str(&format!(
+                        "; last failed at {}: {}",
+                        mutation
+                            .latest_fail_time
+                            .as_deref()
+                            .unwrap_or("unknown time"),
+                        reason
+                    ));
                 }
+
+                let kill_sql = format!(
+                    "KILL MUTATION WHERE database = {} AND table = {} AND mutation_id = {}",
+                    literal(&metrics.database),
+                    literal(&metrics.table),
+                    literal(&mutation.mutation_id)
+                );
+                let target = format!("{}:{}", table_key, mutation.mutation_id);
+                let id = finding_id(self.id(), &target, &severity);
+                let actions = if severity == Severity::Critical {
+                    vec![Action {
+                        id: action_id(&id, 0),
+                        finding_ref: id.clone(),
+                        action_type: ActionType::Recommendation,
+                        priority: Priority::High,
+                        description: "Kill the mutation: it will not succeed on retry and blocks later mutations. Fix the command and submit it again".to_string(),
+                        sql: Some(kill_sql),
+                        destructive: true,
+                    }]
+                } else {
+                    vec![
+                        Action {
+                            id: action_id(&id, 0),
+                            finding_ref: id.clone(),
+                            action_type: ActionType::Recommendation,
+                            priority: Priority::Medium,
+                            description: "Check that parts_to_do is falling; a mutation rewrites every part it touches, so large tables take time".to_string(),
+                            sql: Some(format!(
+                                "SELECT mutation_id, parts_to_do, latest_fail_reason FROM system.mutations WHERE database = {} AND table = {} AND mutation_id = {}",
+                                literal(&metrics.database),
+                                literal(&metrics.table),
+                                literal(&mutation.mutation_id)
+                            )),
+                            destructive: false,
+                        },
+                        Action {
+                            id: action_id(&id, 1),
+                            finding_ref: id.clone(),
+                            action_type: ActionType::Recommendation,
+                            priority: Priority::Low,
+                            description: "Kill the mutation if it is no longer needed".to_string(),
+                            sql: Some(kill_sql),
+                            destructive: true,
+                        },
+                    ]
+                };
+
+                results.push(RuleResult {
+                    finding: Finding {
+                        id,
+                        rule_id: self.id().to_string(),
+                        severity,
+                        target,
+                        message,
+                        evidence_refs: ctx.evidence_refs("system.mutations"),
+                        confidence: 1.0,
+                    },
+                    actions,
+                });
             }
         }
 
@@ -57,18 +181,43 @@ impl Rule for StuckMutationRule {
     }
 }
 
+/// Mutation command shortened for a message
+fn command(mutation: &ActiveMutation) -> String {
+    let command = mutation.command.trim();
+    if command.chars().count() > MAX_COMMAND_LEN {
+        let short: String = command.chars().take(MAX_COMMAND_LEN - 3).collect();
+        format!("{}...", short)
+    } else {
+        command.to_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::report::MutationMetrics;
 
-    fn ctx_with_mutations(active: u64, age_sec: Option<u64>) -> AuditContext {
+    fn mutation(age_sec: u64, latest_fail_reason: Option<&str>) -> ActiveMutation {
+        ActiveMutation {
+            mutation_id: "mutation_7.txt".to_string(),
+            command: "DELETE WHERE user_id = 1".to_string(),
+            create_time: "2024-01-01 00:00:00".to_string(),
+            age_sec,
+            parts_to_do: 12,
+            latest_fail_time: latest_fail_reason.map(|_| "2024-01-01 01:00:00".to_string()),
+            latest_fail_age_sec: latest_fail_reason.map(|_| 60),
+            latest_fail_reason: latest_fail_reason.map(str::to_string),
+        }
+    }
+
+    fn ctx_with_mutations(table: &str, active: Vec<ActiveMutation>) -> AuditContext {
         let mut ctx = AuditContext::new();
         ctx.add_mutations(MutationMetrics {
             database: "testdb".to_string(),
-            table: "events".to_string(),
-            active_mutations: active,
-            oldest_active_mutation_age_sec: age_sec,
+            table: table.to_string(),
+            active_mutations: active.len() as u64,
+            oldest_active_mutation_age_sec: active.iter().map(|m| m.age_sec).max(),
+            active,
             ..Default::default()
         });
         ctx
@@ -76,47 +225,126 @@ mod tests {
 
     #[test]
     fn test_mutation_none() {
-        let rule = StuckMutationRule;
-        let ctx = ctx_with_mutations(0, None);
+        let rule = StuckMutationRule::default();
+        let ctx = ctx_with_mutations("events", vec![]);
         let results = rule.evaluate(&ctx);
         assert!(results.is_empty());
     }
 
     #[test]
     fn test_mutation_active_recent() {
-        let rule = StuckMutationRule;
-        let ctx = ctx_with_mutations(1, Some(600)); // 10 minutes
+        let rule = StuckMutationRule::default();
+        let ctx = ctx_with_mutations("events", vec![mutation(600, None)]); // 10 minutes
         let results = rule.evaluate(&ctx);
         assert!(results.is_empty());
     }
 
     #[test]
-    fn test_mutation_stuck() {
-        let rule = StuckMutationRule;
-        let ctx = ctx_with_mutations(1, Some(7200)); // 2 hours
+    fn test_mutation_slow_is_warning() {
+        let rule = StuckMutationRule::default();
+        let ctx = ctx_with_mutations("events", vec![mutation(7200, None)]); // 2 hours
         let results = rule.evaluate(&ctx);
         assert_eq!(results.len(), 1);
-        assert_eq!(results[0].finding.severity, Severity::Critical);
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+        assert_eq!(
+            results[0].finding.message,
+            "Mutation mutation_7.txt (DELETE WHERE user_id = 1) running for 2.0 hours with 12 parts left (threshold: 1.0 hours)"
+        );
+        assert_eq!(results[0].finding.target, "testdb.events:mutation_7.txt");
+        assert_eq!(results[0].actions.len(), 2);
+        assert_eq!(results[0].actions[1].priority, Priority::Low);
+        assert!(results[0].actions[1].destructive);
     }
 
     #[test]
     fn test_mutation_at_threshold() {
-        let rule = StuckMutationRule;
-        let ctx = ctx_with_mutations(1, Some(3600)); // exactly 1 hour
+        let rule = StuckMutationRule::default();
+        let ctx = ctx_with_mutations("events", vec![mutation(3600, None)]); // exactly 1 hour
         let results = rule.evaluate(&ctx);
         assert!(results.is_empty()); // 3600 is NOT > 3600
     }
 
     #[test]
-    fn test_mutation_action_has_sql() {
-        let rule = StuckMutationRule;
-        let ctx = ctx_with_mutations(1, Some(7200));
+    fn test_mutation_failing_is_critical() {
+        let rule = StuckMutationRule::default();
+        let ctx = ctx_with_mutations(
+            "events",
+            vec![mutation(
+                1800,
+                Some("Code: 341. DB::Exception: Cannot parse\nStack trace:"),
+            )],
+        );
+        let results = rule.evaluate(&ctx);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+        assert!(results[0]
+            .finding
+            .message
+            .ends_with("keeps failing, 12 parts left after 0.5 hours; last failed at 2024-01-01 01:00:00: Code: 341. DB::Exception: Cannot parse"));
+        assert_eq!(
+            results[0].actions[0].sql.as_deref(),
+            Some("KILL MUTATION WHERE database = 'testdb' AND table = 'events' AND mutation_id = 'mutation_7.txt'")
+        );
+        assert!(results[0].actions[0].destructive);
+    }
+
+    #[test]
+    fn test_mutation_old_or_first_failure_is_not_critical() {
+        let rule = StuckMutationRule::default();
+        let reason = Some("Code: 241. DB::Exception: Memory limit exceeded");
+
+        // Failed once shortly after it was created
+        let ctx = ctx_with_mutations("events", vec![mutation(300, reason)]);
+        assert!(rule.evaluate(&ctx).is_empty());
+
+        // Last failed hours ago and has been progressing since
+        let mut stale = mutation(7200, reason);
+        stale.latest_fail_age_sec = Some(5400);
+        let ctx = ctx_with_mutations("events", vec![stale]);
+        let results = rule.evaluate(&ctx);
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+        assert!(results[0]
+            .finding
+            .message
+            .ends_with("(threshold: 1.0 hours); last failed at 2024-01-01 01:00:00: Code: 241. DB::Exception: Memory limit exceeded"));
+
+        // Nothing left to mutate
+        let mut done = mutation(1800, reason);
+        done.parts_to_do = 0;
+        let ctx = ctx_with_mutations("events", vec![done]);
+        assert!(rule.evaluate(&ctx).is_empty());
+    }
+
+    #[test]
+    fn test_mutation_one_finding_per_mutation() {
+        let rule = StuckMutationRule::default();
+        let mut second = mutation(9000, None);
+        second.mutation_id = "mutation_8.txt".to_string();
+        let ctx = ctx_with_mutations("events", vec![mutation(7200, None), second]);
+        let results = rule.evaluate(&ctx);
+        assert_eq!(results.len(), 2);
+        assert_ne!(results[0].finding.target, results[1].finding.target);
+        assert_ne!(results[0].finding.id, results[1].finding.id);
+    }
+
+    #[test]
+    fn test_mutation_action_escapes_names() {
+        let rule = StuckMutationRule::default();
+        let ctx = ctx_with_mutations("it's", vec![mutation(7200, None)]);
+
         let results = rule.evaluate(&ctx);
-        assert!(results[0].actions[0].sql.is_some());
         assert!(results[0].actions[0]
             .sql
             .as_ref()
             .unwrap()
-            .contains("system.mutations"));
+            .contains(r"table = 'it\'s'"));
+    }
+
+    #[test]
+    fn test_command_is_shortened() {
+        let mut m = mutation(0, None);
+        m.command = format!("UPDATE v = 1 WHERE id IN ({})", "1, ".repeat(50));
+        assert_eq!(command(&m).chars().count(), MAX_COMMAND_LEN);
+        assert!(command(&m).ends_with("..."));
     }
 }
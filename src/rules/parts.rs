@@ -1,75 +1,251 @@
-use super::{AuditContext, Rule, RuleResult};
-use crate::report::{Action, ActionType, Finding, Priority, Severity};
+use super::inserts::InsertBatchingRule;
+use super::{action_id, finding_id, AuditContext, Rule, RuleResult};
+use crate::ch::sql::{literal, table_name};
+use crate::config::RuleConfig;
+use crate::report::{
+    Action, ActionType, Finding, MergeTreeSettingsMetrics, PartitionMetrics, PartsMetrics,
+    Priority, Severity,
+};
 
-/// Thresholds for parts explosion detection
+/// Default thresholds for parts explosion detection
 const PARTS_WARNING: u64 = 300;
 const PARTS_CRITICAL: u64 = 1000;
 
+/// Default fractions of the effective limit, when merge_tree settings are known
+const LIMIT_WARNING_RATIO: f64 = 0.5;
+const LIMIT_CRITICAL_RATIO: f64 = 0.8;
+
 /// Rule to detect parts explosion in tables
-pub struct PartsExplosionRule;
+///
+/// When the table's merge_tree settings were collected, severity is the
+/// fraction of `parts_to_throw_insert` (worst partition) or
+/// `max_parts_in_total` (whole table) already used. Otherwise the fixed
+/// `warning`/`critical` thresholds apply to the worst partition.
+///
+/// Tables whose inserts `insert_small_batches` reports are noted in the message.
+#[derive(Default)]
+pub struct PartsExplosionRule {
+    config: RuleConfig,
+    batching: InsertBatchingRule,
+}
+
+impl PartsExplosionRule {
+    pub const ID: &'static str = "parts_explosion";
+
+    pub fn new(config: RuleConfig, batching: InsertBatchingRule) -> Self {
+        Self { config, batching }
+    }
+
+    /// Severity and message relative to the table's effective part limits
+    fn against_limits(
+        &self,
+        table_key: &str,
+        metrics: &PartsMetrics,
+        worst: Option<&PartitionMetrics>,
+        limits: &MergeTreeSettingsMetrics,
+    ) -> Option<(Severity, String)> {
+        let warning_ratio = self
+            .config
+            .threshold(table_key, "warning_ratio", LIMIT_WARNING_RATIO);
+        let critical_ratio =
+            self.config
+                .threshold(table_key, "critical_ratio", LIMIT_CRITICAL_RATIO);
+
+        let partition_parts = worst.map_or(metrics.active_parts, |p| p.active_parts);
+        let ratio = |parts: u64, limit: u64| {
+            if limit == 0 {
+                0.0
+            } else {
+                parts as f64 / limit as f64
+            }
+        };
+        let partition_ratio = ratio(partition_parts, limits.parts_to_throw_insert);
+        let total_ratio = ratio(metrics.active_parts, limits.max_parts_in_total);
+        let delayed =
+            limits.parts_to_delay_insert > 0 && partition_parts >= limits.parts_to_delay_insert;
+
+        let (subject, used, setting, limit) = if total_ratio > partition_ratio {
+            (
+                format!("Table has {} active parts", metrics.active_parts),
+                total_ratio,
+                "max_parts_in_total",
+                limits.max_parts_in_total,
+            )
+        } else {
+            (
+                subject(metrics, worst),
+                partition_ratio,
+                "parts_to_throw_insert",
+                limits.parts_to_throw_insert,
+            )
+        };
+
+        let severity = if used > critical_ratio {
+            Severity::Critical
+        } else if used > warning_ratio || delayed {
+            Severity::Warning
+        } else {
+            return None;
+        };
+
+        let mut message = format!(
+            "{}, {:.0}% of {} = {}: ",
+            subject,
+            used * 100.0,
+            setting,
+            limit
+        );
+        if used >= 1.0 {
+            message.push_str("inserts are being rejected with \"Too many parts\"");
+        } else {
+            message.push_str(&format!(
+                "inserts will be rejected with \"Too many parts\" at {}",
+                limit
+            ));
+            if delayed {
+                message.push_str(&format!(
+                    " and are already delayed (parts_to_delay_insert = {})",
+                    limits.parts_to_delay_insert
+                ));
+            }
+        }
+        Some((severity, message))
+    }
+
+    /// Severity and message from the fixed thresholds
+    fn against_thresholds(
+        &self,
+        table_key: &str,
+        metrics: &PartsMetrics,
+        worst: Option<&PartitionMetrics>,
+    ) -> Option<(Severity, String)> {
+        let warning = self
+            .config
+            .threshold(table_key, "warning", PARTS_WARNING as f64) as u64;
+        let critical = self
+            .config
+            .threshold(table_key, "critical", PARTS_CRITICAL as f64) as u64;
+
+        let active_parts = worst.map_or(metrics.active_parts, |p| p.active_parts);
+        let (severity, level, threshold) = if active_parts > critical {
+            (Severity::Critical, "critical", critical)
+        } else if active_parts > warning {
+            (Severity::Warning, "warning", warning)
+        } else {
+            return None;
+        };
+
+        Some((
+            severity,
+            format!(
+                "{}, exceeding {} threshold of {}",
+                subject(metrics, worst),
+                level,
+                threshold
+            ),
+        ))
+    }
+}
+
+/// "Partition X has N active parts (table total: T)" or "Table has N active parts"
+fn subject(metrics: &PartsMetrics, worst: Option<&PartitionMetrics>) -> String {
+    match worst {
+        Some(p) => format!(
+            "Partition {} has {} active parts (table total: {})",
+            p.partition, p.active_parts, metrics.active_parts
+        ),
+        None => format!("Table has {} active parts", metrics.active_parts),
+    }
+}
 
 impl Rule for PartsExplosionRule {
     fn id(&self) -> &'static str {
-        "parts_explosion"
+        Self::ID
     }
 
     fn name(&self) -> &'static str {
         "Parts Explosion"
     }
 
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.parts"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["warning", "critical", "warning_ratio", "critical_ratio"]
+    }
+
     fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
         let mut results = Vec::new();
 
         for (table_key, metrics) in &ctx.parts {
-            let active_parts = metrics.active_parts;
-
-            if active_parts > PARTS_CRITICAL {
-                results.push(RuleResult {
-                    finding: Finding {
-                        id: format!("f-parts-{}", results.len() + 1),
-                        rule_id: self.id().to_string(),
-                        severity: Severity::Critical,
-                        target: table_key.clone(),
-                        message: format!(
-                            "Table has {} active parts, exceeding critical threshold of {}",
-                            active_parts, PARTS_CRITICAL
-                        ),
-                        evidence_refs: vec![],
-                        confidence: 1.0,
-                    },
-                    actions: vec![Action {
-                        id: format!("a-parts-{}", results.len() + 1),
-                        finding_ref: format!("f-parts-{}", results.len() + 1),
-                        action_type: ActionType::Recommendation,
-                        priority: Priority::High,
-                        description: "Run OPTIMIZE TABLE to reduce parts count".to_string(),
-                        sql: Some(format!("OPTIMIZE TABLE {} FINAL", table_key)),
-                    }],
-                });
-            } else if active_parts > PARTS_WARNING {
-                results.push(RuleResult {
-                    finding: Finding {
-                        id: format!("f-parts-{}", results.len() + 1),
-                        rule_id: self.id().to_string(),
-                        severity: Severity::Warning,
-                        target: table_key.clone(),
-                        message: format!(
-                            "Table has {} active parts, exceeding warning threshold of {}",
-                            active_parts, PARTS_WARNING
-                        ),
-                        evidence_refs: vec![],
-                        confidence: 1.0,
-                    },
-                    actions: vec![Action {
-                        id: format!("a-parts-{}", results.len() + 1),
-                        finding_ref: format!("f-parts-{}", results.len() + 1),
-                        action_type: ActionType::Recommendation,
-                        priority: Priority::Medium,
-                        description: "Consider running OPTIMIZE TABLE to reduce parts".to_string(),
-                        sql: Some(format!("OPTIMIZE TABLE {} FINAL", table_key)),
-                    }],
-                });
+            if !self.config.enabled_for(table_key) {
+                continue;
+            }
+
+            // Inserts are throttled per partition, so judge the worst one
+            let worst = metrics.partitions.iter().max_by_key(|p| p.active_parts);
+
+            let limits = ctx.merge_tree_settings.get(table_key);
+            let assessment = match limits {
+                Some(limits) => self.against_limits(table_key, metrics, worst, limits),
+                None => self.against_thresholds(table_key, metrics, worst),
+            };
+            let Some((severity, mut message)) = assessment else {
+                continue;
+            };
+            let inserts = self.batching.small_inserts(ctx, table_key);
+            if let Some(inserts) = &inserts {
+                message.push_str(&format!("; fed by {}", inserts));
+            }
+
+            let (priority, description) = if severity == Severity::Critical {
+                (Priority::High, "Run OPTIMIZE TABLE to reduce parts count")
+            } else {
+                (
+                    Priority::Medium,
+                    "Consider running OPTIMIZE TABLE to reduce parts",
+                )
+            };
+            let target = table_name(&metrics.database, &metrics.table);
+            let sql = match worst {
+                Some(p) => format!(
+                    "OPTIMIZE TABLE {} PARTITION ID {} FINAL",
+                    target,
+                    literal(&p.partition_id)
+                ),
+                None => format!("OPTIMIZE TABLE {} FINAL", target),
+            };
+
+            let mut evidence_refs = ctx.evidence_refs("system.parts");
+            if limits.is_some() {
+                evidence_refs.extend(ctx.evidence_refs("system.merge_tree_settings"));
+            }
+            if inserts.is_some() {
+                evidence_refs.extend(ctx.evidence_refs("inserts"));
             }
+
+            let id = finding_id(self.id(), table_key, &severity);
+            results.push(RuleResult {
+                finding: Finding {
+                    id: id.clone(),
+                    rule_id: self.id().to_string(),
+                    severity,
+                    target: table_key.clone(),
+                    message,
+                    evidence_refs,
+                    confidence: 1.0,
+                },
+                actions: vec![Action {
+                    id: action_id(&id, 0),
+                    finding_ref: id,
+                    action_type: ActionType::Recommendation,
+                    priority,
+                    description: description.to_string(),
+                    sql: Some(sql),
+                    destructive: false,
+                }],
+            });
         }
 
         results
@@ -79,7 +255,6 @@ impl Rule for PartsExplosionRule {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::report::PartsMetrics;
 
     fn ctx_with_parts(active_parts: u64) -> AuditContext {
         let mut ctx = AuditContext::new();
@@ -94,7 +269,7 @@ mod tests {
 
     #[test]
     fn test_parts_explosion_healthy() {
-        let rule = PartsExplosionRule;
+        let rule = PartsExplosionRule::default();
         let ctx = ctx_with_parts(100); // below 300 threshold
 
         let results = rule.evaluate(&ctx);
@@ -103,7 +278,7 @@ mod tests {
 
     #[test]
     fn test_parts_explosion_warning() {
-        let rule = PartsExplosionRule;
+        let rule = PartsExplosionRule::default();
         let ctx = ctx_with_parts(500); // between 300 and 1000
 
         let results = rule.evaluate(&ctx);
@@ -114,7 +289,7 @@ mod tests {
 
     #[test]
     fn test_parts_explosion_critical() {
-        let rule = PartsExplosionRule;
+        let rule = PartsExplosionRule::default();
         let ctx = ctx_with_parts(1500); // above 1000
 
         let results = rule.evaluate(&ctx);
@@ -124,7 +299,7 @@ mod tests {
 
     #[test]
     fn test_parts_explosion_at_warning_threshold() {
-        let rule = PartsExplosionRule;
+        let rule = PartsExplosionRule::default();
         let ctx = ctx_with_parts(300); // exactly at threshold
 
         let results = rule.evaluate(&ctx);
@@ -133,7 +308,7 @@ mod tests {
 
     #[test]
     fn test_parts_explosion_just_above_warning() {
-        let rule = PartsExplosionRule;
+        let rule = PartsExplosionRule::default();
         let ctx = ctx_with_parts(301);
 
         let results = rule.evaluate(&ctx);
@@ -143,7 +318,7 @@ mod tests {
 
     #[test]
     fn test_parts_explosion_action_has_sql() {
-        let rule = PartsExplosionRule;
+        let rule = PartsExplosionRule::default();
         let ctx = ctx_with_parts(1500);
 
         let results = rule.evaluate(&ctx);
@@ -155,18 +330,50 @@ mod tests {
             .contains("OPTIMIZE"));
     }
 
+    #[test]
+    fn test_parts_explosion_action_quotes_table_name() {
+        let rule = PartsExplosionRule::default();
+        let mut ctx = AuditContext::new();
+        ctx.add_parts(PartsMetrics {
+            database: "raw-data".to_string(),
+            table: "events`; DROP TABLE x".to_string(),
+            active_parts: 1500,
+            ..Default::default()
+        });
+
+        let results = rule.evaluate(&ctx);
+        assert_eq!(
+            results[0].actions[0].sql.as_deref(),
+            Some("OPTIMIZE TABLE `raw-data`.`events\\`; DROP TABLE x` FINAL")
+        );
+    }
+
     #[test]
     fn test_parts_explosion_target_is_table() {
-        let rule = PartsExplosionRule;
+        let rule = PartsExplosionRule::default();
         let ctx = ctx_with_parts(1500);
 
         let results = rule.evaluate(&ctx);
         assert_eq!(results[0].finding.target, "testdb.events");
     }
 
+    #[test]
+    fn test_parts_explosion_ids_are_stable() {
+        let rule = PartsExplosionRule::default();
+        let first = rule.evaluate(&ctx_with_parts(1500));
+        let second = rule.evaluate(&ctx_with_parts(1800));
+
+        assert_eq!(first[0].finding.id, second[0].finding.id);
+        assert_eq!(first[0].actions[0].finding_ref, first[0].finding.id);
+        assert_ne!(
+            first[0].finding.id,
+            rule.evaluate(&ctx_with_parts(500))[0].finding.id
+        );
+    }
+
     #[test]
     fn test_parts_explosion_multiple_tables() {
-        let rule = PartsExplosionRule;
+        let rule = PartsExplosionRule::default();
         let mut ctx = AuditContext::new();
 
         ctx.add_parts(PartsMetrics {
@@ -191,4 +398,224 @@ mod tests {
         let results = rule.evaluate(&ctx);
         assert_eq!(results.len(), 2); // warning + critical, not healthy
     }
+
+    #[test]
+    fn test_parts_explosion_configured_thresholds() {
+        let config = crate::config::Config::parse(
+            r#"
+[rules.parts_explosion]
+warning = 1000
+critical = 5000
+
+[[overrides]]
+pattern = "testdb.*"
+[overrides.rules.parts_explosion]
+critical = 1200
+"#,
+        )
+        .unwrap();
+        let rule = PartsExplosionRule::new(
+            config.rule("parts_explosion"),
+            InsertBatchingRule::default(),
+        );
+
+        assert!(rule.evaluate(&ctx_with_parts(500)).is_empty());
+
+        let results = rule.evaluate(&ctx_with_parts(1500));
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+        assert!(results[0].finding.message.contains("1200"));
+    }
+
+    fn ctx_with_partitions(table_parts: u64, partitions: &[(&str, u64)]) -> AuditContext {
+        let mut ctx = AuditContext::new();
+        ctx.add_parts(PartsMetrics {
+            database: "testdb".to_string(),
+            table: "events".to_string(),
+            active_parts: table_parts,
+            partitions: partitions
+                .iter()
+                .map(|(id, active_parts)| PartitionMetrics {
+                    partition_id: id.to_string(),
+                    partition: id.to_string(),
+                    active_parts: *active_parts,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        });
+        ctx
+    }
+
+    #[test]
+    fn test_parts_explosion_uses_worst_partition() {
+        let rule = PartsExplosionRule::default();
+        let ctx = ctx_with_partitions(1500, &[("202401", 20), ("202402", 400)]);
+
+        let results = rule.evaluate(&ctx);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+        assert_eq!(
+            results[0].finding.message,
+            "Partition 202402 has 400 active parts (table total: 1500), exceeding warning threshold of 300"
+        );
+        assert_eq!(
+            results[0].actions[0].sql.as_deref(),
+            Some("OPTIMIZE TABLE testdb.events PARTITION ID '202402' FINAL")
+        );
+    }
+
+    #[test]
+    fn test_parts_explosion_spread_across_partitions_is_healthy() {
+        let rule = PartsExplosionRule::default();
+        let ctx = ctx_with_partitions(1500, &[("202401", 250), ("202402", 250)]);
+
+        assert!(rule.evaluate(&ctx).is_empty());
+    }
+
+    fn limits(delay: u64, throw: u64, total: u64) -> MergeTreeSettingsMetrics {
+        MergeTreeSettingsMetrics {
+            database: "testdb".to_string(),
+            table: "events".to_string(),
+            parts_to_delay_insert: delay,
+            parts_to_throw_insert: throw,
+            max_parts_in_total: total,
+            overrides: vec![],
+        }
+    }
+
+    #[test]
+    fn test_parts_explosion_uses_table_limits() {
+        let rule = PartsExplosionRule::default();
+        let mut ctx = ctx_with_partitions(2600, &[("202402", 2500), ("202401", 100)]);
+        ctx.set_evidence("system.parts", "ev-001");
+        ctx.set_evidence("system.merge_tree_settings", "ev-002");
+        ctx.add_merge_tree_settings(limits(1000, 3000, 100_000));
+
+        let results = rule.evaluate(&ctx);
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+        assert_eq!(
+            results[0].finding.message,
+            "Partition 202402 has 2500 active parts (table total: 2600), 83% of parts_to_throw_insert = 3000: inserts will be rejected with \"Too many parts\" at 3000 and are already delayed (parts_to_delay_insert = 1000)"
+        );
+        assert_eq!(results[0].finding.evidence_refs, vec!["ev-001", "ev-002"]);
+    }
+
+    #[test]
+    fn test_parts_explosion_limits_replace_fixed_thresholds() {
+        let rule = PartsExplosionRule::default();
+
+        // 1500 parts exceeds the fixed critical threshold but is far from a raised limit
+        let mut ctx = ctx_with_partitions(1500, &[("202402", 1500)]);
+        ctx.add_merge_tree_settings(limits(5000, 10_000, 100_000));
+        assert!(rule.evaluate(&ctx).is_empty());
+
+        // ...while a lowered per-table limit makes 200 parts a warning
+        let mut ctx = ctx_with_partitions(200, &[("202402", 200)]);
+        ctx.add_merge_tree_settings(limits(150, 300, 100_000));
+        let results = rule.evaluate(&ctx);
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+        assert!(results[0]
+            .finding
+            .message
+            .contains("67% of parts_to_throw_insert = 300"));
+    }
+
+    #[test]
+    fn test_parts_explosion_max_parts_in_total() {
+        let rule = PartsExplosionRule::default();
+        let mut ctx = ctx_with_partitions(9500, &[("a", 100), ("b", 100)]);
+        ctx.add_merge_tree_settings(limits(1000, 3000, 10_000));
+
+        let results = rule.evaluate(&ctx);
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+        assert!(results[0]
+            .finding
+            .message
+            .starts_with("Table has 9500 active parts, 95% of max_parts_in_total = 10000"));
+    }
+
+    #[test]
+    fn test_parts_explosion_already_rejecting() {
+        let rule = PartsExplosionRule::default();
+        let mut ctx = ctx_with_partitions(3100, &[("202402", 3100)]);
+        ctx.add_merge_tree_settings(limits(1000, 3000, 100_000));
+
+        let results = rule.evaluate(&ctx);
+        assert!(results[0]
+            .finding
+            .message
+            .ends_with("inserts are being rejected with \"Too many parts\""));
+    }
+
+    fn ctx_with_small_inserts() -> AuditContext {
+        let mut ctx = ctx_with_parts(1500);
+        ctx.set_evidence("inserts", "ev-inserts");
+        ctx.add_inserts(crate::report::InsertMetrics {
+            database: "testdb".to_string(),
+            table: "events".to_string(),
+            inserts_per_sec: 5.0,
+            rows: 1000,
+            median_rows_per_insert: 2,
+            parts_per_insert: 1.0,
+            ..Default::default()
+        });
+        ctx
+    }
+
+    #[test]
+    fn test_parts_explosion_mentions_small_inserts() {
+        let rule = PartsExplosionRule::default();
+        let ctx = ctx_with_small_inserts();
+
+        let results = rule.evaluate(&ctx);
+        assert!(results[0]
+            .finding
+            .message
+            .ends_with("; fed by 5.0 inserts/s with a median of 2 rows, 1.0 parts per insert"));
+        assert!(results[0]
+            .finding
+            .evidence_refs
+            .contains(&"ev-inserts".to_string()));
+    }
+
+    #[test]
+    fn test_parts_explosion_small_inserts_use_configured_thresholds() {
+        let config = crate::config::Config::parse(
+            r#"
+[rules.insert_small_batches]
+warning_inserts_per_sec = 10
+"#,
+        )
+        .unwrap();
+        let rule = PartsExplosionRule::new(
+            config.rule(PartsExplosionRule::ID),
+            InsertBatchingRule::new(config.rule(InsertBatchingRule::ID)),
+        );
+
+        let results = rule.evaluate(&ctx_with_small_inserts());
+        assert!(!results[0].finding.message.contains("fed by"));
+        assert!(!results[0]
+            .finding
+            .evidence_refs
+            .contains(&"ev-inserts".to_string()));
+    }
+
+    #[test]
+    fn test_parts_explosion_disabled_for_table() {
+        let config = crate::config::Config::parse(
+            r#"
+[[overrides]]
+pattern = "testdb.events"
+[overrides.rules.parts_explosion]
+enabled = false
+"#,
+        )
+        .unwrap();
+        let rule = PartsExplosionRule::new(
+            config.rule("parts_explosion"),
+            InsertBatchingRule::default(),
+        );
+
+        assert!(rule.evaluate(&ctx_with_parts(1500)).is_empty());
+    }
 }
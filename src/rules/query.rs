@@ -1,83 +1,342 @@
-use super::{AuditContext, Rule, RuleResult};
-use crate::report::{Action, ActionType, Finding, Priority, Severity};
+use super::{action_id, finding_id, AuditContext, Rule, RuleResult};
+use crate::config::RuleConfig;
+use crate::report::{Action, ActionType, Finding, Priority, QueryMetrics, Severity};
 
 const READ_AMP_WARNING: f64 = 100.0;
 const READ_AMP_CRITICAL: f64 = 1000.0;
 
-/// Rule to detect high read amplification in queries
-pub struct QueryAmplificationRule;
+const GIB: f64 = 1_073_741_824.0;
+
+/// Default fractions of table marks read per run for a full scan
+const SCAN_RATIO_WARNING: f64 = 0.5;
+const SCAN_RATIO_CRITICAL: f64 = 0.9;
+
+/// Default table sizes below which scans are not reported, or not critical
+const SCAN_MIN_BYTES: f64 = GIB;
+const SCAN_CRITICAL_BYTES: f64 = 100.0 * GIB;
+
+/// Default p95 growth factors between the previous and current window
+const REGRESSION_WARNING: f64 = 2.0;
+const REGRESSION_CRITICAL: f64 = 5.0;
+
+/// Default runs needed in each window for the p95 to be comparable
+const REGRESSION_MIN_EXECUTIONS: f64 = 10.0;
+
+/// Default current p95 below which a regression is not worth reporting
+const REGRESSION_MIN_P95_MS: f64 = 100.0;
+
+/// Rule to detect queries that read far more than they need
+///
+/// Uses the fraction of table marks selected when the tables read are known,
+/// otherwise read rows per result row.
+#[derive(Default)]
+pub struct QueryAmplificationRule {
+    config: RuleConfig,
+}
+
+/// Rule to detect queries whose p95 duration grew since the previous window
+#[derive(Default)]
+pub struct LatencyRegressionRule {
+    config: RuleConfig,
+}
+
+impl LatencyRegressionRule {
+    pub const ID: &'static str = "query_latency_regression";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl QueryAmplificationRule {
+    pub const ID: &'static str = "query_amplification";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
 
 impl Rule for QueryAmplificationRule {
     fn id(&self) -> &'static str {
-        "query_amplification"
+        Self::ID
     }
 
     fn name(&self) -> &'static str {
         "Query Read Amplification"
     }
 
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.query_log"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &[
+            "warning",
+            "critical",
+            "warning_scan_ratio",
+            "critical_scan_ratio",
+            "min_scan_bytes",
+            "critical_scan_bytes",
+        ]
+    }
+
     fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
         let mut results = Vec::new();
 
         for query in &ctx.queries {
-            let amp = query.read_amplification;
-
-            if amp > READ_AMP_CRITICAL {
-                results.push(RuleResult {
-                    finding: Finding {
-                        id: format!("f-query-{}", results.len() + 1),
-                        rule_id: self.id().to_string(),
-                        severity: Severity::Critical,
-                        target: truncate_fingerprint(&query.query_fingerprint),
-                        message: format!(
-                            "Query has {:.0}x read amplification (critical threshold: {:.0}x)",
-                            amp, READ_AMP_CRITICAL
-                        ),
-                        evidence_refs: vec![],
-                        confidence: 1.0,
-                    },
-                    actions: vec![Action {
-                        id: format!("a-query-{}", results.len() + 1),
-                        finding_ref: format!("f-query-{}", results.len() + 1),
-                        action_type: ActionType::Recommendation,
-                        priority: Priority::High,
-                        description: "Review query, add PREWHERE or adjust ORDER BY".to_string(),
-                        sql: None,
-                    }],
-                });
-            } else if amp > READ_AMP_WARNING {
-                results.push(RuleResult {
-                    finding: Finding {
-                        id: format!("f-query-{}", results.len() + 1),
-                        rule_id: self.id().to_string(),
-                        severity: Severity::Warning,
-                        target: truncate_fingerprint(&query.query_fingerprint),
-                        message: format!(
-                            "Query has {:.0}x read amplification (warning threshold: {:.0}x)",
-                            amp, READ_AMP_WARNING
-                        ),
-                        evidence_refs: vec![],
-                        confidence: 1.0,
-                    },
-                    actions: vec![Action {
-                        id: format!("a-query-{}", results.len() + 1),
-                        finding_ref: format!("f-query-{}", results.len() + 1),
-                        action_type: ActionType::Recommendation,
-                        priority: Priority::Medium,
-                        description: "Consider optimizing query pattern".to_string(),
-                        sql: None,
-                    }],
-                });
+            let fingerprint = &query.query_fingerprint;
+            if !self.config.enabled_for(fingerprint) {
+                continue;
+            }
+
+            // Marks selected against the table size measure the scan directly;
+            // read/result rows is the fallback and penalises aggregations
+            let verdict = match query.marks_scanned_ratio {
+                Some(ratio) => self.scan_verdict(query, ratio),
+                None => self.amplification_verdict(query),
+            };
+            let Some((severity, message)) = verdict else {
+                continue;
+            };
+
+            // Hash the full fingerprint: the displayed target is truncated
+            let id = finding_id(self.id(), fingerprint, &severity);
+            let (priority, description) = match (&severity, query.marks_scanned_ratio) {
+                (Severity::Critical, Some(_)) => (
+                    Priority::High,
+                    "Filter on a prefix of the sorting key, or add a skip index or projection, so the query selects fewer granules",
+                ),
+                (_, Some(_)) => (
+                    Priority::Medium,
+                    "Check with EXPLAIN indexes = 1 whether the primary key or skip indexes prune granules for this query",
+                ),
+                (Severity::Critical, None) => {
+                    (Priority::High, "Review query, add PREWHERE or adjust ORDER BY")
+                }
+                (_, None) => (Priority::Medium, "Consider optimizing query pattern"),
+            };
+            results.push(RuleResult {
+                finding: Finding {
+                    id: id.clone(),
+                    rule_id: self.id().to_string(),
+                    severity,
+                    target: truncate_fingerprint(&query.query_fingerprint),
+                    message,
+                    evidence_refs: ctx.evidence_refs("system.query_log"),
+                    confidence: 1.0,
+                },
+                actions: vec![Action {
+                    id: action_id(&id, 0),
+                    finding_ref: id,
+                    action_type: ActionType::Recommendation,
+                    priority,
+                    description: description.to_string(),
+                    sql: query
+                        .marks_scanned_ratio
+                        .and(query.sample_query.as_ref())
+                        .map(|q| format!("EXPLAIN indexes = 1 {}", q)),
+                    destructive: false,
+                }],
+            });
+        }
+
+        results
+    }
+}
+
+impl QueryAmplificationRule {
+    /// Severity from the fraction of table marks read, weighted by table size
+    fn scan_verdict(&self, query: &QueryMetrics, ratio: f64) -> Option<(Severity, String)> {
+        let fingerprint = &query.query_fingerprint;
+        let warning_ratio =
+            self.config
+                .threshold(fingerprint, "warning_scan_ratio", SCAN_RATIO_WARNING);
+        let critical_ratio =
+            self.config
+                .threshold(fingerprint, "critical_scan_ratio", SCAN_RATIO_CRITICAL);
+        let min_bytes = self
+            .config
+            .threshold(fingerprint, "min_scan_bytes", SCAN_MIN_BYTES);
+        let critical_bytes =
+            self.config
+                .threshold(fingerprint, "critical_scan_bytes", SCAN_CRITICAL_BYTES);
+
+        let bytes = query.table_bytes as f64;
+        let severity = if ratio >= critical_ratio && bytes >= critical_bytes {
+            Severity::Critical
+        } else if ratio >= warning_ratio && bytes >= min_bytes {
+            Severity::Warning
+        } else {
+            return None;
+        };
+
+        Some((
+            severity,
+            format!(
+                "Query reads {:.0}% of marks ({:.0} of {} in {:.0} parts, {:.0} ranges per run) of {} ({:.1}GB)",
+                ratio * 100.0,
+                query.avg_selected_marks,
+                query.table_marks,
+                query.avg_selected_parts,
+                query.avg_selected_ranges,
+                query.tables.join(", "),
+                bytes / GIB
+            ),
+        ))
+    }
+
+    /// Severity from read rows per result row
+    fn amplification_verdict(&self, query: &QueryMetrics) -> Option<(Severity, String)> {
+        let fingerprint = &query.query_fingerprint;
+        let amp = query.read_amplification;
+        let warning = self
+            .config
+            .threshold(fingerprint, "warning", READ_AMP_WARNING);
+        let critical = self
+            .config
+            .threshold(fingerprint, "critical", READ_AMP_CRITICAL);
+
+        if amp > critical {
+            Some((
+                Severity::Critical,
+                format!(
+                    "Query has {:.0}x read amplification (critical threshold: {:.0}x)",
+                    amp, critical
+                ),
+            ))
+        } else if amp > warning {
+            Some((
+                Severity::Warning,
+                format!(
+                    "Query has {:.0}x read amplification (warning threshold: {:.0}x)",
+                    amp, warning
+                ),
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+impl Rule for LatencyRegressionRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Query Latency Regression"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.query_log"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &[
+            "regression_factor",
+            "critical_factor",
+            "min_executions",
+            "min_p95_ms",
+        ]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for query in &ctx.queries {
+            let fingerprint = &query.query_fingerprint;
+            if !self.config.enabled_for(fingerprint) {
+                continue;
             }
+            let Some(previous_p95) = query.previous_p95_duration_ms else {
+                continue;
+            };
+
+            let warning =
+                self.config
+                    .threshold(fingerprint, "regression_factor", REGRESSION_WARNING);
+            let critical =
+                self.config
+                    .threshold(fingerprint, "critical_factor", REGRESSION_CRITICAL);
+            let min_executions =
+                self.config
+                    .threshold(fingerprint, "min_executions", REGRESSION_MIN_EXECUTIONS)
+                    as u64;
+            let min_p95_ms =
+                self.config
+                    .threshold(fingerprint, "min_p95_ms", REGRESSION_MIN_P95_MS);
+            if query.execution_count < min_executions
+                || query.previous_execution_count < min_executions
+                || query.p95_duration_ms < min_p95_ms
+            {
+                continue;
+            }
+
+            let factor = query.p95_duration_ms / previous_p95.max(1.0);
+            let severity = if factor >= critical {
+                Severity::Critical
+            } else if factor >= warning {
+                Severity::Warning
+            } else {
+                continue;
+            };
+
+            let id = finding_id(self.id(), fingerprint, &severity);
+            results.push(RuleResult {
+                finding: Finding {
+                    id: id.clone(),
+                    rule_id: self.id().to_string(),
+                    severity: severity.clone(),
+                    target: truncate_fingerprint(fingerprint),
+                    message: format!(
+                        "p95 duration rose {:.1}x from {:.0}ms to {:.0}ms over the last {} ({} runs, {} in the {} before)",
+                        factor,
+                        previous_p95,
+                        query.p95_duration_ms,
+                        format_window(query.window_sec),
+                        query.execution_count,
+                        query.previous_execution_count,
+                        format_window(query.window_sec)
+                    ),
+                    evidence_refs: ctx.evidence_refs("system.query_log"),
+                    confidence: 1.0,
+                },
+                actions: vec![Action {
+                    id: action_id(&id, 0),
+                    finding_ref: id,
+                    action_type: ActionType::Recommendation,
+                    priority: if severity == Severity::Critical {
+                        Priority::High
+                    } else {
+                        Priority::Medium
+                    },
+                    description: "Check what changed for this query: table growth, part count, dropped projections or skip indexes, or a new filter the sorting key does not cover".to_string(),
+                    sql: query
+                        .sample_query
+                        .as_ref()
+                        .map(|q| format!("EXPLAIN indexes = 1 {}", q)),
+                    destructive: false,
+                }],
+            });
         }
 
         results
     }
 }
 
+/// Window length in the largest whole unit, e.g. 7d or 6h
+pub(super) fn format_window(sec: u64) -> String {
+    match sec {
+        s if s > 0 && s % 86400 == 0 => format!("{}d", s / 86400),
+        s if s > 0 && s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s > 0 && s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
 fn truncate_fingerprint(fp: &str) -> String {
-    if fp.len() > 80 {
-        format!("{}...", &fp[..77])
+    if fp.chars().count() > 80 {
+        format!("{}...", fp.chars().take(77).collect::<String>())
     } else {
         fp.to_string()
     }
@@ -86,7 +345,6 @@ fn truncate_fingerprint(fp: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::report::QueryMetrics;
 
     fn ctx_with_query_amp(amp: f64) -> AuditContext {
         let mut ctx = AuditContext::new();
@@ -100,7 +358,7 @@ mod tests {
 
     #[test]
     fn test_query_amp_healthy() {
-        let rule = QueryAmplificationRule;
+        let rule = QueryAmplificationRule::default();
         let ctx = ctx_with_query_amp(50.0);
         let results = rule.evaluate(&ctx);
         assert!(results.is_empty());
@@ -108,7 +366,7 @@ mod tests {
 
     #[test]
     fn test_query_amp_warning() {
-        let rule = QueryAmplificationRule;
+        let rule = QueryAmplificationRule::default();
         let ctx = ctx_with_query_amp(500.0);
         let results = rule.evaluate(&ctx);
         assert_eq!(results.len(), 1);
@@ -117,7 +375,7 @@ mod tests {
 
     #[test]
     fn test_query_amp_critical() {
-        let rule = QueryAmplificationRule;
+        let rule = QueryAmplificationRule::default();
         let ctx = ctx_with_query_amp(2000.0);
         let results = rule.evaluate(&ctx);
         assert_eq!(results.len(), 1);
@@ -126,7 +384,7 @@ mod tests {
 
     #[test]
     fn test_query_amp_multiple() {
-        let rule = QueryAmplificationRule;
+        let rule = QueryAmplificationRule::default();
         let mut ctx = AuditContext::new();
         ctx.set_queries(vec![
             QueryMetrics {
@@ -148,4 +406,126 @@ mod tests {
         let results = rule.evaluate(&ctx);
         assert_eq!(results.len(), 2); // warning + critical, not healthy
     }
+
+    fn ctx_with_scan(ratio: f64, table_gib: u64) -> AuditContext {
+        let mut ctx = AuditContext::new();
+        ctx.set_queries(vec![QueryMetrics {
+            query_fingerprint: "SELECT count() FROM events WHERE status = ?".to_string(),
+            // One row returned: a high ratio the aggregation cannot avoid
+            read_amplification: 1_000_000.0,
+            avg_selected_marks: ratio * 10_000.0,
+            avg_selected_parts: 12.0,
+            avg_selected_ranges: 12.0,
+            tables: vec!["testdb.events".to_string()],
+            table_marks: 10_000,
+            table_bytes: table_gib * GIB as u64,
+            marks_scanned_ratio: Some(ratio),
+            sample_query: Some("SELECT count() FROM events WHERE status = 1".to_string()),
+            ..Default::default()
+        }]);
+        ctx
+    }
+
+    #[test]
+    fn test_scan_ratio_replaces_amplification() {
+        let rule = QueryAmplificationRule::default();
+        // Reads few granules: not flagged despite the read/result ratio
+        assert!(rule.evaluate(&ctx_with_scan(0.05, 500)).is_empty());
+    }
+
+    #[test]
+    fn test_full_scan_of_large_table_is_critical() {
+        let rule = QueryAmplificationRule::default();
+        let results = rule.evaluate(&ctx_with_scan(1.0, 500));
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+        assert_eq!(
+            results[0].finding.message,
+            "Query reads 100% of marks (10000 of 10000 in 12 parts, 12 ranges per run) of testdb.events (500.0GB)"
+        );
+        assert_eq!(
+            results[0].actions[0].sql.as_deref(),
+            Some("EXPLAIN indexes = 1 SELECT count() FROM events WHERE status = 1")
+        );
+    }
+
+    #[test]
+    fn test_full_scan_severity_depends_on_table_size() {
+        let rule = QueryAmplificationRule::default();
+        let results = rule.evaluate(&ctx_with_scan(1.0, 10));
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+
+        // Small tables are cheap to scan
+        let mut ctx = ctx_with_scan(1.0, 0);
+        ctx.queries[0].table_bytes = 100 * 1024 * 1024;
+        assert!(rule.evaluate(&ctx).is_empty());
+    }
+
+    fn ctx_with_p95(p95: f64, previous_p95: Option<f64>) -> AuditContext {
+        let mut ctx = AuditContext::new();
+        ctx.set_queries(vec![QueryMetrics {
+            query_fingerprint: "SELECT * FROM events WHERE user_id = ?".to_string(),
+            execution_count: 50,
+            window_sec: 7 * 86400,
+            p95_duration_ms: p95,
+            previous_execution_count: 40,
+            previous_p95_duration_ms: previous_p95,
+            sample_query: Some("SELECT * FROM events WHERE user_id = 1".to_string()),
+            ..Default::default()
+        }]);
+        ctx
+    }
+
+    #[test]
+    fn test_latency_regression_warning() {
+        let rule = LatencyRegressionRule::default();
+        let results = rule.evaluate(&ctx_with_p95(900.0, Some(300.0)));
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+        assert_eq!(
+            results[0].finding.message,
+            "p95 duration rose 3.0x from 300ms to 900ms over the last 7d (50 runs, 40 in the 7d before)"
+        );
+        assert_eq!(
+            results[0].actions[0].sql.as_deref(),
+            Some("EXPLAIN indexes = 1 SELECT * FROM events WHERE user_id = 1")
+        );
+    }
+
+    #[test]
+    fn test_latency_regression_critical() {
+        let rule = LatencyRegressionRule::default();
+        let results = rule.evaluate(&ctx_with_p95(3000.0, Some(300.0)));
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+    }
+
+    #[test]
+    fn test_latency_regression_ignored() {
+        let rule = LatencyRegressionRule::default();
+        // Stable, new, or too fast to matter
+        assert!(rule.evaluate(&ctx_with_p95(400.0, Some(300.0))).is_empty());
+        assert!(rule.evaluate(&ctx_with_p95(900.0, None)).is_empty());
+        assert!(rule.evaluate(&ctx_with_p95(50.0, Some(10.0))).is_empty());
+
+        let mut ctx = ctx_with_p95(900.0, Some(300.0));
+        ctx.queries[0].previous_execution_count = 2;
+        assert!(rule.evaluate(&ctx).is_empty());
+    }
+
+    #[test]
+    fn test_format_window() {
+        assert_eq!(format_window(7 * 86400), "7d");
+        assert_eq!(format_window(6 * 3600), "6h");
+        assert_eq!(format_window(90 * 60), "90m");
+        assert_eq!(format_window(45), "45s");
+    }
+
+    #[test]
+    fn test_truncate_fingerprint_non_ascii() {
+        let fp = format!("SELECT * FROM t WHERE city = '{}'", "Москва".repeat(20));
+        let short = truncate_fingerprint(&fp);
+        assert_eq!(short.chars().count(), 80);
+        assert!(short.ends_with("..."));
+        assert_eq!(truncate_fingerprint("SELECT '東京'"), "SELECT '東京'");
+    }
 }
@@ -0,0 +1,467 @@
+use super::{action_id, finding_id, AuditContext, Rule, RuleResult};
+use crate::ch::sql::{literal, table_name};
+use crate::config::RuleConfig;
+use crate::report::{Action, ActionType, Finding, Priority, ReplicationMetrics, Severity};
+
+const LAG_WARNING_SEC: u64 = 300; // 5 minutes
+const LAG_CRITICAL_SEC: u64 = 3600; // 1 hour
+const QUEUE_STUCK_SEC: u64 = 3600; // 1 hour
+const QUEUE_MAX_TRIES: u64 = 10;
+
+/// Rule to detect replicas lagging behind the rest of the cluster
+#[derive(Default)]
+pub struct ReplicationLagRule {
+    config: RuleConfig,
+}
+
+/// Rule to detect readonly replicas and expired Keeper sessions
+#[derive(Default)]
+pub struct ReadonlyReplicaRule {
+    config: RuleConfig,
+}
+
+/// Rule to detect replication queue entries that are not making progress
+#[derive(Default)]
+pub struct StuckReplicationQueueRule {
+    config: RuleConfig,
+}
+
+impl ReplicationLagRule {
+    pub const ID: &'static str = "replication_lag";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl ReadonlyReplicaRule {
+    pub const ID: &'static str = "replica_readonly";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl StuckReplicationQueueRule {
+    pub const ID: &'static str = "replication_queue_stuck";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Rule for ReplicationLagRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Replication Lag"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.replicas"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["warning_sec", "critical_sec"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for (table_key, metrics) in &ctx.replication {
+            if !self.config.enabled_for(table_key) {
+                continue;
+            }
+
+            let warning = self
+                .config
+                .threshold(table_key, "warning_sec", LAG_WARNING_SEC as f64)
+                as u64;
+            let critical = self
+                .config
+                .threshold(table_key, "critical_sec", LAG_CRITICAL_SEC as f64)
+                as u64;
+
+            let (severity, threshold, priority) = if metrics.absolute_delay > critical {
+                (Severity::Critical, critical, Priority::High)
+            } else if metrics.absolute_delay > warning {
+                (Severity::Warning, warning, Priority::Medium)
+            } else {
+                continue;
+            };
+
+            let id = finding_id(self.id(), table_key, &severity);
+            results.push(RuleResult {
+                finding: Finding {
+                    id: id.clone(),
+                    rule_id: self.id().to_string(),
+                    severity,
+                    target: table_key.clone(),
+                    message: format!(
+                        "Replica {} is {}s behind (threshold: {}s), {} entries in queue ({} inserts, {} merges)",
+                        metrics.replica_name,
+                        metrics.absolute_delay,
+                        threshold,
+                        metrics.queue_size,
+                        metrics.inserts_in_queue,
+                        metrics.merges_in_queue
+                    ),
+                    evidence_refs: ctx.evidence_refs("system.replicas"),
+                    confidence: 1.0,
+                },
+                actions: vec![Action {
+                    id: action_id(&id, 0),
+                    finding_ref: id,
+                    action_type: ActionType::Recommendation,
+                    priority,
+                    description: "Inspect the replication queue for entries that keep failing"
+                        .to_string(),
+                    sql: Some(queue_sql(metrics)),
+                    destructive: false,
+                }],
+            });
+        }
+
+        results
+    }
+}
+
+impl Rule for ReadonlyReplicaRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Readonly Replica"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.replicas"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for (table_key, metrics) in &ctx.replication {
+            if !self.config.enabled_for(table_key) {
+                continue;
+            }
+
+            let message = match (metrics.is_readonly, metrics.is_session_expired) {
+                (_, true) => format!(
+                    "Replica {} lost its Keeper session and cannot accept inserts",
+                    metrics.replica_name
+                ),
+                (true, false) => format!(
+                    "Replica {} is read-only and cannot accept inserts",
+                    metrics.replica_name
+                ),
+                (false, false) => continue,
+            };
+
+            let id = finding_id(self.id(), table_key, &Severity::Critical);
+            results.push(RuleResult {
+                finding: Finding {
+                    id: id.clone(),
+                    rule_id: self.id().to_string(),
+                    severity: Severity::Critical,
+                    target: table_key.clone(),
+                    message,
+                    evidence_refs: ctx.evidence_refs("system.replicas"),
+                    confidence: 1.0,
+                },
+                actions: vec![Action {
+                    id: action_id(&id, 0),
+                    finding_ref: id,
+                    action_type: ActionType::Recommendation,
+                    priority: Priority::High,
+                    description:
+                        "Check ZooKeeper/Keeper connectivity, then reinitialize the replica session"
+                            .to_string(),
+                    sql: Some(restart_replica_sql(metrics)),
+                    destructive: false,
+                }],
+            });
+        }
+
+        results
+    }
+}
+
+impl Rule for StuckReplicationQueueRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Stuck Replication Queue"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["system.replicas"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["stuck_sec", "max_tries"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for (table_key, metrics) in &ctx.replication {
+            if !self.config.enabled_for(table_key) {
+                continue;
+            }
+            let Some(entry) = &metrics.oldest_queue_entry else {
+                continue;
+            };
+
+            let stuck_sec = self
+                .config
+                .threshold(table_key, "stuck_sec", QUEUE_STUCK_SEC as f64)
+                as u64;
+            let max_tries = self
+                .config
+                .threshold(table_key, "max_tries", QUEUE_MAX_TRIES as f64)
+                as u64;
+
+            if entry.age_sec <= stuck_sec {
+                continue;
+            }
+
+            // A younger entry can be the one failing over and over
+            let retried = metrics
+                .most_retried_queue_entry
+                .as_ref()
+                .filter(|r| r.num_tries > entry.num_tries);
+            let worst = retried.unwrap_or(entry);
+            let severity = if worst.num_tries > max_tries {
+                Severity::Critical
+            } else {
+                Severity::Warning
+            };
+            let mut message = format!(
+                "Oldest queue entry ({}) is {:.1} hours old after {} tries",
+                entry.entry_type,
+                entry.age_sec as f64 / 3600.0,
+                entry.num_tries
+            );
+            if let Some(retried) = retried {
+                message.push_str(&format!(
+                    "; a {} entry from {:.1} hours ago has {} tries",
+                    retried.entry_type,
+                    retried.age_sec as f64 / 3600.0,
+                    retried.num_tries
+                ));
+            }
+            if let Some(exception) = &worst.last_exception {
+                message.push_str(&format!(": {}", first_line(exception)));
+            }
+
+            let id = finding_id(self.id(), table_key, &severity);
+            results.push(RuleResult {
+                finding: Finding {
+                    id: id.clone(),
+                    rule_id: self.id().to_string(),
+                    severity,
+                    target: table_key.clone(),
+                    message,
+                    evidence_refs: ctx.evidence_refs("system.replicas"),
+                    confidence: 1.0,
+                },
+                actions: vec![
+                    Action {
+                        id: action_id(&id, 0),
+                        finding_ref: id.clone(),
+                        action_type: ActionType::Recommendation,
+                        priority: Priority::High,
+                        description: "Inspect the failing queue entries and their exceptions"
+                            .to_string(),
+                        sql: Some(queue_sql(metrics)),
+                        destructive: false,
+                    },
+                    Action {
+                        id: action_id(&id, 1),
+                        finding_ref: id,
+                        action_type: ActionType::Recommendation,
+                        priority: Priority::Medium,
+                        description: "Restart the replica to reload its queue from Keeper"
+                            .to_string(),
+                        sql: Some(restart_replica_sql(metrics)),
+                        destructive: false,
+                    },
+                ],
+            });
+        }
+
+        results
+    }
+}
+
+fn queue_sql(metrics: &ReplicationMetrics) -> String {
+    format!(
+        "SELECT type, create_time, num_tries, last_exception, postpone_reason FROM system.replication_queue WHERE database = {} AND table = {} ORDER BY create_time LIMIT 20",
+        literal(&metrics.database),
+        literal(&metrics.table)
+    )
+}
+
+fn restart_replica_sql(metrics: &ReplicationMetrics) -> String {
+    format!(
+        "SYSTEM RESTART REPLICA {}",
+        table_name(&metrics.database, &metrics.table)
+    )
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::ReplicationQueueEntry;
+
+    fn ctx_with_replica(metrics: ReplicationMetrics) -> AuditContext {
+        let mut ctx = AuditContext::new();
+        ctx.add_replication(ReplicationMetrics {
+            database: "testdb".to_string(),
+            table: "events".to_string(),
+            replica_name: "replica_1".to_string(),
+            ..metrics
+        });
+        ctx
+    }
+
+    fn queue_entry(age_sec: u64, num_tries: u64) -> Option<ReplicationQueueEntry> {
+        Some(ReplicationQueueEntry {
+            entry_type: "GET_PART".to_string(),
+            age_sec,
+            num_tries,
+            last_exception: Some("Code: 234. No active replica has part\nStack trace".to_string()),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_lag_healthy() {
+        let ctx = ctx_with_replica(ReplicationMetrics {
+            absolute_delay: 60,
+            ..Default::default()
+        });
+        assert!(ReplicationLagRule::default().evaluate(&ctx).is_empty());
+    }
+
+    #[test]
+    fn test_lag_warning_and_critical() {
+        let ctx = ctx_with_replica(ReplicationMetrics {
+            absolute_delay: 600,
+            ..Default::default()
+        });
+        let results = ReplicationLagRule::default().evaluate(&ctx);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+        assert!(results[0].actions[0]
+            .sql
+            .as_ref()
+            .unwrap()
+            .contains("system.replication_queue WHERE database = 'testdb' AND table = 'events'"));
+
+        let ctx = ctx_with_replica(ReplicationMetrics {
+            absolute_delay: 7200,
+            ..Default::default()
+        });
+        let results = ReplicationLagRule::default().evaluate(&ctx);
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+    }
+
+    #[test]
+    fn test_readonly_replica() {
+        let ctx = ctx_with_replica(ReplicationMetrics {
+            is_readonly: true,
+            ..Default::default()
+        });
+        let results = ReadonlyReplicaRule::default().evaluate(&ctx);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+        assert_eq!(
+            results[0].actions[0].sql.as_deref(),
+            Some("SYSTEM RESTART REPLICA testdb.events")
+        );
+    }
+
+    #[test]
+    fn test_session_expired_replica() {
+        let ctx = ctx_with_replica(ReplicationMetrics {
+            is_readonly: true,
+            is_session_expired: true,
+            ..Default::default()
+        });
+        let results = ReadonlyReplicaRule::default().evaluate(&ctx);
+        assert!(results[0].finding.message.contains("Keeper session"));
+
+        let ctx = ctx_with_replica(ReplicationMetrics::default());
+        assert!(ReadonlyReplicaRule::default().evaluate(&ctx).is_empty());
+    }
+
+    #[test]
+    fn test_queue_entry_recent() {
+        let ctx = ctx_with_replica(ReplicationMetrics {
+            oldest_queue_entry: queue_entry(600, 50),
+            ..Default::default()
+        });
+        assert!(StuckReplicationQueueRule::default()
+            .evaluate(&ctx)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_queue_entry_stuck() {
+        let ctx = ctx_with_replica(ReplicationMetrics {
+            oldest_queue_entry: queue_entry(7200, 3),
+            ..Default::default()
+        });
+        let results = StuckReplicationQueueRule::default().evaluate(&ctx);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+        assert!(results[0]
+            .finding
+            .message
+            .ends_with("3 tries: Code: 234. No active replica has part"));
+        assert_eq!(results[0].actions.len(), 2);
+        assert_eq!(
+            results[0].actions[1].sql.as_deref(),
+            Some("SYSTEM RESTART REPLICA testdb.events")
+        );
+    }
+
+    #[test]
+    fn test_queue_entry_stuck_with_many_tries_is_critical() {
+        let ctx = ctx_with_replica(ReplicationMetrics {
+            oldest_queue_entry: queue_entry(7200, 500),
+            ..Default::default()
+        });
+        let results = StuckReplicationQueueRule::default().evaluate(&ctx);
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+    }
+
+    #[test]
+    fn test_younger_entry_with_many_tries_is_critical() {
+        let mut retried = queue_entry(1800, 5000).unwrap();
+        retried.entry_type = "MERGE_PARTS".to_string();
+        retried.last_exception = Some("Code: 49. Part is covered".to_string());
+        let ctx = ctx_with_replica(ReplicationMetrics {
+            oldest_queue_entry: queue_entry(7200, 3),
+            most_retried_queue_entry: Some(retried),
+            ..Default::default()
+        });
+        let results = StuckReplicationQueueRule::default().evaluate(&ctx);
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+        assert_eq!(
+            results[0].finding.message,
+            "Oldest queue entry (GET_PART) is 2.0 hours old after 3 tries; a MERGE_PARTS entry from 0.5 hours ago has 5000 tries: Code: 49. Part is covered"
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use super::{action_id, finding_id, AuditContext, Rule, RuleResult};
+use crate::config::RuleConfig;
+use crate::report::{Action, ActionType, Finding, PoolMetrics, Priority, Severity};
+
+const GIB: f64 = 1_073_741_824.0;
+
+/// Default fractions of a background pool in use
+const POOL_WARNING_RATIO: f64 = 0.8;
+const POOL_CRITICAL_RATIO: f64 = 1.0;
+
+/// Default fractions of max_server_memory_usage still free
+const MEMORY_WARNING_HEADROOM: f64 = 0.2;
+const MEMORY_CRITICAL_HEADROOM: f64 = 0.1;
+
+/// Server setting that sizes each pool
+const POOL_SETTINGS: &[(&str, &str)] = &[
+    (
+        "merges_mutations",
+        "background_pool_size (or background_merges_mutations_concurrency_ratio)",
+    ),
+    ("fetches", "background_fetches_pool_size"),
+    ("moves", "background_move_pool_size"),
+];
+
+/// Rule to detect background pools with few or no free slots
+#[derive(Default)]
+pub struct BackgroundPoolRule {
+    config: RuleConfig,
+}
+
+/// Rule to detect memory tracked close to max_server_memory_usage
+#[derive(Default)]
+pub struct MemoryHeadroomRule {
+    config: RuleConfig,
+}
+
+impl BackgroundPoolRule {
+    pub const ID: &'static str = "background_pool_saturated";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl MemoryHeadroomRule {
+    pub const ID: &'static str = "memory_headroom";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Rule for BackgroundPoolRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Background Pool Saturated"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["server"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["warning_ratio", "critical_ratio"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+        let Some(server) = &ctx.server else {
+            return results;
+        };
+
+        for pool in &server.pools {
+            let target = format!("{}_pool", pool.name);
+            if !self.config.enabled_for(&target) || pool.size == 0 {
+                continue;
+            }
+
+            let warning = self
+                .config
+                .threshold(&target, "warning_ratio", POOL_WARNING_RATIO);
+            let critical = self
+                .config
+                .threshold(&target, "critical_ratio", POOL_CRITICAL_RATIO);
+            let used = usage(pool);
+            let severity = if used >= critical {
+                Severity::Critical
+            } else if used >= warning {
+                Severity::Warning
+            } else {
+                continue;
+            };
+
+            let setting = POOL_SETTINGS
+                .iter()
+                .find(|(name, _)| *name == pool.name)
+                .map_or("the pool size", |(_, setting)| setting);
+            let id = finding_id(self.id(), &target, &severity);
+            results.push(RuleResult {
+                finding: Finding {
+                    id: id.clone(),
+                    rule_id: self.id().to_string(),
+                    severity,
+                    target,
+                    message: format!(
+                        "Background {} pool is {:.0}% busy ({}/{} tasks)",
+                        pool.name.replace('_', "/"),
+                        used * 100.0,
+                        pool.tasks,
+                        pool.size
+                    ),
+                    evidence_refs: ctx.evidence_refs("server"),
+                    confidence: 1.0,
+                },
+                actions: vec![Action {
+                    id: action_id(&id, 0),
+                    finding_ref: id,
+                    action_type: ActionType::Recommendation,
+                    priority: Priority::High,
+                    description: format!(
+                        "Raise {} in the server config if CPU and disk have headroom; otherwise reduce the work queued (fewer, larger inserts; fewer mutations)",
+                        setting
+                    ),
+                    sql: None,
+                    destructive: false,
+                }],
+            });
+        }
+
+        results
+    }
+}
+
+impl Rule for MemoryHeadroomRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Server Memory Headroom"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["server"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["warning_headroom", "critical_headroom"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+        let Some(server) = &ctx.server else {
+            return results;
+        };
+        let target = "server".to_string();
+        if !self.config.enabled_for(&target) || server.memory_limit == 0 {
+            return results;
+        }
+
+        let warning = self
+            .config
+            .threshold(&target, "warning_headroom", MEMORY_WARNING_HEADROOM);
+        let critical =
+            self.config
+                .threshold(&target, "critical_headroom", MEMORY_CRITICAL_HEADROOM);
+        let headroom = 1.0 - server.memory_tracked as f64 / server.memory_limit as f64;
+        let severity = if headroom < critical {
+            Severity::Critical
+        } else if headroom < warning {
+            Severity::Warning
+        } else {
+            return results;
+        };
+
+        let id = finding_id(self.id(), &target, &severity);
+        results.push(RuleResult {
+            finding: Finding {
+                id: id.clone(),
+                rule_id: self.id().to_string(),
+                severity,
+                target,
+                message: format!(
+                    "Server tracks {:.1}GB of {:.1}GB max_server_memory_usage ({:.0}% headroom): queries and merges will fail with MEMORY_LIMIT_EXCEEDED",
+                    server.memory_tracked as f64 / GIB,
+                    server.memory_limit as f64 / GIB,
+                    headroom.max(0.0) * 100.0
+                ),
+                evidence_refs: ctx.evidence_refs("server"),
+                confidence: 1.0,
+            },
+            actions: vec![Action {
+                id: action_id(&id, 0),
+                finding_ref: id,
+                action_type: ActionType::Recommendation,
+                priority: Priority::High,
+                description: "Find the queries holding the most memory; limit them with max_memory_usage or spill with max_bytes_before_external_group_by".to_string(),
+                sql: Some(
+                    "SELECT query_id, user, formatReadableSize(memory_usage) AS memory, elapsed, query FROM system.processes ORDER BY memory_usage DESC LIMIT 10".to_string(),
+                ),
+                destructive: false,
+            }],
+        });
+
+        results
+    }
+}
+
+/// Fraction of the pool's slots in use
+fn usage(pool: &PoolMetrics) -> f64 {
+    if pool.size == 0 {
+        0.0
+    } else {
+        pool.tasks as f64 / pool.size as f64
+    }
+}
+
+/// The merge/mutation pool, if it is saturated by the default threshold
+pub(super) fn saturated_merge_pool(ctx: &AuditContext) -> Option<&PoolMetrics> {
+    ctx.server
+        .as_ref()?
+        .pools
+        .iter()
+        .find(|p| p.name == "merges_mutations" && p.size > 0 && usage(p) >= POOL_WARNING_RATIO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::ServerSection;
+
+    fn ctx_with(tasks: u64, memory_tracked: u64) -> AuditContext {
+        let mut ctx = AuditContext::new();
+        ctx.set_server(ServerSection {
+            memory_tracked,
+            memory_limit: 100 * GIB as u64,
+            pools: vec![
+                PoolMetrics {
+                    name: "merges_mutations".to_string(),
+                    tasks,
+                    size: 32,
+                },
+                PoolMetrics {
+                    name: "moves".to_string(),
+                    tasks: 0,
+                    size: 0,
+                },
+            ],
+            ..Default::default()
+        });
+        ctx
+    }
+
+    #[test]
+    fn test_pool_saturation() {
+        let rule = BackgroundPoolRule::default();
+
+        assert!(rule.evaluate(&ctx_with(8, 0)).is_empty());
+
+        let results = rule.evaluate(&ctx_with(28, 0));
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+        assert_eq!(results[0].finding.target, "merges_mutations_pool");
+        assert_eq!(
+            results[0].finding.message,
+            "Background merges/mutations pool is 88% busy (28/32 tasks)"
+        );
+        assert!(results[0].actions[0]
+            .description
+            .starts_with("Raise background_pool_size"));
+
+        let results = rule.evaluate(&ctx_with(32, 0));
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+    }
+
+    #[test]
+    fn test_memory_headroom() {
+        let rule = MemoryHeadroomRule::default();
+
+        assert!(rule.evaluate(&ctx_with(0, 50 * GIB as u64)).is_empty());
+
+        let results = rule.evaluate(&ctx_with(0, 85 * GIB as u64));
+        assert_eq!(results[0].finding.severity, Severity::Warning);
+        assert!(results[0]
+            .finding
+            .message
+            .starts_with("Server tracks 85.0GB of 100.0GB max_server_memory_usage (15% headroom)"));
+
+        let results = rule.evaluate(&ctx_with(0, 95 * GIB as u64));
+        assert_eq!(results[0].finding.severity, Severity::Critical);
+    }
+
+    #[test]
+    fn test_memory_limit_unknown() {
+        let mut ctx = ctx_with(0, 95 * GIB as u64);
+        ctx.server.as_mut().unwrap().memory_limit = 0;
+        assert!(MemoryHeadroomRule::default().evaluate(&ctx).is_empty());
+    }
+
+    #[test]
+    fn test_saturated_merge_pool() {
+        assert!(saturated_merge_pool(&ctx_with(30, 0)).is_some());
+        assert!(saturated_merge_pool(&ctx_with(10, 0)).is_none());
+        assert!(saturated_merge_pool(&AuditContext::new()).is_none());
+    }
+}
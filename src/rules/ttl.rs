@@ -0,0 +1,506 @@
+use super::{action_id, finding_id, AuditContext, Rule, RuleResult};
+use crate::ch::sql::table_name;
+use crate::config::RuleConfig;
+use crate::report::{Action, ActionType, Finding, Priority, Severity, TtlMetrics};
+
+const GIB: f64 = 1_073_741_824.0;
+
+/// Defaults for tables that should have a TTL
+const MISSING_TTL_MIN_BYTES: f64 = 10.0 * GIB;
+const GROWTH_WINDOW_SEC: u64 = 86400; // 1 day
+
+/// Expired parts are normally cleaned up within merge_with_ttl_timeout (4h)
+const EXPIRED_GRACE_SEC: u64 = 86400; // 1 day
+
+/// Keywords that can appear in a TTL clause but are not column names
+const TTL_KEYWORDS: &[&str] = &[
+    "INTERVAL", "DELETE", "SECOND", "MINUTE", "HOUR", "DAY", "WEEK", "MONTH", "QUARTER", "YEAR",
+];
+
+/// Rule to detect large tables still receiving data that have no TTL
+#[derive(Default)]
+pub struct MissingTtlRule {
+    config: RuleConfig,
+}
+
+/// Rule to detect parts past their TTL that have not been dropped or moved
+#[derive(Default)]
+pub struct ExpiredTtlRule {
+    config: RuleConfig,
+}
+
+/// Rule to detect TTL deletes that rewrite parts where whole parts could be dropped
+#[derive(Default)]
+pub struct TtlOnlyDropPartsRule {
+    config: RuleConfig,
+}
+
+impl MissingTtlRule {
+    pub const ID: &'static str = "ttl_missing";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl ExpiredTtlRule {
+    pub const ID: &'static str = "ttl_expired_parts";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl TtlOnlyDropPartsRule {
+    pub const ID: &'static str = "ttl_only_drop_parts";
+
+    pub fn new(config: RuleConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Rule for MissingTtlRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Growing Table Without TTL"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["ttl"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["min_bytes", "growth_window_sec"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for (table_key, ttl) in &ctx.ttl {
+            if !self.config.enabled_for(table_key) || ttl.ttl.is_some() {
+                continue;
+            }
+
+            let min_bytes = self
+                .config
+                .threshold(table_key, "min_bytes", MISSING_TTL_MIN_BYTES);
+            let window =
+                self.config
+                    .threshold(table_key, "growth_window_sec", GROWTH_WINDOW_SEC as f64)
+                    as u64;
+            let growing = ttl.newest_part_age_sec.is_some_and(|age| age <= window);
+            if !growing || (ttl.bytes_on_disk as f64) < min_bytes {
+                continue;
+            }
+
+            let id = finding_id(self.id(), table_key, &Severity::Warning);
+            results.push(RuleResult {
+                finding: Finding {
+                    id: id.clone(),
+                    rule_id: self.id().to_string(),
+                    severity: Severity::Warning,
+                    target: table_key.clone(),
+                    message: format!(
+                        "Table takes {:.1}GB, is still receiving data and has no TTL",
+                        ttl.bytes_on_disk as f64 / GIB
+                    ),
+                    evidence_refs: ctx.evidence_refs("ttl"),
+                    confidence: 1.0,
+                },
+                actions: vec![Action {
+                    id: action_id(&id, 0),
+                    finding_ref: id,
+                    action_type: ActionType::Recommendation,
+                    priority: Priority::Medium,
+                    description: format!(
+                        "Add a TTL matching the retention requirement (ALTER TABLE {} MODIFY TTL <date column> + INTERVAL <n> DAY)",
+                        table_name(&ttl.database, &ttl.table)
+                    ),
+                    sql: None,
+                    destructive: false,
+                }],
+            });
+        }
+
+        results
+    }
+}
+
+impl Rule for ExpiredTtlRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "Expired TTL Not Applied"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["ttl"]
+    }
+
+    fn thresholds(&self) -> &'static [&'static str] {
+        &["grace_sec"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for (table_key, ttl) in &ctx.ttl {
+            if !self.config.enabled_for(table_key) {
+                continue;
+            }
+
+            let grace = self
+                .config
+                .threshold(table_key, "grace_sec", EXPIRED_GRACE_SEC as f64)
+                as u64;
+
+            let mut problems = Vec::new();
+            if ttl.expired_parts > 0 && ttl.oldest_expired_age_sec > grace {
+                problems.push(format!(
+                    "{} parts ({:.1}GB) expired up to {}h ago are still on disk",
+                    ttl.expired_parts,
+                    ttl.expired_bytes as f64 / GIB,
+                    ttl.oldest_expired_age_sec / 3600
+                ));
+            }
+            if ttl.unmoved_parts > 0 && ttl.oldest_unmoved_age_sec > grace {
+                problems.push(format!(
+                    "{} parts ({:.1}GB) past a move TTL for up to {}h have not been moved",
+                    ttl.unmoved_parts,
+                    ttl.unmoved_bytes as f64 / GIB,
+                    ttl.oldest_unmoved_age_sec / 3600
+                ));
+            }
+            if problems.is_empty() {
+                continue;
+            }
+
+            let id = finding_id(self.id(), table_key, &Severity::Warning);
+            results.push(RuleResult {
+                finding: Finding {
+                    id: id.clone(),
+                    rule_id: self.id().to_string(),
+                    severity: Severity::Warning,
+                    target: table_key.clone(),
+                    message: problems.join("; "),
+                    evidence_refs: ctx.evidence_refs("ttl"),
+                    confidence: 1.0,
+                },
+                actions: vec![Action {
+                    id: action_id(&id, 0),
+                    finding_ref: id,
+                    action_type: ActionType::Recommendation,
+                    priority: Priority::Medium,
+                    description: "Apply the TTL now, and check merge_with_ttl_timeout and background pool saturation".to_string(),
+                    sql: Some(format!(
+                        "ALTER TABLE {} MATERIALIZE TTL",
+                        table_name(&ttl.database, &ttl.table)
+                    )),
+                    destructive: false,
+                }],
+            });
+        }
+
+        results
+    }
+}
+
+impl Rule for TtlOnlyDropPartsRule {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn name(&self) -> &'static str {
+        "TTL Rewrites Instead of Dropping Parts"
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["ttl"]
+    }
+
+    fn evaluate(&self, ctx: &AuditContext) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for (table_key, ttl) in &ctx.ttl {
+            if !self.config.enabled_for(table_key) || ttl.ttl_only_drop_parts {
+                continue;
+            }
+            let Some(clause) = &ttl.ttl else {
+                continue;
+            };
+            if !drops_by_partition(clause, &ttl.partition_key) {
+                continue;
+            }
+
+            let id = finding_id(self.id(), table_key, &Severity::Warning);
+            results.push(RuleResult {
+                finding: Finding {
+                    id: id.clone(),
+                    rule_id: self.id().to_string(),
+                    severity: Severity::Warning,
+                    target: table_key.clone(),
+                    message: format!(
+                        "TTL {} follows the partition key {}, but ttl_only_drop_parts is disabled so expired rows are removed by rewriting parts",
+                        clause, ttl.partition_key
+                    ),
+                    evidence_refs: ctx.evidence_refs("ttl"),
+                    confidence: 0.8,
+                },
+                actions: vec![Action {
+                    id: action_id(&id, 0),
+                    finding_ref: id,
+                    action_type: ActionType::DdlProposal,
+                    priority: Priority::Low,
+                    description: "Drop whole parts once all their rows expire".to_string(),
+                    sql: Some(format!(
+                        "ALTER TABLE {} MODIFY SETTING ttl_only_drop_parts = 1",
+                        table_name(&ttl.database, &ttl.table)
+                    )),
+                    destructive: false,
+                }],
+            });
+        }
+
+        results
+    }
+}
+
+/// Largest tables (by bytes) that have no TTL, for pointing disk findings at them
+pub(super) fn largest_without_ttl(ctx: &AuditContext, limit: usize) -> Vec<&TtlMetrics> {
+    let mut tables: Vec<&TtlMetrics> = ctx
+        .ttl
+        .values()
+        .filter(|t| t.ttl.is_none() && t.bytes_on_disk > 0)
+        .collect();
+    tables.sort_by_key(|t| std::cmp::Reverse(t.bytes_on_disk));
+    tables.truncate(limit);
+    tables
+}
+
+/// Whether a plain delete item of the TTL uses a column of the partition key,
+/// so whole partitions (and their parts) expire at once
+fn drops_by_partition(ttl: &str, partition_key: &str) -> bool {
+    let key_columns = columns(partition_key);
+    if key_columns.is_empty() {
+        return false;
+    }
+
+    split_items(ttl)
+        .into_iter()
+        .filter(|item| {
+            ![
+                " TO DISK ",
+                " TO VOLUME ",
+                " RECOMPRESS ",
+                " GROUP BY ",
+                " WHERE ",
+            ]
+            .iter()
+            .any(|clause| item.contains(clause))
+        })
+        .any(|item| columns(item).iter().any(|c| key_columns.contains(c)))
+}
+
+/// Split a TTL clause on top-level commas
+fn split_items(ttl: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    for (i, c) in ttl.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(&ttl[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&ttl[start..]);
+    items
+}
+
+/// Identifiers in an expression that are not function names or keywords
+fn columns(expr: &str) -> Vec<&str> {
+    let mut columns = Vec::new();
+    let mut rest = expr;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '`') {
+        rest = &rest[start..];
+        let (name, after) = if let Some(quoted) = rest.strip_prefix('`') {
+            let end = quoted.find('`').unwrap_or(quoted.len());
+            (&quoted[..end], &quoted[(end + 1).min(quoted.len())..])
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            (&rest[..end], &rest[end..])
+        };
+        let is_function = after.trim_start().starts_with('(');
+        if !is_function && !TTL_KEYWORDS.contains(&name) {
+            columns.push(name);
+        }
+        rest = after;
+    }
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx_with_ttl(ttl: TtlMetrics) -> AuditContext {
+        let mut ctx = AuditContext::new();
+        ctx.add_ttl(TtlMetrics {
+            database: "testdb".to_string(),
+            table: "events".to_string(),
+            ..ttl
+        });
+        ctx
+    }
+
+    #[test]
+    fn test_missing_ttl_on_growing_table() {
+        let ctx = ctx_with_ttl(TtlMetrics {
+            bytes_on_disk: 50 * GIB as u64,
+            newest_part_age_sec: Some(120),
+            ..Default::default()
+        });
+
+        let results = MissingTtlRule::default().evaluate(&ctx);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].finding.message.starts_with("Table takes 50.0GB"));
+    }
+
+    #[test]
+    fn test_missing_ttl_ignores_static_and_small_tables() {
+        let rule = MissingTtlRule::default();
+        let stale = ctx_with_ttl(TtlMetrics {
+            bytes_on_disk: 50 * GIB as u64,
+            newest_part_age_sec: Some(30 * 86400),
+            ..Default::default()
+        });
+        let small = ctx_with_ttl(TtlMetrics {
+            bytes_on_disk: 1000,
+            newest_part_age_sec: Some(120),
+            ..Default::default()
+        });
+        let with_ttl = ctx_with_ttl(TtlMetrics {
+            ttl: Some("ts + toIntervalDay(30)".to_string()),
+            bytes_on_disk: 50 * GIB as u64,
+            newest_part_age_sec: Some(120),
+            ..Default::default()
+        });
+
+        assert!(rule.evaluate(&stale).is_empty());
+        assert!(rule.evaluate(&small).is_empty());
+        assert!(rule.evaluate(&with_ttl).is_empty());
+    }
+
+    #[test]
+    fn test_expired_parts() {
+        let ctx = ctx_with_ttl(TtlMetrics {
+            expired_parts: 12,
+            expired_bytes: 2 * GIB as u64,
+            oldest_expired_age_sec: 3 * 86400,
+            unmoved_parts: 3,
+            unmoved_bytes: GIB as u64,
+            oldest_unmoved_age_sec: 2 * 86400,
+            ..Default::default()
+        });
+
+        let results = ExpiredTtlRule::default().evaluate(&ctx);
+        assert_eq!(
+            results[0].finding.message,
+            "12 parts (2.0GB) expired up to 72h ago are still on disk; 3 parts (1.0GB) past a move TTL for up to 48h have not been moved"
+        );
+        assert_eq!(
+            results[0].actions[0].sql.as_deref(),
+            Some("ALTER TABLE testdb.events MATERIALIZE TTL")
+        );
+    }
+
+    #[test]
+    fn test_recently_expired_parts_within_grace() {
+        let ctx = ctx_with_ttl(TtlMetrics {
+            expired_parts: 2,
+            oldest_expired_age_sec: 3600,
+            ..Default::default()
+        });
+        assert!(ExpiredTtlRule::default().evaluate(&ctx).is_empty());
+    }
+
+    #[test]
+    fn test_ttl_only_drop_parts() {
+        let ctx = ctx_with_ttl(TtlMetrics {
+            ttl: Some("ts + toIntervalDay(30)".to_string()),
+            partition_key: "toYYYYMM(ts)".to_string(),
+            ..Default::default()
+        });
+
+        let results = TtlOnlyDropPartsRule::default().evaluate(&ctx);
+        assert_eq!(results[0].actions[0].action_type, ActionType::DdlProposal);
+        assert_eq!(
+            results[0].actions[0].sql.as_deref(),
+            Some("ALTER TABLE testdb.events MODIFY SETTING ttl_only_drop_parts = 1")
+        );
+
+        let enabled = ctx_with_ttl(TtlMetrics {
+            ttl: Some("ts + toIntervalDay(30)".to_string()),
+            partition_key: "toYYYYMM(ts)".to_string(),
+            ttl_only_drop_parts: true,
+            ..Default::default()
+        });
+        assert!(TtlOnlyDropPartsRule::default()
+            .evaluate(&enabled)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_drops_by_partition() {
+        assert!(drops_by_partition("ts + toIntervalDay(30)", "toYYYYMM(ts)"));
+        assert!(drops_by_partition(
+            "`event time` + INTERVAL 1 MONTH DELETE",
+            "toStartOfMonth(`event time`)"
+        ));
+        // TTL on a column outside the partition key expires rows across all partitions
+        assert!(!drops_by_partition(
+            "created + toIntervalDay(30)",
+            "toYYYYMM(ts)"
+        ));
+        // Moves and conditional deletes are not whole-part drops
+        assert!(!drops_by_partition(
+            "ts + toIntervalDay(7) TO VOLUME 'cold'",
+            "toYYYYMM(ts)"
+        ));
+        assert!(!drops_by_partition(
+            "ts + toIntervalDay(7) WHERE level = 'debug'",
+            "toYYYYMM(ts)"
+        ));
+        assert!(!drops_by_partition("ts + toIntervalDay(30)", ""));
+    }
+
+    #[test]
+    fn test_largest_without_ttl() {
+        let mut ctx = AuditContext::new();
+        for (table, bytes, ttl) in [("a", 10, None), ("b", 30, None), ("c", 50, Some("x"))] {
+            ctx.add_ttl(TtlMetrics {
+                database: "db".to_string(),
+                table: table.to_string(),
+                bytes_on_disk: bytes,
+                ttl: ttl.map(str::to_string),
+                ..Default::default()
+            });
+        }
+
+        let tables: Vec<&str> = largest_without_ttl(&ctx, 5)
+            .iter()
+            .map(|t| t.table.as_str())
+            .collect();
+        assert_eq!(tables, vec!["b", "a"]);
+    }
+}
@@ -58,3 +58,8 @@ SELECT
     ['sale', 'refund', 'view'][(number % 3) + 1] AS event_type,
     (rand() % 10000) / 100.0 AS value
 FROM numbers(5000);
+
+-- Scenario 3: INSERT ... SELECT, attributed to the destination table only
+CREATE TABLE testdb.events_copy AS testdb.events;
+
+INSERT INTO testdb.events_copy SELECT * FROM testdb.events WHERE event_type = 'purchase';
@@ -6,8 +6,8 @@
 use anyhow::Result;
 use pipeaudit::ch::ChClient;
 use pipeaudit::collectors::{
-    DiskCollector, MergesCollector, MutationsCollector, MvDagCollector, PartsCollector,
-    QueryLogCollector,
+    DiskCollector, InsertsCollector, MergesCollector, MutationsCollector, MvDagCollector,
+    PartsCollector, QueryLogCollector, QueryWindow, TableDiscovery, TableFilter, TableRef,
 };
 use pipeaudit::report::{ReportBuilder, Targets};
 use pipeaudit::rules::RuleRegistry;
@@ -21,6 +21,10 @@ fn get_client() -> ChClient {
     ChClient::new(ENDPOINT, USER, PASSWORD, DATABASE)
 }
 
+fn databases() -> Vec<String> {
+    vec![DATABASE.to_string()]
+}
+
 #[tokio::test]
 #[ignore = "requires ClickHouse"]
 async fn test_clickhouse_ping() -> Result<()> {
@@ -29,13 +33,31 @@ async fn test_clickhouse_ping() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+#[ignore = "requires ClickHouse"]
+async fn test_table_discovery() -> Result<()> {
+    let client = get_client();
+    let filter = TableFilter {
+        databases: databases(),
+        exclude: vec!["*_raw".to_string()],
+        ..Default::default()
+    };
+
+    let tables = TableDiscovery::discover(&client, &filter).await?;
+
+    assert!(tables.contains(&TableRef::new(DATABASE, "events")));
+    assert!(tables.iter().all(|t| t.database == DATABASE));
+    assert!(!tables.iter().any(|t| t.table.ends_with("_raw")));
+    Ok(())
+}
+
 #[tokio::test]
 #[ignore = "requires ClickHouse"]
 async fn test_parts_collector() -> Result<()> {
     let client = get_client();
-    let tables = vec!["events".to_string()];
+    let tables = vec![TableRef::new(DATABASE, "events")];
 
-    let metrics = PartsCollector::collect(&client, DATABASE, &tables).await?;
+    let metrics = PartsCollector::collect(&client, &tables).await?;
 
     assert!(
         !metrics.is_empty(),
@@ -59,10 +81,10 @@ async fn test_parts_collector() -> Result<()> {
 #[ignore = "requires ClickHouse"]
 async fn test_merges_collector() -> Result<()> {
     let client = get_client();
-    let tables = vec!["events".to_string()];
+    let tables = vec![TableRef::new(DATABASE, "events")];
 
     // Merges may or may not be running, just verify no errors
-    let metrics = MergesCollector::collect(&client, DATABASE, &tables).await?;
+    let metrics = MergesCollector::collect(&client, &tables).await?;
 
     // Can be empty if no merges are in progress, which is fine
     let _ = metrics; // Silence unused warning
@@ -74,9 +96,9 @@ async fn test_merges_collector() -> Result<()> {
 #[ignore = "requires ClickHouse"]
 async fn test_mutations_collector() -> Result<()> {
     let client = get_client();
-    let tables = vec!["events".to_string()];
+    let tables = vec![TableRef::new(DATABASE, "events")];
 
-    let metrics = MutationsCollector::collect(&client, DATABASE, &tables).await?;
+    let metrics = MutationsCollector::collect(&client, &tables).await?;
 
     // Can have mutations or not - just checking the query works
     let _ = metrics; // Silence unused warning
@@ -112,7 +134,8 @@ async fn test_query_log_collector() -> Result<()> {
     let client = get_client();
 
     // Query log may or may not have entries depending on ClickHouse config
-    let metrics = QueryLogCollector::collect(&client, DATABASE).await?;
+    let metrics =
+        QueryLogCollector::collect(&client, &databases(), &QueryWindow::default()).await?;
 
     // Just verify query runs without error - metrics is a Vec
     let _ = metrics; // May be empty if no queries recorded yet
@@ -120,12 +143,35 @@ async fn test_query_log_collector() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+#[ignore = "requires ClickHouse"]
+async fn test_inserts_collector_insert_select() -> Result<()> {
+    let client = get_client();
+    let tables = vec![
+        TableRef::new(DATABASE, "events"),
+        TableRef::new(DATABASE, "events_copy"),
+    ];
+
+    let metrics = InsertsCollector::collect(&client, &tables, 86400).await?;
+
+    // The INSERT ... SELECT into events_copy read events but did not insert into it
+    for m in &metrics {
+        assert_eq!(
+            m.inserts, 1,
+            "{}.{} should have one insert",
+            m.database, m.table
+        );
+    }
+
+    Ok(())
+}
+
 #[tokio::test]
 #[ignore = "requires ClickHouse"]
 async fn test_mv_dag_collector() -> Result<()> {
     let client = get_client();
 
-    let dag = MvDagCollector::collect(&client, DATABASE).await?;
+    let dag = MvDagCollector::collect(&client, &databases()).await?;
 
     // We have events_raw -> events_daily_mv
     assert!(dag.total_tables >= 1, "Should have at least one table");
@@ -142,40 +188,55 @@ async fn test_mv_dag_collector() -> Result<()> {
 #[ignore = "requires ClickHouse"]
 async fn test_full_audit_flow() -> Result<()> {
     let client = get_client();
-    let tables = vec!["events".to_string(), "events_raw".to_string()];
+    let tables = vec![
+        TableRef::new(DATABASE, "events"),
+        TableRef::new(DATABASE, "events_raw"),
+    ];
 
     // 1. Initialize report builder
     let targets = Targets {
         endpoint: ENDPOINT.to_string(),
         database: DATABASE.to_string(),
-        tables: tables.clone(),
+        tables: tables.iter().map(|t| t.to_string()).collect(),
     };
     let mut builder = ReportBuilder::new(targets);
 
     // 2. Run all collectors
-    let parts = PartsCollector::collect(&client, DATABASE, &tables).await?;
-    let parts_sql = PartsCollector::sql(DATABASE, &tables);
-    builder.with_parts(parts, &parts_sql);
+    let tracked = client.tracked();
+    let parts = PartsCollector::collect(&tracked, &tables).await?;
+    let parts_sql = PartsCollector::sql(&tables);
+    builder.with_parts(parts, &parts_sql, &tracked.stats());
 
-    let merges = MergesCollector::collect(&client, DATABASE, &tables).await?;
-    let merges_sql = MergesCollector::sql(DATABASE, &tables);
-    builder.with_merges(merges, &merges_sql);
+    let tracked = client.tracked();
 
-    let mutations = MutationsCollector::collect(&client, DATABASE, &tables).await?;
-    let mutations_sql = MutationsCollector::sql(DATABASE, &tables);
-    builder.with_mutations(mutations, &mutations_sql);
+    let merges = MergesCollector::collect(&tracked, &tables).await?;
+    let merges_sql = MergesCollector::sql(&tables);
+    builder.with_merges(merges, &merges_sql, &tracked.stats());
 
-    let disk = DiskCollector::collect(&client).await?;
+    let tracked = client.tracked();
+
+    let mutations = MutationsCollector::collect(&tracked, &tables).await?;
+    let mutations_sql = MutationsCollector::sql(&tables);
+    builder.with_mutations(mutations, &mutations_sql, &tracked.stats());
+
+    let tracked = client.tracked();
+
+    let disk = DiskCollector::collect(&tracked).await?;
     let disk_sql = DiskCollector::sql();
-    builder.with_disk(disk, &disk_sql);
+    builder.with_disk(disk, &disk_sql, &tracked.stats());
 
-    let queries = QueryLogCollector::collect(&client, DATABASE).await?;
-    let queries_sql = QueryLogCollector::sql(DATABASE);
-    builder.with_queries(queries, &queries_sql);
+    let tracked = client.tracked();
 
-    let mv_dag = MvDagCollector::collect(&client, DATABASE).await?;
-    let mv_dag_sql = MvDagCollector::sql(DATABASE);
-    builder.with_mv_dag(mv_dag, &mv_dag_sql);
+    let queries =
+        QueryLogCollector::collect(&tracked, &databases(), &QueryWindow::default()).await?;
+    let queries_sql = QueryLogCollector::sql(&databases(), &QueryWindow::default());
+    builder.with_queries(queries, &queries_sql, &tracked.stats());
+
+    let tracked = client.tracked();
+
+    let mv_dag = MvDagCollector::collect(&tracked, &databases()).await?;
+    let mv_dag_sql = MvDagCollector::sql(&databases());
+    builder.with_mv_dag(mv_dag, &mv_dag_sql, &tracked.stats());
 
     // 3. Run rules
     let registry = RuleRegistry::with_default_rules();
@@ -192,6 +253,13 @@ async fn test_full_audit_flow() -> Result<()> {
     );
     assert_eq!(report.targets.database, DATABASE);
     assert!(!report.evidence.is_empty(), "Report should have evidence");
+    let parts_evidence = report
+        .evidence
+        .iter()
+        .find(|e| e.source == "system.parts")
+        .expect("Report should have parts evidence");
+    assert_eq!(parts_evidence.query_ids.len(), 2); // tables + partitions
+    assert!(parts_evidence.row_count > 0);
 
     // 6. Verify sections have data
     assert!(
@@ -220,19 +288,20 @@ async fn test_full_audit_flow() -> Result<()> {
 #[ignore = "requires ClickHouse"]
 async fn test_healthy_table_no_critical_findings() -> Result<()> {
     let client = get_client();
-    let tables = vec!["events".to_string()];
+    let tables = vec![TableRef::new(DATABASE, "events")];
 
     let targets = Targets {
         endpoint: ENDPOINT.to_string(),
         database: DATABASE.to_string(),
-        tables: tables.clone(),
+        tables: tables.iter().map(|t| t.to_string()).collect(),
     };
     let mut builder = ReportBuilder::new(targets);
 
     // Collect only parts (should be healthy with ~10 parts for test data)
-    let parts = PartsCollector::collect(&client, DATABASE, &tables).await?;
-    let parts_sql = PartsCollector::sql(DATABASE, &tables);
-    builder.with_parts(parts, &parts_sql);
+    let tracked = client.tracked();
+    let parts = PartsCollector::collect(&tracked, &tables).await?;
+    let parts_sql = PartsCollector::sql(&tables);
+    builder.with_parts(parts, &parts_sql, &tracked.stats());
 
     let registry = RuleRegistry::with_default_rules();
     builder.run_rules(&registry);